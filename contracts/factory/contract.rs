@@ -1,17 +1,27 @@
-use crate::state::{DETAILS, VOLUMES, MEDIA, MARKETS, ACTIVE_MARKETS, STATISTICS, COMPLETED_MARKETS, ADMINS_MAP, INCENTIVES};
+use crate::state::{DETAILS, STATISTICS, ADMINS_MAP, INCENTIVES, BALANCES, NONCES, SIGNING_KEYS, TRADING_GRANTS, PENDING_CONFIG, ROLES, SUPER_ADMINS, PENDING_SUPER_ADMIN, PAUSED, COLLATERALS, UNRECORDED_COLLATERALS, FEE_REVENUE, REFERRERS, REFERRAL_SHARE, POINTS_CONFIG, EPOCH_POINTS, LIQUIDITY_REWARDS, ACCOUNT_STATS, ALL_TIME};
 
-use packages::factory::{ExecuteMsg, InstantiateMsg, QueryMsg, MarketInfo, MarketList, Details, Statistics};
+use packages::factory::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, Details, Statistics, TradingGrant, PendingConfig, PendingSuperAdmin, Role, PointsConfig, LiquidityRewards, AccountStats};
 
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
     to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response,
-    StdError, StdResult, WasmQuery, Uint128, Addr, QueryRequest
+    StdResult, Storage, Uint128, Addr, Order
 };
 
-use packages::market::{Data, Quote, QueryMsg as QueryMarketMsg, Information, Shares};
+use crate::execute::{execute_msg, Payment};
 
-use crate::execute::execute_msg;
+use crate::incentives::incentives_msg;
+
+use crate::query::query_msg;
+
+use packages::collateral::{Collateral, CollateralConfig, DEFAULT_DECIMALS, DEFAULT_MIN_LIQUIDITY};
+use crate::ContractError;
+
+use cw2::{get_contract_version, set_contract_version, ContractVersion};
+
+const CONTRACT_NAME: &str = concat!("crates.io:", env!("CARGO_PKG_NAME"));
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -19,9 +29,10 @@ pub fn instantiate(
     _env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> { 
+) -> Result<Response, ContractError> {
     
     deps.api.addr_validate(msg.fees_address.as_str())?;
+    execute_msg::validate_collateral(deps.api, &msg.usdc)?;
     
     let details: Details = Details {
         usdc: msg.usdc,
@@ -33,19 +44,135 @@ pub fn instantiate(
         total_pools: Uint128::from(0u128),
         unique_wallets: Uint128::from(0u128),
         active_events: Uint128::from(0u128),
-        completed_events: Uint128::from(0u128)
+        completed_events: Uint128::from(0u128),
+        fees_collected: Uint128::from(0u128)
     };
     STATISTICS.save(deps.storage, &statistics)?;
+    whitelist_default_collateral(deps.storage, &details.usdc)?;
     DETAILS.save(deps.storage, &details)?;
-    ADMINS_MAP.save(deps.storage, info.sender, &true)?;
+    ROLES.save(deps.storage, info.sender, &vec![Role::SuperAdmin])?;
+    SUPER_ADMINS.save(deps.storage, &1u32)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     Ok(Response::new())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+
+    // Factories deployed before versioning have no stored version
+    if let Ok(stored) = get_contract_version(deps.storage) {
+        ensure_upgrade(&stored)?;
+    }
+
+    // Factories deployed before roles existed only have admin flags,
+    // each of which had every permission
+    if !SUPER_ADMINS.exists(deps.storage) {
+
+        let admins = ADMINS_MAP
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<(Addr, bool)>>>()?;
+
+        SUPER_ADMINS.save(deps.storage, &0u32)?;
+
+        let mut seeded = admins.iter()
+            .filter(|(_, is_admin)| *is_admin)
+            .map(|(account, _)| account.clone())
+            .collect::<Vec<Addr>>();
+
+        if seeded.is_empty() {
+            if let Some(super_admin) = msg.super_admin {
+                seeded.push(deps.api.addr_validate(super_admin.as_str())?);
+            }
+        }
+
+        for account in seeded {
+            execute_msg::add_role(deps.storage, &account, Role::SuperAdmin)?;
+            for role in Role::OPERATIONAL {
+                execute_msg::add_role(deps.storage, &account, role)?;
+            }
+        }
+
+        for (account, _) in admins {
+            ADMINS_MAP.remove(deps.storage, account);
+        }
+
+        if SUPER_ADMINS.load(deps.storage)? == 0 {
+            return Err(ContractError::NoSuperAdmin {});
+        }
+
+    }
+
+    if let Some(market_code_id) = msg.market_code_id {
+        if market_code_id == 0 {
+            return Err(ContractError::InvalidCodeId {});
+        }
+        DETAILS.update(deps.storage, |mut details| -> StdResult<_> {
+            details.market_code_id = market_code_id;
+            Ok(details)
+        })?;
+    }
+
+    // Factories deployed before the whitelist only know their single collateral,
+    // and none of their markets has its collateral recorded
+    if COLLATERALS.is_empty(deps.storage) {
+        let details = DETAILS.load(deps.storage)?;
+        whitelist_default_collateral(deps.storage, &details.usdc)?;
+        let statistics = STATISTICS.load(deps.storage)?;
+        UNRECORDED_COLLATERALS.save(deps.storage, &statistics.total_pools.u128())?;
+    }
+
+    // Markets created before the category and search indexes are indexed a
+    // page at a time by `MigrateMarkets`, never here
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("version", CONTRACT_VERSION))
+
+}
+
+/// Whitelists `collateral` with the 6 decimals and 10 USDC minimum
+/// liquidity markets used before collaterals were configurable
+fn whitelist_default_collateral(storage: &mut dyn Storage, collateral: &Collateral) -> StdResult<()> {
+
+    COLLATERALS.save(storage, collateral.to_string(), &CollateralConfig {
+        collateral: collateral.clone(),
+        decimals: DEFAULT_DECIMALS,
+        min_liquidity: Uint128::from(DEFAULT_MIN_LIQUIDITY)
+    })
+
+}
+
+fn ensure_upgrade(stored: &ContractVersion) -> Result<(), ContractError> {
+
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidMigration { previous_contract: stored.contract.clone() });
+    }
+
+    if semver::Version::parse(&stored.version)? > semver::Version::parse(CONTRACT_VERSION)? {
+        return Err(ContractError::CannotDowngrade { previous: stored.version.clone(), current: CONTRACT_VERSION.to_string() });
+    }
+
+    Ok(())
+
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> Result<Response, ContractError> {
+
+    // Native collateral is attached as funds, CW20 collateral arrives through `Receive`
+    let payment = Payment::Native(info.funds.clone());
+
     match msg {
-        ExecuteMsg::CreateMarket { title, description, end_date, categories, media } => {
-            execute_msg::create_market(deps, env, info, title, description, end_date, categories, media)
+        ExecuteMsg::CreateMarket { title, description, end_date, categories, media, collateral } => {
+            execute_msg::create_market(deps, env, info, title, description, end_date, categories, media, collateral)
+        },
+        ExecuteMsg::AddCategory { category } => {
+            execute_msg::add_category(deps, env, info, category)
+        },
+        ExecuteMsg::RemoveCategory { category } => {
+            execute_msg::remove_category(deps, env, info, category)
         },
         ExecuteMsg::RecordStats { amount, account, stat_type, data } => {
             execute_msg::record_stats(deps, env, info, amount, account, stat_type, data)
@@ -56,6 +183,24 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
         ExecuteMsg::RemoveAdmin { account } => {
             execute_msg::remove_admin(deps, env, info, account)
         }
+        ExecuteMsg::GrantRole { account, role } => {
+            execute_msg::grant_role(deps, env, info, account, role)
+        },
+        ExecuteMsg::RevokeRole { account, role } => {
+            execute_msg::revoke_role(deps, env, info, account, role)
+        },
+        ExecuteMsg::TransferSuperAdmin { account } => {
+            execute_msg::transfer_super_admin(deps, env, info, account)
+        },
+        ExecuteMsg::AcceptSuperAdmin {} => {
+            execute_msg::accept_super_admin(deps, env, info)
+        },
+        ExecuteMsg::Pause {} => {
+            execute_msg::set_paused(deps, env, info, true)
+        },
+        ExecuteMsg::Unpause {} => {
+            execute_msg::set_paused(deps, env, info, false)
+        },
         ExecuteMsg::InitializeLiquidity {
             market,
             yes_price,
@@ -64,6 +209,7 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             deps,
             env,
             info,
+            payment,
             market,
             yes_price,
             liquidity
@@ -71,221 +217,197 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
         ExecuteMsg::AddLiquidity {
             market,
             amount
-        } => execute_msg::add_liquidity(deps, env, info, market, amount),
+        } => execute_msg::add_liquidity(deps, env, info, payment, market, amount),
 
         ExecuteMsg::RemoveLiquidity { market, shares } => {
             execute_msg::remove_liquidity(deps, env, info, market, shares)
         },
-        ExecuteMsg::Claim { market, variant } => {
-            execute_msg::claim(deps, env, info, market, variant)
+        ExecuteMsg::Claim { market, variant, on_behalf_of, use_balance } => {
+            execute_msg::claim(deps, env, info, market, variant, on_behalf_of, use_balance)
         },
         ExecuteMsg::ResolveMarket { market, variant, market_index } => {
             execute_msg::resolve_market(deps, env, info, market, variant, market_index)
         },
-        ExecuteMsg::PlaceOrder { market, variant, buy_or_sell, amount } => {
-            execute_msg::place_order(deps, env, info, market, variant, buy_or_sell, amount)
+        ExecuteMsg::ProposeResolution { market, variant } => {
+            execute_msg::propose_resolution(deps, env, info, market, variant)
+        },
+        ExecuteMsg::FinalizeMarket { market } => {
+            execute_msg::finalize_market(deps, env, info, market)
+        },
+        ExecuteMsg::CancelMarket { market, market_index } => {
+            execute_msg::cancel_market(deps, env, info, market, market_index)
+        },
+        ExecuteMsg::PlaceOrder { market, variant, buy_or_sell, amount, min_out, on_behalf_of, use_balance, referrer } => {
+            execute_msg::place_order(deps, env, info, payment, market, variant, buy_or_sell, amount, min_out, on_behalf_of, use_balance, referrer)
+        },
+        ExecuteMsg::RegisterSigningKey { pubkey } => {
+            execute_msg::register_signing_key(deps, env, info, pubkey)
+        },
+        ExecuteMsg::Deposit {} => {
+            execute_msg::deposit(deps, env, info, payment)
+        },
+        ExecuteMsg::Withdraw { amount, collateral } => {
+            execute_msg::withdraw(deps, env, info, amount, collateral)
+        },
+        ExecuteMsg::SubmitSignedOrder { signer, order, signature } => {
+            execute_msg::submit_signed_order(deps, env, info, signer, order, signature)
+        },
+        ExecuteMsg::GrantTrading { operator, expires, max_notional, markets } => {
+            execute_msg::grant_trading(deps, env, info, operator, expires, max_notional, markets)
+        },
+        ExecuteMsg::RevokeTrading { operator } => {
+            execute_msg::revoke_trading(deps, env, info, operator)
+        },
+        ExecuteMsg::BatchPlaceOrder { orders, atomic, use_balance } => {
+            execute_msg::batch_place_order(deps, env, info, orders, atomic, use_balance)
+        },
+        ExecuteMsg::CreditBalance { account } => {
+            execute_msg::credit_balance(deps, env, info, payment, account)
+        },
+        ExecuteMsg::MigrateMarkets { new_code_id, start_after, limit } => {
+            execute_msg::migrate_markets(deps, env, info, new_code_id, start_after, limit)
+        },
+        ExecuteMsg::UpdateConfig { fees_address, market_code_id, usdc } => {
+            execute_msg::update_config(deps, env, info, fees_address, market_code_id, usdc)
+        },
+        ExecuteMsg::ExecuteConfigUpdate {} => {
+            execute_msg::execute_config_update(deps, env, info)
+        },
+        ExecuteMsg::CancelConfigUpdate {} => {
+            execute_msg::cancel_config_update(deps, env, info)
+        },
+        ExecuteMsg::SetCollateral { collateral, decimals, min_liquidity } => {
+            execute_msg::set_collateral(deps, env, info, collateral, decimals, min_liquidity)
+        },
+        ExecuteMsg::RemoveCollateral { collateral } => {
+            execute_msg::remove_collateral(deps, env, info, collateral)
+        },
+        ExecuteMsg::CollectFees { markets } => {
+            execute_msg::collect_fees(deps, env, info, markets)
+        },
+        ExecuteMsg::CreditReferral { referrer } => {
+            execute_msg::credit_referral(deps, env, info, payment, referrer)
+        },
+        ExecuteMsg::ClaimReferralEarnings { collateral } => {
+            execute_msg::claim_referral_earnings(deps, env, info, collateral)
+        },
+        ExecuteMsg::SetReferralShare { share_bps } => {
+            execute_msg::set_referral_share(deps, env, info, share_bps)
+        },
+        ExecuteMsg::SetPointsConfig { per_volume, per_lp_day, per_win, epoch_cap } => {
+            execute_msg::set_points_config(deps, env, info, per_volume, per_lp_day, per_win, epoch_cap)
+        },
+        ExecuteMsg::AccrueLiquidityPoints { markets } => {
+            execute_msg::accrue_liquidity(deps, env, info, markets)
+        },
+        ExecuteMsg::FundEpochRewards { epoch } => {
+            execute_msg::fund_epoch_rewards(deps, env, info, payment, epoch)
+        },
+        ExecuteMsg::ClaimEpochRewards { epochs } => {
+            execute_msg::claim_epoch_rewards(deps, env, info, epochs)
+        },
+        ExecuteMsg::FundLiquidityRewards { market, reward_per_second } => {
+            incentives_msg::fund_liquidity_rewards(deps, env, info, payment, market, reward_per_second)
+        },
+        ExecuteMsg::ClaimLiquidityRewards { markets } => {
+            incentives_msg::claim_liquidity_rewards(deps, env, info, markets)
+        },
+        ExecuteMsg::Receive(wrapper) => {
+            execute_msg::receive(deps, env, info, wrapper)
         }
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetMarketInfo { contract_address, account } => to_json_binary(&query::get_market_info(deps, _env, contract_address, account)?),
-        QueryMsg::FetchMarkets { page, items_per_page, account, market_type } => to_json_binary(&query::fetch_markets(deps, _env, page, items_per_page, account, market_type)?),
-        QueryMsg::FeesAddress {} => to_json_binary(&query::fees_address(deps, _env)?),
-        QueryMsg::Quote { market, variant, buy_or_sell, amount } => to_json_binary(&query::quote(deps, _env, market, variant, buy_or_sell, amount)?),
-        QueryMsg::IsAdmin { account } => to_json_binary(&query::is_admin(deps, _env, account)?),
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    let response = match msg {
+        QueryMsg::GetMarketInfo { contract_address, account } => to_json_binary(&query_msg::get_market_info(deps, _env, contract_address, account)?),
+        QueryMsg::FetchMarkets { page, items_per_page, account, market_type } => to_json_binary(&query_msg::fetch_markets(deps, _env, page, items_per_page, account, market_type)?),
+        QueryMsg::FetchMarketsByStatus { status, account, start_after, limit } => to_json_binary(&query_msg::fetch_markets_by_status(deps, _env, status, account, start_after, limit)?),
+        QueryMsg::SearchMarkets { filter, sort_by, descending, start_after, limit } => to_json_binary(&query_msg::search_markets(deps, _env, filter, sort_by, descending, start_after, limit)?),
+        QueryMsg::FetchMarketsByCategory { category, status, start_after, limit } => to_json_binary(&query_msg::fetch_markets_by_category(deps, _env, category, status, start_after, limit)?),
+        QueryMsg::ListCategories { start_after, limit } => to_json_binary(&query_msg::list_categories(deps, _env, start_after, limit)?),
+        QueryMsg::FeesAddress {} => to_json_binary(&query_msg::fees_address(deps, _env)?),
+        QueryMsg::GetReferrer { account } => {
+            to_json_binary::<Option<Addr>>(&REFERRERS.may_load(deps.storage, account)?)
+        },
+        QueryMsg::GetReferralStats { referrer } => to_json_binary(&query_msg::get_referral_stats(deps, _env, referrer)?),
+        QueryMsg::ListReferees { referrer, start_after, limit } => to_json_binary(&query_msg::list_referees(deps, _env, referrer, start_after, limit)?),
+        QueryMsg::ReferralShare {} => {
+            to_json_binary::<u16>(&REFERRAL_SHARE.may_load(deps.storage)?.unwrap_or_default())
+        },
+        QueryMsg::Quote { market, variant, buy_or_sell, amount } => to_json_binary(&query_msg::quote(deps, _env, market, variant, buy_or_sell, amount)?),
+        QueryMsg::IsAdmin { account } => to_json_binary(&query_msg::is_admin(deps, _env, account)?),
+        QueryMsg::HasRole { account, role } => to_json_binary(&execute_msg::has_role(deps.storage, &account, role)?),
+        QueryMsg::ListRoles { start_after, limit } => to_json_binary(&query_msg::list_roles(deps, _env, start_after, limit)?),
+        QueryMsg::PendingSuperAdmin {} => {
+            to_json_binary::<Option<PendingSuperAdmin>>(&PENDING_SUPER_ADMIN.may_load(deps.storage)?)
+        },
+        QueryMsg::IsPaused {} => {
+            to_json_binary::<bool>(&PAUSED.may_load(deps.storage)?.unwrap_or(false))
+        },
         QueryMsg::GetIncentives { account } => {
             to_json_binary::<u64>(&INCENTIVES.load(deps.storage, account)?)
         },
+        QueryMsg::PointsConfig {} => {
+            to_json_binary::<Option<PointsConfig>>(&POINTS_CONFIG.may_load(deps.storage)?)
+        },
+        QueryMsg::GetPoints { account } => to_json_binary(&query_msg::get_points(deps, _env, account)?),
+        QueryMsg::GetEpochPoints { epoch, account } => {
+            to_json_binary::<Uint128>(&EPOCH_POINTS.may_load(deps.storage, (epoch, account))?.unwrap_or_default())
+        },
+        QueryMsg::GetEpoch { epoch } => to_json_binary(&query_msg::get_epoch(deps, _env, epoch)?),
+        QueryMsg::GetEpochRewards { epoch, account } => {
+            let unclaimed = execute_msg::unclaimed_epoch_rewards(deps.storage, epoch, &account)?
+                .map(|(_, unclaimed)| unclaimed)
+                .unwrap_or_default();
+            to_json_binary::<Uint128>(&unclaimed)
+        },
+        QueryMsg::GetAccountStats { account, epoch } => {
+            to_json_binary::<AccountStats>(&ACCOUNT_STATS.may_load(deps.storage, (epoch.unwrap_or(ALL_TIME), account))?.unwrap_or_default())
+        },
+        QueryMsg::Leaderboard { metric, epoch, start_after, limit } => to_json_binary(&query_msg::leaderboard(deps, _env, metric, epoch, start_after, limit)?),
+        QueryMsg::GetLiquidityRewards { market } => {
+            to_json_binary::<Option<LiquidityRewards>>(&LIQUIDITY_REWARDS.may_load(deps.storage, market)?)
+        },
+        QueryMsg::PendingLiquidityRewards { market, account } => {
+            to_json_binary::<Uint128>(&incentives_msg::pending_liquidity_rewards(deps, &_env, market, account)?)
+        },
         QueryMsg::Details {} => {
             to_json_binary::<Details>(&DETAILS.load(deps.storage)?)
         },
         QueryMsg::GetStatistics {} => {
             to_json_binary::<Statistics>(&STATISTICS.load(deps.storage)?)
-        }
-    }
-}
-
-pub mod query {
-
-    use super::*;
-
-    pub fn fees_address(_deps: Deps, _env: Env) -> StdResult<Addr> {
-
-        let details = DETAILS.load(_deps.storage)?;
-
-        let fees_address = details.fees_address;
-
-        Ok(fees_address)
-
-    }
-
-    pub fn is_admin(_deps: Deps, _env: Env, account: Addr) -> StdResult<bool> {
-
-        let is_admin = ADMINS_MAP.load(_deps.storage, account).unwrap_or(false);
-
-        Ok(is_admin)
-
-    }
-
-    pub fn quote(_deps: Deps, _env: Env, market: Addr, variant: Uint128, buy_or_sell: Uint128, amount: Uint128) -> StdResult<Quote> {
-
-        let msg = QueryMarketMsg::Quote {
-            variant,
-            buy_or_sell,
-            amount
-        };
-        
-        let query_msg = QueryRequest::Wasm(WasmQuery::Smart {
-            contract_addr: market.to_string(),
-            msg: to_json_binary(&msg)?,
-        });
-
-        let quote: Quote = _deps.querier.query(&query_msg)?;
-        
-        Ok(quote)
-
-    }
-
-    pub fn get_market_info(_deps: Deps, _env: Env, contract_address: Addr, account: Addr) -> StdResult<MarketInfo> {
-
-        let msg = QueryMarketMsg::GetInfo {
-            account
-        };
-        
-        let query_msg = QueryRequest::Wasm(WasmQuery::Smart {
-            contract_addr: contract_address.to_string(),
-            msg: to_json_binary(&msg)?,
-        });
-
-        let data: Data = _deps.querier.query(&query_msg)?;
-
-        let information: Information = data.information;
-        let shares: Shares = data.shares;
-
-        let volume: Uint128 = VOLUMES.load(_deps.storage, contract_address.clone()).unwrap_or(Uint128::from(0u128));
-        let media_: Vec<String> = MEDIA.load(_deps.storage, contract_address.clone()).unwrap_or(vec![String::from(""), String::from("")]);
-
-        let media: [String; 2] = media_.try_into().unwrap();
-
-        let market_info: MarketInfo = MarketInfo {
-            information,
-            shares,
-            volume,
-            media
-        };
-
-        Ok(market_info)
-
-    }
-
-    pub fn fetch_markets(_deps: Deps, _env: Env, page_: u128, items_per_page: u128, account: Addr, market_type: u128) -> StdResult<MarketList> {
-
-        if page_ == 0u128 {
-            return Err(StdError::generic_err("Page must be greater than 0"));
-        }
-        if items_per_page == 0u128 {
-            return Err(StdError::generic_err("Items per page must be greater than 0"));
-        }
-
-        let statistics = STATISTICS.load(_deps.storage)?;
-
-        let total_markets;
-        
-        if market_type == 0u128 {
-            total_markets = statistics.total_pools;
-        }
-        else if market_type == 1u128 {
-            total_markets = statistics.active_events;
-        }
-        else {
-            total_markets = statistics.completed_events;
-        }
-
-        let number_of_pages = if total_markets % Uint128::from(items_per_page) == Uint128::from(0u128) {
-            total_markets / Uint128::from(items_per_page)
-        }
-        else {
-            (total_markets / Uint128::from(items_per_page)) + Uint128::from(1u128)
-        };
-
-        let page = Uint128::from(page_);
-
-        if page > number_of_pages {
-            return Err(StdError::generic_err("Page is out of bounds"));
-        }
-
-        if total_markets == Uint128::from(0u128) {
-            return Err(StdError::generic_err("No market exists at the moment"));
-        }
-
-        let start_index = total_markets - ((page - Uint128::from(1u128)) * Uint128::from(items_per_page));
-
-        let end_index = if start_index < Uint128::from(items_per_page) {
-            Uint128::from(1u128)
-        }
-        else {
-            (start_index - Uint128::from(items_per_page)) + Uint128::from(1u128)
-        };
-
-        let number_of_items = (start_index - end_index) + Uint128::from(1u128);
-
-        if number_of_items == Uint128::from(0u128) {
-            return Err(StdError::generic_err("No market found for this page"));
-        }
-
-        let mut paginated_markets: Vec<Information> = vec![];
-        let mut contract_addresses: Vec<Addr> = vec![];
-        let mut indexes: Vec<u128> = vec![];
-
-        let mut current_index = start_index;
-
-        while current_index >= end_index {
-
-            let index = current_index.u128();
-
-            let market_at;
-            
-            if market_type == 0u128 {
-                market_at = MARKETS.load(_deps.storage, index)?;
-            }
-            else if market_type == 1u128 {
-                market_at = ACTIVE_MARKETS.load(_deps.storage, index)?;
-            }
-            else {
-                market_at = COMPLETED_MARKETS.load(_deps.storage, index)?;
-            }
-            
-            let msg = QueryMarketMsg::GetInfo {
-                account: account.clone()
+        },
+        QueryMsg::GetStatisticsHistory { from_day, to_day } => to_json_binary(&query_msg::get_statistics_history(deps, _env, from_day, to_day)?),
+        QueryMsg::ListCollaterals { start_after, limit } => to_json_binary(&query_msg::list_collaterals(deps, _env, start_after, limit)?),
+        QueryMsg::GetCollateralVolumes { start_after, limit } => to_json_binary(&query_msg::get_collateral_volumes(deps, _env, start_after, limit)?),
+        QueryMsg::GetFeeRevenue { market } => {
+            to_json_binary::<Uint128>(&FEE_REVENUE.may_load(deps.storage, market)?.unwrap_or_default())
+        },
+        QueryMsg::PendingConfig {} => {
+            to_json_binary::<Option<PendingConfig>>(&PENDING_CONFIG.may_load(deps.storage)?)
+        },
+        QueryMsg::GetBalance { account, collateral } => {
+            let collateral: Collateral = match collateral {
+                Some(collateral) => collateral,
+                None => DETAILS.load(deps.storage)?.usdc
             };
+            to_json_binary::<Uint128>(&BALANCES.load(deps.storage, (account, collateral.to_string())).unwrap_or(Uint128::from(0u128)))
+        },
+        QueryMsg::GetNonce { account } => {
+            to_json_binary::<u64>(&NONCES.load(deps.storage, account).unwrap_or(0u64))
+        },
+        QueryMsg::GetSigningKey { account } => {
+            to_json_binary::<Option<Binary>>(&SIGNING_KEYS.may_load(deps.storage, account)?)
+        },
+        QueryMsg::GetTradingGrant { granter, operator } => {
+            to_json_binary::<Option<TradingGrant>>(&TRADING_GRANTS.may_load(deps.storage, (granter, operator))?)
+        },
+        QueryMsg::ListTradingGrants { granter, start_after, limit } => to_json_binary(&query_msg::list_trading_grants(deps, _env, granter, start_after, limit)?),
+        QueryMsg::GetAccountTrades { account, start_after, limit } => to_json_binary(&query_msg::get_account_trades(deps, _env, account, start_after, limit)?),
+        QueryMsg::GetPortfolio { account, start_after, limit } => to_json_binary(&query_msg::get_portfolio(deps, _env, account, start_after, limit)?)
+    };
 
-            let query_msg = &QueryRequest::Wasm(WasmQuery::Smart {
-                contract_addr: market_at.to_string(),
-                msg: to_json_binary(&msg)?,
-            });
-
-            let data: Data = _deps.querier.query(query_msg)?;
-
-            let information: Information = data.information;
-
-            paginated_markets.push(information);
-            contract_addresses.push(market_at);
-            indexes.push(index);
-
-            current_index = Uint128::from(current_index.u128() - 1u128);
-
-        }
-
-        let market_list: MarketList = MarketList {
-            information: paginated_markets,
-            contracts: contract_addresses,
-            indexes
-        };
-
-        Ok(market_list)
-
-    }
-}
\ No newline at end of file
+    Ok(response?)
+}
@@ -1,4 +1,6 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
+use cw0::PaymentError;
+use packages::factory::Role;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,8 +8,192 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
+    #[error("{0}")]
+    Semver(#[from] semver::Error),
+
     #[error("Unauthorized")]
     Unauthorized {},
-    // Add any other custom errors you like here.
-    // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
+
+    #[error("Missing role {role:?}")]
+    MissingRole { role: Role },
+
+    #[error("Account does not hold role {role:?}")]
+    RoleNotGranted { role: Role },
+
+    #[error("Cannot revoke the last super admin")]
+    LastSuperAdmin {},
+
+    #[error("No super admin transfer is pending")]
+    NoPendingSuperAdmin {},
+
+    #[error("Migration would leave the factory without a super admin")]
+    NoSuperAdmin {},
+
+    #[error("Factory is paused")]
+    Paused {},
+
+    #[error("Cannot migrate from {previous_contract}")]
+    InvalidMigration { previous_contract: String },
+
+    #[error("Cannot migrate from version {previous} to older version {current}")]
+    CannotDowngrade { previous: String, current: String },
+
+    #[error("Incorrect market")]
+    UnknownMarket {},
+
+    #[error("Market index does not match intended market")]
+    MarketIndexMismatch {},
+
+    #[error("End date must be a date in the future")]
+    InvalidEndDate {},
+
+    #[error("Invalid funds: expected {expected}, got {got}")]
+    InvalidFunds { expected: Uint128, got: Uint128 },
+
+    #[error("Funds must not be attached to sells or when trading from balance")]
+    FundsNotAllowed {},
+
+    #[error("Amount must be greater than 0")]
+    ZeroAmount {},
+
+    #[error("Insufficient balance: have {have}, want {want}")]
+    InsufficientBalance { have: Uint128, want: Uint128 },
+
+    #[error("Batch must contain at least one order")]
+    EmptyBatch {},
+
+    #[error("Batch must not contain more than {max} orders")]
+    BatchTooLarge { max: usize },
+
+    #[error("Invalid secp256k1 public key")]
+    InvalidPublicKey {},
+
+    #[error("Signer has not registered a signing key")]
+    NoSigningKey {},
+
+    #[error("Signed order has expired")]
+    OrderExpired {},
+
+    #[error("Invalid nonce: expected {expected}, got {got}")]
+    InvalidNonce { expected: u64, got: u64 },
+
+    #[error("Invalid signature")]
+    InvalidSignature {},
+
+    #[error("Cannot grant trading to self")]
+    SelfGrant {},
+
+    #[error("Expiry must be a date in the future")]
+    InvalidExpiry {},
+
+    #[error("No trading grant exists")]
+    NoTradingGrant {},
+
+    #[error("Trading grant has expired")]
+    GrantExpired {},
+
+    #[error("Trading grant does not cover this market")]
+    GrantMarketNotAllowed {},
+
+    #[error("Trading grant notional limit exceeded: remaining {remaining}, want {want}")]
+    GrantLimitExceeded { remaining: Uint128, want: Uint128 },
+
+    #[error("Config update must change at least one field")]
+    EmptyConfigUpdate {},
+
+    #[error("Market code id must be greater than 0")]
+    InvalidCodeId {},
+
+    #[error("Markets can only migrate to the configured code id {expected}, got {got}")]
+    CodeIdMismatch { expected: u64, got: u64 },
+
+    #[error("Collateral denom must not be empty")]
+    InvalidDenom {},
+
+    #[error("Invalid collateral: expected {expected}")]
+    InvalidCollateral { expected: String },
+
+    #[error("Collateral {collateral} is not whitelisted")]
+    CollateralNotWhitelisted { collateral: String },
+
+    #[error("Cannot remove the default collateral")]
+    DefaultCollateral {},
+
+    #[error("{markets} markets still need their collateral recorded by MigrateMarkets")]
+    UnrecordedCollaterals { markets: u128 },
+
+    #[error("Minimum liquidity must be greater than 0")]
+    InvalidMinLiquidity {},
+
+    #[error("Collateral decimals must not exceed {max}")]
+    InvalidDecimals { max: u8 },
+
+    #[error("A config update is already pending")]
+    ConfigUpdatePending {},
+
+    #[error("No config update is pending")]
+    NoPendingConfig {},
+
+    #[error("Config update is timelocked until {executable_at}")]
+    TimelockActive { executable_at: u64 },
+
+    #[error("Cannot refer yourself")]
+    SelfReferral {},
+
+    #[error("Only the trading account itself can set its referrer")]
+    DelegatedReferral {},
+
+    #[error("Referral share must be at most 10000 basis points")]
+    InvalidReferralShare {},
+
+    #[error("No referral earnings to claim")]
+    NoReferralEarnings {},
+
+    #[error("Page must be greater than 0")]
+    ZeroPage {},
+
+    #[error("Items per page must be greater than 0")]
+    ZeroItemsPerPage {},
+
+    #[error("Page is out of bounds")]
+    PageOutOfBounds {},
+
+    #[error("No market exists at the moment")]
+    NoMarkets {},
+
+    #[error("No market found for this page")]
+    EmptyPage {},
+
+    #[error("Statistics history is limited to {max} days")]
+    HistoryTooLong { max: u64 },
+
+    #[error("Points are not configured")]
+    PointsNotConfigured {},
+
+    #[error("Epoch {epoch} has not finished")]
+    EpochNotFinished { epoch: u64 },
+
+    #[error("Nobody earned points in epoch {epoch}")]
+    EmptyEpoch { epoch: u64 },
+
+    #[error("No epoch rewards to claim")]
+    NoEpochRewards {},
+
+    #[error("Reward rate must be greater than 0")]
+    InvalidRewardRate {},
+
+    #[error("Reward budget must last at least one second")]
+    RewardBudgetTooSmall {},
+
+    #[error("No liquidity rewards to claim")]
+    NoLiquidityRewards {},
+
+    #[error("Category must not be empty")]
+    InvalidCategory {},
+
+    #[error("Category {category} is not registered")]
+    UnknownCategory { category: String },
 }
@@ -1,18 +1,65 @@
 #![allow(clippy::too_many_arguments)]
 #[cfg(not(feature = "library"))]
+use crate::state::{DETAILS, VOLUMES, MEDIA, MARKETS, ACTIVE_MARKETS, COMPLETED_MARKETS, TEMP_INFORMATION, KNOWN_MARKETS, STATISTICS, UNIQUE_WALLETS, BALANCES, NONCES, SIGNING_KEYS, TRADING_GRANTS, BATCH_ORDERS, ACCOUNT_MARKETS, MARKET_STATUS, MARKET_ENDS, STATUS_MARKETS, MARKET_COLLATERAL, UNRECORDED_COLLATERALS, FORMER_FEES_ADDRESSES, PENDING_CONFIG, ROLES, SUPER_ADMINS, PENDING_SUPER_ADMIN, PAUSED, COLLATERALS, COLLATERAL_VOLUMES, FEE_REVENUE, REFERRERS, REFEREES, REFEREE_COUNTS, REFERRAL_VOLUMES, REFERRAL_EARNINGS, REFERRAL_SHARE, POINTS_CONFIG, TOTAL_POINTS, EPOCH_POINTS, EPOCH_TOTALS, EPOCH_REWARDS, EPOCH_CLAIMS, LIQUIDITY_POSITIONS, ACCOUNT_STATS, VOLUME_RANKING, PNL_RANKING, ALL_TIME, DAILY_STATISTICS, CATEGORIES, CATEGORY_MARKETS, CATEGORY_COUNTS, MARKET_INDEXES, OWNER_MARKETS, END_DATE_MARKETS, VOLUME_MARKETS};
 
-use crate::state::{DETAILS, VOLUMES, MEDIA, MARKETS, ACTIVE_MARKETS, COMPLETED_MARKETS, ADMINS_MAP, TEMP_INFORMATION, KNOWN_MARKETS, STATISTICS, UNIQUE_WALLETS, INCENTIVES};
+use packages::factory::{Statistics, DailyStatistics, TempInformation, SignedOrder, SignedOrderPayload, TradingGrant, BatchOrder, PendingBatchOrder, PendingConfig, PendingSuperAdmin, ReceiveMsg, Role, CollateralVolume, ReferralEarnings, PointsConfig, EpochReward, LiquidityPosition};
 
-use packages::factory::{Statistics, TempInformation};
+use packages::collateral::{Collateral, CollateralConfig, DEFAULT_DECIMALS, MAX_DECIMALS};
 
 use cosmwasm_std::{
-    entry_point, to_json_binary, CosmosMsg, DepsMut, Env, MessageInfo, Reply, ReplyOn, Response,
-    StdError, StdResult, SubMsg, WasmMsg, Uint128, Addr, Event, Coin
+    entry_point, from_json, to_json_binary, to_json_vec, Api, CosmosMsg, DepsMut, Env, MessageInfo, Reply, ReplyOn, Response,
+    StdError, StdResult, Storage, Order, SubMsg, SubMsgResult, WasmMsg, Int128, Uint128, Addr, Event, Coin, Binary,
+    OverflowError, OverflowOperation, QuerierWrapper
 };
 
+use cw20::Cw20ReceiveMsg;
+
 use cw0::*;
 
-use packages::market::{InstantiateMsg as InstantiateMarketMsg, ExecuteMsg as ExecuteMarketMsg};
+use crate::ContractError;
+
+use crate::incentives::incentives_msg;
+
+use crate::query::{DEFAULT_LIMIT, MAX_LIMIT};
+
+use cw_storage_plus::Bound;
+
+use sha2::{Digest, Sha256};
+
+use std::collections::BTreeMap;
+
+use packages::market::{InstantiateMsg as InstantiateMarketMsg, ExecuteMsg as ExecuteMarketMsg, QueryMsg as QueryMarketMsg, MigrateMsg as MigrateMarketMsg, Data, RemoveLiquiditySimulation, MarketStatus, Outcome, Quote, Referral, Side};
+
+pub const BATCH_ORDER_REPLY_ID: u64 = 1_000;
+
+pub(crate) const MAX_BATCH_ORDERS: usize = 20;
+
+/// Points epochs last a week, counted from when points were first configured
+pub const EPOCH_LENGTH: u64 = 604_800;
+
+pub(crate) const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Delay between proposing and applying a config update
+pub const CONFIG_TIMELOCK: u64 = 86_400;
+
+/// Decimals that totals spanning several collaterals are kept in, those of USDC
+pub const STATS_DECIMALS: u8 = DEFAULT_DECIMALS;
+
+/// Collateral paid into a call, either native funds or an amount of a CW20
+/// token delivered through `Receive`
+pub enum Payment {
+    Native(Vec<Coin>),
+    Cw20 { token: Addr, amount: Uint128 }
+}
+
+impl Payment {
+    fn is_empty(&self) -> bool {
+        match self {
+            Payment::Native(funds) => funds.is_empty(),
+            Payment::Cw20 { amount, .. } => amount.is_zero()
+        }
+    }
+}
 
 pub mod execute_msg {
 
@@ -26,22 +73,29 @@ pub mod execute_msg {
         description: String,
         end_date: u64,
         categories: Vec<String>,
-        media_: [String; 2]
-    ) -> StdResult<Response> {
+        media_: [String; 2],
+        collateral: Option<Collateral>
+    ) -> Result<Response, ContractError> {
 
         let owner = info.sender;
 
         if end_date <= env.block.time.seconds() {
-            return Err(StdError::generic_err("End date must be a date in the future"));
+            return Err(ContractError::InvalidEndDate {});
         }
 
-        let status: bool = ADMINS_MAP.load(deps.storage, owner.clone()).unwrap_or(false);
+        ensure_not_paused(deps.storage)?;
+        ensure_role(deps.storage, &owner, Role::MarketCreator)?;
 
-        if !status {
-            return Err(StdError::generic_err("Only admins can create markets"));
+        if !CATEGORIES.is_empty(deps.storage) {
+            if let Some(category) = categories.iter().find(|category| !CATEGORIES.has(deps.storage, category.to_string())) {
+                return Err(ContractError::UnknownCategory { category: category.clone() });
+            }
         }
 
-        let details = DETAILS.load(deps.storage).unwrap();
+        let details = DETAILS.load(deps.storage)?;
+
+        let collateral = collateral.unwrap_or(details.usdc);
+        let config = whitelisted_collateral(deps.storage, &collateral)?;
 
         TEMP_INFORMATION.save(
             deps.storage,
@@ -58,7 +112,7 @@ pub mod execute_msg {
                 market_end: end_date,
                 categories: categories.clone(),
                 liquidity_shares: Uint128::from(0u128),
-                usdc: details.usdc.clone(),
+                usdc: collateral.clone(),
                 owner: owner.clone(),
                 resolved: false,
                 factory: env.contract.address.clone(),
@@ -77,9 +131,11 @@ pub mod execute_msg {
                 description: description.clone(),
                 end_date,
                 categories,
-                usdc: details.usdc.clone(),
+                usdc: collateral,
                 owner: owner.clone(),
-                factory: env.contract.address.clone()
+                factory: env.contract.address.clone(),
+                decimals: config.decimals,
+                min_liquidity: config.min_liquidity
             })?
         });
 
@@ -97,20 +153,19 @@ pub mod execute_msg {
         _env: Env,
         info: MessageInfo,
         account: Addr
-    )-> StdResult<Response> {
-
+    ) -> Result<Response, ContractError> {
 
         deps.api.addr_validate(account.as_str())?;
-        
-        let is_admin = ADMINS_MAP.load(deps.storage, info.sender).unwrap_or(false);
 
-        if !is_admin {
-            return Err(StdError::generic_err("Only admins can add new admins".to_string()));
-        }
+        ensure_role(deps.storage, &info.sender, Role::SuperAdmin)?;
 
-        ADMINS_MAP.save(deps.storage, account, &true)?;
+        for role in Role::OPERATIONAL {
+            add_role(deps.storage, &account, role)?;
+        }
 
-        Ok(Response::new())
+        Ok(Response::new()
+            .add_attribute("action", "add_admin")
+            .add_attribute("account", account))
         
     }
 
@@ -119,18 +174,140 @@ pub mod execute_msg {
         _env: Env,
         info: MessageInfo,
         account: Addr
-    )-> StdResult<Response> {
+    ) -> Result<Response, ContractError> {
+        
+        ensure_role(deps.storage, &info.sender, Role::SuperAdmin)?;
+
+        let mut roles = ROLES.may_load(deps.storage, account.clone())?.unwrap_or_default();
+        roles.retain(|role| *role == Role::SuperAdmin);
+        save_roles(deps.storage, &account, roles)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "remove_admin")
+            .add_attribute("account", account))
         
-        let is_admin = ADMINS_MAP.load(deps.storage, info.sender).unwrap_or(false);
+    }
 
-        if !is_admin {
-            return Err(StdError::generic_err("Only admins can remove admins".to_string()));
+    pub fn grant_role(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        account: Addr,
+        role: Role
+    ) -> Result<Response, ContractError> {
+
+        deps.api.addr_validate(account.as_str())?;
+
+        ensure_role(deps.storage, &info.sender, Role::SuperAdmin)?;
+
+        add_role(deps.storage, &account, role)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "grant_role")
+            .add_attribute("account", account)
+            .add_attribute("role", format!("{:?}", role)))
+
+    }
+
+    pub fn revoke_role(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        account: Addr,
+        role: Role
+    ) -> Result<Response, ContractError> {
+
+        ensure_role(deps.storage, &info.sender, Role::SuperAdmin)?;
+
+        remove_role(deps.storage, &account, role)?;
+
+        if role == Role::SuperAdmin {
+            if let Some(pending) = PENDING_SUPER_ADMIN.may_load(deps.storage)? {
+                if pending.from == account {
+                    PENDING_SUPER_ADMIN.remove(deps.storage);
+                }
+            }
         }
 
-        ADMINS_MAP.save(deps.storage, account, &false)?;
+        Ok(Response::new()
+            .add_attribute("action", "revoke_role")
+            .add_attribute("account", account)
+            .add_attribute("role", format!("{:?}", role)))
+
+    }
+
+    pub fn transfer_super_admin(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        account: Addr
+    ) -> Result<Response, ContractError> {
+
+        deps.api.addr_validate(account.as_str())?;
+
+        ensure_role(deps.storage, &info.sender, Role::SuperAdmin)?;
+
+        PENDING_SUPER_ADMIN.save(deps.storage, &PendingSuperAdmin {
+            from: info.sender.clone(),
+            to: account.clone()
+        })?;
+
+        Ok(Response::new()
+            .add_attribute("action", "transfer_super_admin")
+            .add_attribute("from", info.sender)
+            .add_attribute("to", account))
+
+    }
+
+    pub fn accept_super_admin(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo
+    ) -> Result<Response, ContractError> {
+
+        let pending = PENDING_SUPER_ADMIN.may_load(deps.storage)?
+            .ok_or(ContractError::NoPendingSuperAdmin {})?;
+
+        if pending.to != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        // A proposer whose super admin role was revoked can no longer hand it on
+        if !has_role(deps.storage, &pending.from, Role::SuperAdmin)? {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        // The nominee is granted first so the proposer is never the last super admin
+        add_role(deps.storage, &pending.to, Role::SuperAdmin)?;
+
+        if pending.from != pending.to {
+            remove_role(deps.storage, &pending.from, Role::SuperAdmin)?;
+        }
+
+        PENDING_SUPER_ADMIN.remove(deps.storage);
+
+        Ok(Response::new()
+            .add_attribute("action", "accept_super_admin")
+            .add_attribute("from", pending.from)
+            .add_attribute("to", pending.to))
+
+    }
+
+    pub fn set_paused(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        paused: bool
+    ) -> Result<Response, ContractError> {
+
+        ensure_role(deps.storage, &info.sender, Role::Pauser)?;
+
+        PAUSED.save(deps.storage, &paused)?;
+
+        Ok(Response::new()
+            .add_attribute("action", if paused { "pause" } else { "unpause" })
+            .add_attribute("account", info.sender))
 
-        Ok(Response::new())
-        
     }
 
     pub fn record_stats(
@@ -141,26 +318,56 @@ pub mod execute_msg {
         account: Addr,
         stat_type: String,
         data: Vec<Uint128>
-    ) -> StdResult<Response> {
+    ) -> Result<Response, ContractError> {
 
         let sender = info.sender;
 
-        let exists = KNOWN_MARKETS.load(deps.storage, sender.clone()).unwrap_or(false);
+        ensure_known_market(deps.storage, &sender)?;
 
         let mut response = Response::new();
 
-        if !exists {
-            return Err(StdError::generic_err("Call must be made from known market contract".to_string()));
-        }
-
         let mut statistics = STATISTICS.load(deps.storage)?;
 
         if stat_type == *"volume" {
 
+            let collateral = market_collateral(deps.storage, &sender)?;
+            let normalized = normalize_amount(deps.storage, &collateral, amount)?;
+
             let volume = VOLUMES.load(deps.storage, sender.clone()).unwrap_or(Uint128::from(0u128));
             VOLUMES.save(deps.storage, sender.clone(), &(volume + amount))?;
+
+            if let Some(index) = MARKET_INDEXES.may_load(deps.storage, sender.clone())? {
+                let previous = normalize_amount(deps.storage, &collateral, volume)?;
+                let current = normalize_amount(deps.storage, &collateral, volume + amount)?;
+                VOLUME_MARKETS.remove(deps.storage, (previous.u128(), index));
+                VOLUME_MARKETS.save(deps.storage, (current.u128(), index), &sender)?;
+            }
             
-            statistics.volume += amount;
+            statistics.volume += normalized;
+
+            let mut collateral_volume = COLLATERAL_VOLUMES.may_load(deps.storage, collateral.to_string())?
+                .unwrap_or(CollateralVolume { collateral: collateral.clone(), volume: Uint128::from(0u128) });
+            collateral_volume.volume += amount;
+            COLLATERAL_VOLUMES.save(deps.storage, collateral.to_string(), &collateral_volume)?;
+
+            if let Some(config) = POINTS_CONFIG.may_load(deps.storage)? {
+                let points = amount.multiply_ratio(config.per_volume, collateral_unit(deps.storage, &collateral)?);
+                award_points(deps.storage, &config, env.block.time.seconds(), &account, points)?;
+            }
+
+            // Sells report the fee sent to the fees address
+            if let Some(fee) = data.get(3) {
+                add_fee_revenue(deps.storage, &mut statistics, &sender, *fee)?;
+            }
+
+            add_account_stats(deps.storage, env.block.time.seconds(), &account, normalized, 1, Int128::zero())?;
+
+            if let Some(referrer) = REFERRERS.may_load(deps.storage, account.clone())? {
+                let volume = REFERRAL_VOLUMES.may_load(deps.storage, referrer.clone())?.unwrap_or_default();
+                REFERRAL_VOLUMES.save(deps.storage, referrer, &(volume + normalized))?;
+            }
+
+            ACCOUNT_MARKETS.save(deps.storage, (account.clone(), sender.clone()), &true)?;
 
             let is_unique_wallet = UNIQUE_WALLETS.load(deps.storage, account.clone()).unwrap_or(false);
             if !is_unique_wallet {
@@ -168,6 +375,14 @@ pub mod execute_msg {
                 statistics.unique_wallets += Uint128::from(1u128);
             }
 
+            add_daily_statistics(deps.storage, &env, |daily| {
+                daily.volume += normalized;
+                daily.trades += Uint128::from(1u128);
+                if !is_unique_wallet {
+                    daily.new_wallets += Uint128::from(1u128);
+                }
+            })?;
+
             response = Response::new()
                 .add_event(Event::new("xionmarkets_event")
                     .add_attribute("market", sender)
@@ -180,7 +395,7 @@ pub mod execute_msg {
                     .add_attribute("factory", env.contract.address.clone()))
 
         }
-        else if stat_type ==*"resolve" {
+        else if stat_type == *"resolve" || stat_type == *"cancel" {
 
             statistics.completed_events += Uint128::from(1u128);
 
@@ -188,10 +403,10 @@ pub mod execute_msg {
 
             let market_address = ACTIVE_MARKETS.load(deps.storage, index.u128()).unwrap_or(env.contract.address.clone());
             if market_address != sender {
-                return Err(StdError::generic_err("Market index does not match intended market".to_string()));
+                return Err(ContractError::MarketIndexMismatch {});
             }
 
-            let last_market = ACTIVE_MARKETS.load(deps.storage, statistics.active_events.u128()).unwrap_or(env.contract.address);
+            let last_market = ACTIVE_MARKETS.load(deps.storage, statistics.active_events.u128()).unwrap_or(env.contract.address.clone());
 
             ACTIVE_MARKETS.save(deps.storage, index.u128(), &last_market)?;
             ACTIVE_MARKETS.remove(deps.storage, statistics.active_events.u128());
@@ -200,10 +415,66 @@ pub mod execute_msg {
 
             COMPLETED_MARKETS.save(deps.storage, statistics.completed_events.u128(), &market_address.clone())?;
 
+            if stat_type == *"resolve" {
+                add_daily_statistics(deps.storage, &env, |daily| daily.markets_resolved += Uint128::from(1u128))?;
+            }
+
+            response = Response::new()
+                .add_event(Event::new("xionmarkets_event")
+                    .add_attribute("market", sender)
+                    .add_attribute("type", if stat_type == *"resolve" { "resolution" } else { "cancellation" }));
+        }
+        else if stat_type == *"fees" {
+
+            add_fee_revenue(deps.storage, &mut statistics, &sender, amount)?;
+
+            response = Response::new()
+                .add_event(Event::new("xionmarkets_event")
+                    .add_attribute("market", sender)
+                    .add_attribute("fees", amount)
+                    .add_attribute("type", "fee_collection"));
+        }
+        else if stat_type == *"pnl" {
+
+            let collateral = market_collateral(deps.storage, &sender)?;
+            let pnl = Int128::try_from(normalize_amount(deps.storage, &collateral, amount)?).map_err(StdError::from)?;
+            let pnl = if data[usize::try_from(0).unwrap()].is_zero() { pnl } else { -pnl };
+
+            add_account_stats(deps.storage, env.block.time.seconds(), &account, Uint128::from(0u128), 0, pnl)?;
+
+            response = Response::new()
+                .add_event(Event::new("xionmarkets_event")
+                    .add_attribute("market", sender)
+                    .add_attribute("account", account)
+                    .add_attribute("pnl", pnl.to_string())
+                    .add_attribute("type", "pnl"));
+        }
+        else if stat_type == *"liquidity" {
+
+            // Sent after every liquidity change with the account's shares, the
+            // market's total shares and the pool they split
+            let total_shares = data[usize::try_from(0).unwrap()];
+
+            let mut position = accrue_liquidity_points(deps.storage, &deps.querier, &env, &account, &sender)?;
+
+            incentives_msg::settle_liquidity_rewards(deps.storage, &env, &sender, &mut position, total_shares)?;
+
+            position.amount = match data.get(1) {
+                Some(_) if total_shares.is_zero() => Uint128::from(0u128),
+                Some(pool) => pool.multiply_ratio(amount, total_shares),
+                None if position.shares.is_zero() => Uint128::from(0u128),
+                None => position.amount.multiply_ratio(amount.min(position.shares), position.shares)
+            };
+            position.shares = amount;
+
+            LIQUIDITY_POSITIONS.save(deps.storage, (account.clone(), sender.clone()), &position)?;
+
             response = Response::new()
                 .add_event(Event::new("xionmarkets_event")
                     .add_attribute("market", sender)
-                    .add_attribute("type", "resolution"));
+                    .add_attribute("account", account)
+                    .add_attribute("liquidity_shares", amount)
+                    .add_attribute("type", "liquidity"));
         }
 
         STATISTICS.save(deps.storage, &statistics)?;
@@ -216,42 +487,36 @@ pub mod execute_msg {
         deps: DepsMut,
         _env: Env,
         _info: MessageInfo,
+        payment: Payment,
         market: Addr,
         yes_price: Uint128,
         liquidity: Uint128
-    ) -> StdResult<Response> {
-
-        let info = DETAILS.load(deps.storage)?;
+    ) -> Result<Response, ContractError> {
 
         let account = _info.sender;
 
-        let is_market_known = KNOWN_MARKETS.load(deps.storage, market.clone())?;
-        if !is_market_known {
-            return Err(StdError::generic_err("Incorrect market"));
-        }
+        ensure_known_market(deps.storage, &market)?;
+        ensure_not_paused(deps.storage)?;
 
-        let sent_sufficient_funds = _info.funds.iter().any(|coin| {
-            coin.denom == info.usdc.to_string() && coin.amount == liquidity
-        });
+        let collateral = market_collateral(deps.storage, &market)?;
 
-        if !sent_sufficient_funds {
-            return Err(StdError::generic_err("Invalid funds"));
-        }
+        check_payment(&payment, &collateral, liquidity)?;
 
-        let xfer_funds = Coin {
-            denom: info.usdc.clone(),
-            amount: liquidity
-        };
+        ACCOUNT_MARKETS.save(deps.storage, (account.clone(), market.clone()), &true)?;
+        set_market_status(deps.storage, &market, MarketStatus::Open)?;
 
-        let external_msg = WasmMsg::Execute {
-            contract_addr: market.to_string(),
-            msg: to_json_binary(&ExecuteMarketMsg::InitializeLiquidity {
-                yes_price,
-                liquidity,
-                receiver: account
-            })?,
-            funds: vec![xfer_funds]
-        };
+        // Points are accrued on the liquidity held so far, the market reports
+        // the new position back through the liquidity hook
+        let position = accrue_liquidity_points(deps.storage, &deps.querier, &_env, &account, &market)?;
+        LIQUIDITY_POSITIONS.save(deps.storage, (account.clone(), market.clone()), &position)?;
+
+        let msg = to_json_binary(&ExecuteMarketMsg::InitializeLiquidity {
+            yes_price,
+            liquidity,
+            receiver: account
+        })?;
+
+        let external_msg = collateral.execute_msg(&market, msg, liquidity)?;
 
         Ok(Response::new().add_message(external_msg))
 
@@ -261,40 +526,31 @@ pub mod execute_msg {
         deps: DepsMut,
         _env: Env,
         _info: MessageInfo,
+        payment: Payment,
         market: Addr,
         amount: Uint128
-    ) -> StdResult<Response> {
-
-        let info = DETAILS.load(deps.storage)?;
+    ) -> Result<Response, ContractError> {
 
         let account = _info.sender;
 
-        let is_market_known = KNOWN_MARKETS.load(deps.storage, market.clone())?;
-        if !is_market_known {
-            return Err(StdError::generic_err("Incorrect market"));
-        }
+        ensure_known_market(deps.storage, &market)?;
+        ensure_not_paused(deps.storage)?;
 
-        let sent_sufficient_funds = _info.funds.iter().any(|coin| {
-            coin.denom == info.usdc.to_string() && coin.amount == amount
-        });
+        let collateral = market_collateral(deps.storage, &market)?;
 
-        if !sent_sufficient_funds {
-            return Err(StdError::generic_err("Invalid funds"));
-        }
+        check_payment(&payment, &collateral, amount)?;
 
-        let xfer_funds = Coin {
-            denom: info.usdc.clone(),
-            amount
-        };
+        ACCOUNT_MARKETS.save(deps.storage, (account.clone(), market.clone()), &true)?;
 
-        let external_msg = WasmMsg::Execute {
-            contract_addr: market.to_string(),
-            msg: to_json_binary(&ExecuteMarketMsg::AddLiquidity {
-                amount,
-                receiver: account
-            })?,
-            funds: vec![xfer_funds]
-        };
+        let position = accrue_liquidity_points(deps.storage, &deps.querier, &_env, &account, &market)?;
+        LIQUIDITY_POSITIONS.save(deps.storage, (account.clone(), market.clone()), &position)?;
+
+        let msg = to_json_binary(&ExecuteMarketMsg::AddLiquidity {
+            amount,
+            receiver: account
+        })?;
+
+        let external_msg = collateral.execute_msg(&market, msg, amount)?;
 
         Ok(Response::new().add_message(external_msg))
 
@@ -306,14 +562,14 @@ pub mod execute_msg {
         _info: MessageInfo,
         market: Addr,
         shares: Uint128
-    ) -> StdResult<Response> {
+    ) -> Result<Response, ContractError> {
 
         let account = _info.sender;
         
-        let is_market_known = KNOWN_MARKETS.load(_deps.storage, market.clone())?;
-        if !is_market_known {
-            return Err(StdError::generic_err("Incorrect market"));
-        }
+        ensure_known_market(_deps.storage, &market)?;
+
+        let position = accrue_liquidity_points(_deps.storage, &_deps.querier, &_env, &account, &market)?;
+        LIQUIDITY_POSITIONS.save(_deps.storage, (account.clone(), market.clone()), &position)?;
         
         let external_msg = WasmMsg::Execute {
             contract_addr: market.to_string(),
@@ -329,25 +585,39 @@ pub mod execute_msg {
     }
 
     pub fn claim(
-        _deps: DepsMut,
+        mut _deps: DepsMut,
         _env: Env,
         _info: MessageInfo,
         market: Addr,
-        variant: Uint128
-    ) -> StdResult<Response> {
+        variant: Outcome,
+        on_behalf_of: Option<Addr>,
+        use_balance: bool
+    ) -> Result<Response, ContractError> {
 
-        let account = _info.sender;
+        ensure_known_market(_deps.storage, &market)?;
+
+        let account = trading_account(_deps.branch(), &_env, _info.sender, on_behalf_of, &market, Uint128::from(0u128))?;
+
+        // Claiming the losing outcome only writes those shares off
+        if let Some(config) = POINTS_CONFIG.may_load(_deps.storage)? {
+
+            let data: Data = _deps.querier.query_wasm_smart(
+                market.to_string(),
+                &QueryMarketMsg::GetInfo { account: account.clone() }
+            )?;
+
+            if data.information.resolved && data.information.resolved_to == Uint128::from(variant) {
+                award_points(_deps.storage, &config, _env.block.time.seconds(), &account, config.per_win)?;
+            }
 
-        let is_market_known = KNOWN_MARKETS.load(_deps.storage, market.clone())?;
-        if !is_market_known {
-            return Err(StdError::generic_err("Incorrect market"));
         }
 
         let external_msg = WasmMsg::Execute {
             contract_addr: market.to_string(),
             msg: to_json_binary(&ExecuteMarketMsg::Claim {
                 variant,
-                receiver: account
+                receiver: account,
+                credit_balance: use_balance
             })?,
             funds: vec![]
         };
@@ -361,16 +631,15 @@ pub mod execute_msg {
         _env: Env,
         _info: MessageInfo,
         market: Addr,
-        variant: Uint128,
+        variant: Outcome,
         market_index: u128
-    ) -> StdResult<Response> {
+    ) -> Result<Response, ContractError> {
 
         let account = _info.sender;
 
-        let is_market_known = KNOWN_MARKETS.load(_deps.storage, market.clone())?;
-        if !is_market_known {
-            return Err(StdError::generic_err("Incorrect market"));
-        }
+        ensure_known_market(_deps.storage, &market)?;
+
+        set_market_status(_deps.storage, &market, MarketStatus::Resolved)?;
 
         let external_msg = WasmMsg::Execute {
             contract_addr: market.to_string(),
@@ -386,102 +655,1821 @@ pub mod execute_msg {
 
     }
 
-    pub fn place_order(
+    pub fn propose_resolution(
         deps: DepsMut,
         _env: Env,
-        _info: MessageInfo,
+        info: MessageInfo,
         market: Addr,
-        variant: Uint128,
-        buy_or_sell: Uint128,
-        amount: Uint128
-    ) -> StdResult<Response> {
+        variant: Outcome
+    ) -> Result<Response, ContractError> {
 
-        let info = DETAILS.load(deps.storage)?;
+        lifecycle_message(deps, market, MarketStatus::ResolutionProposed, ExecuteMarketMsg::ProposeResolution {
+            variant,
+            receiver: info.sender
+        })
 
-        let account = _info.sender;
-        
-        let is_market_known = KNOWN_MARKETS.load(deps.storage, market.clone())?;
-        if !is_market_known {
-            return Err(StdError::generic_err("Incorrect market"));
+    }
+
+    pub fn finalize_market(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        market: Addr
+    ) -> Result<Response, ContractError> {
+
+        lifecycle_message(deps, market, MarketStatus::Finalized, ExecuteMarketMsg::FinalizeMarket {
+            receiver: info.sender
+        })
+
+    }
+
+    pub fn cancel_market(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        market: Addr,
+        market_index: u128
+    ) -> Result<Response, ContractError> {
+
+        lifecycle_message(deps, market, MarketStatus::Cancelled, ExecuteMarketMsg::CancelMarket {
+            receiver: info.sender,
+            market_index
+        })
+
+    }
+
+    fn add_fee_revenue(storage: &mut dyn Storage, statistics: &mut Statistics, market: &Addr, amount: Uint128) -> StdResult<()> {
+
+        let revenue = FEE_REVENUE.may_load(storage, market.clone())?.unwrap_or_default();
+        FEE_REVENUE.save(storage, market.clone(), &(revenue + amount))?;
+
+        let collateral = market_collateral(storage, market)?;
+        statistics.fees_collected += normalize_amount(storage, &collateral, amount)?;
+
+        Ok(())
+
+    }
+
+    pub fn collect_fees(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        markets: Vec<Addr>
+    ) -> Result<Response, ContractError> {
+
+        ensure_role(deps.storage, &info.sender, Role::FeeManager)?;
+
+        if markets.is_empty() {
+            return Err(ContractError::EmptyBatch {});
+        }
+
+        if markets.len() > MAX_BATCH_ORDERS {
+            return Err(ContractError::BatchTooLarge { max: MAX_BATCH_ORDERS });
         }
 
-        let mut funds = vec![];
+        let former_fees_addresses = FORMER_FEES_ADDRESSES
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<Addr>>>()?;
 
-        if buy_or_sell == Uint128::from(1u128) {
+        let mut messages = vec![];
+        let mut skipped = 0u32;
 
-            let sent_sufficient_funds = _info.funds.iter().any(|coin| {
-                coin.denom == info.usdc.to_string() && coin.amount == amount
-            });
+        for market in markets {
 
-            if !sent_sufficient_funds {
-                return Err(StdError::generic_err("Invalid funds"));
+            ensure_known_market(deps.storage, &market)?;
+
+            if MARKET_STATUS.may_load(deps.storage, market.clone())? != Some(MarketStatus::Resolved) {
+                skipped += 1;
+                continue;
             }
-    
-            let xfer_funds = Coin {
-                denom: info.usdc.clone(),
-                amount
-            };
 
-            funds = vec![xfer_funds];
+            messages.push(WasmMsg::Execute {
+                contract_addr: market.to_string(),
+                msg: to_json_binary(&ExecuteMarketMsg::CollectFees { former_fees_addresses: former_fees_addresses.clone() })?,
+                funds: vec![]
+            });
 
         }
 
-        let external_msg = WasmMsg::Execute {
-            contract_addr: market.to_string(),
-            msg: to_json_binary(&ExecuteMarketMsg::PlaceOrder {
-                variant,
-                buy_or_sell,
-                amount,
-                receiver: account.clone()
-            })?,
-            funds
-        };
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_attribute("action", "collect_fees")
+            .add_attribute("skipped", skipped.to_string()))
 
-        let incentives = INCENTIVES.load(deps.storage, account.clone()).unwrap_or(0u64);
-        INCENTIVES.save(deps.storage, account.clone(), &(incentives + 10u64))?;
+    }
 
-        Ok(Response::new().add_message(external_msg))
+    pub(crate) fn ensure_known_market(storage: &dyn Storage, market: &Addr) -> Result<(), ContractError> {
 
-    }
+        if !KNOWN_MARKETS.load(storage, market.clone()).unwrap_or(false) {
+            return Err(ContractError::UnknownMarket {});
+        }
 
-}
+        Ok(())
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> StdResult<Response> {
-    match msg.id {
-        1u64 => reply::instantiate_reply(deps, env, msg),
-        _ => Ok(Response::default()),
     }
-}
 
-pub mod reply {
-    use super::*;
-    pub fn instantiate_reply(deps: DepsMut, _env: Env, msg: Reply) -> StdResult<Response> {
-        
-        let temp_information: TempInformation = TEMP_INFORMATION.load(deps.storage)?;
-        let mut statistics: Statistics = STATISTICS.load(deps.storage)?;
+    /// Checks that exactly `expected` of `denom` is attached
+    fn check_funds(funds: &[Coin], denom: &str, expected: Uint128) -> Result<(), ContractError> {
 
-        let res = parse_reply_instantiate_data(msg).map_err(|e| {
-            StdError::generic_err(format!("parse reply instantiate data error: {}", e))
-        })?;
+        let got: Uint128 = funds
+            .iter()
+            .filter(|coin| coin.denom == denom)
+            .map(|coin| coin.amount)
+            .sum();
 
-        let contract_address = Addr::unchecked(res.contract_address);
+        if got != expected || funds.iter().any(|coin| coin.denom != denom) {
+            return Err(ContractError::InvalidFunds { expected, got });
+        }
 
-        let media: Vec<String> = temp_information.media;
+        Ok(())
 
-        statistics.total_pools += Uint128::from(1u128);
-        statistics.active_events += Uint128::from(1u128);
-        STATISTICS.save(deps.storage, &statistics)?;
+    }
 
-        KNOWN_MARKETS.save(deps.storage, contract_address.clone(), &true)?;
-        ACTIVE_MARKETS.save(deps.storage, statistics.active_events.u128(), &contract_address.clone())?;
-        MARKETS.save(deps.storage, statistics.total_pools.u128(), &contract_address.clone())?;
-        
-        MEDIA.save(deps.storage, contract_address.clone(), &media)?;
-        
+    /// Checks that exactly `expected` of `collateral` was paid
+    fn check_payment(payment: &Payment, collateral: &Collateral, expected: Uint128) -> Result<(), ContractError> {
+
+        match (payment, collateral) {
+            (Payment::Native(funds), Collateral::Native { denom }) => check_funds(funds, denom, expected),
+            (Payment::Cw20 { token, amount }, Collateral::CW20Token { contract_address }) if token == contract_address => {
+                if *amount != expected {
+                    return Err(ContractError::InvalidFunds { expected, got: *amount });
+                }
+                Ok(())
+            },
+            _ => Err(ContractError::InvalidCollateral { expected: collateral.to_string() })
+        }
+
+    }
+
+    /// The collateral `payment` is made in. Native payments in several denoms
+    /// are attributed to the current collateral and rejected by `paid_amount`.
+    pub(crate) fn payment_collateral(storage: &dyn Storage, payment: &Payment) -> StdResult<Collateral> {
+
+        match payment {
+            Payment::Native(funds) if funds.len() == 1 => Ok(Collateral::native(funds[0].denom.clone())),
+            Payment::Native(_) => Ok(DETAILS.load(storage)?.usdc),
+            Payment::Cw20 { token, .. } => Ok(Collateral::CW20Token { contract_address: token.clone() })
+        }
+
+    }
+
+    /// The amount of `collateral` paid, which must be the only asset sent
+    pub(crate) fn paid_amount(payment: &Payment, collateral: &Collateral) -> Result<Uint128, ContractError> {
+
+        match (payment, collateral) {
+            (Payment::Native(funds), Collateral::Native { denom }) => {
+                match funds.as_slice() {
+                    [] => Err(PaymentError::NoFunds {}.into()),
+                    [coin] if coin.denom != *denom => Err(PaymentError::MissingDenom(denom.clone()).into()),
+                    [coin] if coin.amount.is_zero() => Err(PaymentError::NoFunds {}.into()),
+                    [coin] => Ok(coin.amount),
+                    _ => Err(PaymentError::MultipleDenoms {}.into())
+                }
+            },
+            (Payment::Cw20 { token, amount }, Collateral::CW20Token { contract_address }) if token == contract_address => Ok(*amount),
+            _ => Err(ContractError::InvalidCollateral { expected: collateral.to_string() })
+        }
+
+    }
+
+    pub(crate) fn validate_collateral(api: &dyn Api, collateral: &Collateral) -> Result<(), ContractError> {
+
+        match collateral {
+            Collateral::Native { denom } if denom.trim().is_empty() => Err(ContractError::InvalidDenom {}),
+            Collateral::Native { .. } => Ok(()),
+            Collateral::CW20Token { contract_address } => {
+                api.addr_validate(contract_address.as_str())?;
+                Ok(())
+            }
+        }
+
+    }
+
+    /// The collateral a market was created with. Markets created before this
+    /// was recorded use the current collateral.
+    pub(crate) fn market_collateral(storage: &dyn Storage, market: &Addr) -> StdResult<Collateral> {
+
+        match MARKET_COLLATERAL.may_load(storage, market.clone())? {
+            Some(collateral) => Ok(collateral),
+            None => Ok(DETAILS.load(storage)?.usdc)
+        }
+
+    }
+
+    /// Forwards a lifecycle transition to `market` and mirrors the resulting
+    /// status for listing. A rejected transition reverts the whole transaction.
+    fn lifecycle_message(deps: DepsMut, market: Addr, status: MarketStatus, msg: ExecuteMarketMsg) -> Result<Response, ContractError> {
+
+        ensure_known_market(deps.storage, &market)?;
+
+        set_market_status(deps.storage, &market, status)?;
+
+        let external_msg = WasmMsg::Execute {
+            contract_addr: market.to_string(),
+            msg: to_json_binary(&msg)?,
+            funds: vec![]
+        };
+
+        Ok(Response::new().add_message(external_msg))
+
+    }
+
+    pub fn place_order(
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        payment: Payment,
+        market: Addr,
+        variant: Outcome,
+        buy_or_sell: Side,
+        amount: Uint128,
+        min_out: Option<Uint128>,
+        on_behalf_of: Option<Addr>,
+        use_balance: bool,
+        referrer: Option<Addr>
+    ) -> Result<Response, ContractError> {
+
+        ensure_not_paused(deps.storage)?;
+
+        let collateral = market_collateral(deps.storage, &market)?;
+
+        // Sells count against a grant by their quoted USDC value
+        let notional = if on_behalf_of.is_none() || buy_or_sell == Side::Buy {
+            amount
+        }
+        else {
+            let quote: Quote = deps.querier.query_wasm_smart(
+                market.to_string(),
+                &QueryMarketMsg::Quote { variant, buy_or_sell, amount }
+            )?;
+            quote.amount_out
+        };
+
+        let account = trading_account(deps.branch(), &env, info.sender.clone(), on_behalf_of, &market, notional)?;
+
+        // Operators trading for a granter cannot pick the granter's referrer
+        if let Some(referrer) = referrer {
+            if info.sender != account {
+                return Err(ContractError::DelegatedReferral {});
+            }
+            set_referrer(deps.branch(), &account, referrer)?;
+        }
+
+        // Sells pay out, so attached funds would be left in the factory
+        if (use_balance || buy_or_sell == Side::Sell) && !payment.is_empty() {
+            return Err(ContractError::FundsNotAllowed {});
+        }
+
+        if buy_or_sell == Side::Buy {
+
+            if use_balance {
+                debit_balance(deps.branch(), account.clone(), &collateral, amount)?;
+            }
+            else {
+
+                check_payment(&payment, &collateral, amount)?;
+
+            }
+
+        }
+
+        let external_msg = order_message(deps, market, variant, buy_or_sell, amount, min_out, account, &collateral, use_balance)?;
+
+        Ok(Response::new().add_message(external_msg))
+
+    }
+
+    pub fn batch_place_order(
+        mut deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        orders: Vec<BatchOrder>,
+        atomic: bool,
+        use_balance: bool
+    ) -> Result<Response, ContractError> {
+
+        ensure_not_paused(deps.storage)?;
+
+        let account = info.sender.clone();
+
+        if orders.is_empty() {
+            return Err(ContractError::EmptyBatch {});
+        }
+        if orders.len() > MAX_BATCH_ORDERS {
+            return Err(ContractError::BatchTooLarge { max: MAX_BATCH_ORDERS });
+        }
+
+        let collaterals = orders
+            .iter()
+            .map(|order| market_collateral(deps.storage, &order.market))
+            .collect::<StdResult<Vec<Collateral>>>()?;
+
+        // Markets may use different collateral, so buys are totalled per asset
+        let mut total_buys: BTreeMap<String, (Collateral, Uint128)> = BTreeMap::new();
+
+        for (order, collateral) in orders.iter().zip(collaterals.iter()) {
+            if order.buy_or_sell == Side::Buy {
+                total_buys.entry(collateral.to_string()).or_insert((collateral.clone(), Uint128::from(0u128))).1 += order.amount;
+            }
+        }
+
+        if use_balance {
+
+            if !info.funds.is_empty() {
+                return Err(ContractError::FundsNotAllowed {});
+            }
+
+            for (collateral, total) in total_buys.values() {
+                debit_balance(deps.branch(), account.clone(), collateral, *total)?;
+            }
+
+        }
+        else {
+
+            if let Some(coin) = info.funds.iter().find(|coin| !total_buys.contains_key(&coin.denom)) {
+                return Err(ContractError::InvalidFunds { expected: Uint128::from(0u128), got: coin.amount });
+            }
+
+            // CW20 collateral can only be paid through `Receive`
+            let payment = Payment::Native(info.funds.clone());
+
+            for (collateral, total) in total_buys.values() {
+                check_payment(&payment, collateral, *total)?;
+            }
+
+        }
+
+        let mut response = Response::new()
+            .add_attribute("action", "batch_place_order")
+            .add_attribute("account", account.clone())
+            .add_attribute("orders", orders.len().to_string())
+            .add_attribute("atomic", atomic.to_string());
+
+        for (index, (order, collateral)) in orders.into_iter().zip(collaterals).enumerate() {
+
+            let is_buy = order.buy_or_sell == Side::Buy;
+
+            let external_msg = order_message(
+                deps.branch(),
+                order.market,
+                order.variant,
+                order.buy_or_sell,
+                order.amount,
+                order.min_out,
+                account.clone(),
+                &collateral,
+                use_balance
+            )?;
+
+            if atomic {
+                response = response.add_message(external_msg);
+            }
+            else {
+
+                // A failed order is reverted on its own and its buy amount refunded in the reply
+                let id = BATCH_ORDER_REPLY_ID + index as u64;
+
+                BATCH_ORDERS.save(deps.storage, id, &PendingBatchOrder {
+                    account: account.clone(),
+                    refund: if is_buy { order.amount } else { Uint128::from(0u128) },
+                    collateral,
+                    use_balance
+                })?;
+
+                response = response.add_submessage(SubMsg::reply_always(external_msg, id));
+
+            }
+
+        }
+
+        Ok(response)
+
+    }
+
+    /// Handles CW20 collateral sent to the factory. `info.sender` is the token
+    /// contract, the actions run for the account that sent the tokens.
+    pub fn receive(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        wrapper: Cw20ReceiveMsg
+    ) -> Result<Response, ContractError> {
+
+        let amount = wrapper.amount;
+
+        let payment = Payment::Cw20 { token: info.sender, amount };
+
+        let info = MessageInfo {
+            sender: deps.api.addr_validate(&wrapper.sender)?,
+            funds: vec![]
+        };
+
+        match from_json(&wrapper.msg)? {
+            ReceiveMsg::InitializeLiquidity { market, yes_price } => {
+                initialize_liquidity(deps, env, info, payment, market, yes_price, amount)
+            },
+            ReceiveMsg::AddLiquidity { market } => {
+                add_liquidity(deps, env, info, payment, market, amount)
+            },
+            ReceiveMsg::PlaceOrder { market, variant, min_out, on_behalf_of, referrer } => {
+                place_order(deps, env, info, payment, market, variant, Side::Buy, amount, min_out, on_behalf_of, false, referrer)
+            },
+            ReceiveMsg::Deposit {} => {
+                deposit(deps, env, info, payment)
+            },
+            ReceiveMsg::CreditBalance { account } => {
+                credit_balance(deps, env, info, payment, account)
+            },
+            ReceiveMsg::CreditReferral { referrer } => {
+                credit_referral(deps, env, info, payment, referrer)
+            },
+            ReceiveMsg::FundEpochRewards { epoch } => {
+                fund_epoch_rewards(deps, env, info, payment, epoch)
+            },
+            ReceiveMsg::FundLiquidityRewards { market, reward_per_second } => {
+                incentives_msg::fund_liquidity_rewards(deps, env, info, payment, market, reward_per_second)
+            }
+        }
+
+    }
+
+    pub fn register_signing_key(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        pubkey: Binary
+    ) -> Result<Response, ContractError> {
+
+        // Compressed (33 bytes) or uncompressed (65 bytes) secp256k1 public key
+        if pubkey.len() != 33 && pubkey.len() != 65 {
+            return Err(ContractError::InvalidPublicKey {});
+        }
+
+        // The verifier parses the key after the signature, so with a well formed
+        // probe signature it only errors when the key is not a curve point
+        deps.api
+            .secp256k1_verify(&[0u8; 32], &[1u8; 64], &pubkey)
+            .map_err(|_| ContractError::InvalidPublicKey {})?;
+
+        SIGNING_KEYS.save(deps.storage, info.sender.clone(), &pubkey)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "register_signing_key")
+            .add_attribute("account", info.sender))
+
+    }
+
+    pub fn deposit(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        payment: Payment
+    ) -> Result<Response, ContractError> {
+
+        // Any whitelisted collateral can be deposited
+        let collateral = payment_collateral(deps.storage, &payment)?;
+
+        whitelisted_collateral(deps.storage, &collateral)?;
+
+        let amount = paid_amount(&payment, &collateral)?;
+
+        add_to_balance(deps, info.sender.clone(), &collateral, amount)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "deposit")
+            .add_attribute("account", info.sender)
+            .add_attribute("collateral", collateral.to_string())
+            .add_attribute("amount", amount))
+
+    }
+
+    pub fn withdraw(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        amount: Uint128,
+        collateral: Option<Collateral>
+    ) -> Result<Response, ContractError> {
+
+        let collateral = match collateral {
+            Some(collateral) => collateral,
+            None => DETAILS.load(deps.storage)?.usdc
+        };
+
+        if amount == Uint128::from(0u128) {
+            return Err(ContractError::ZeroAmount {});
+        }
+
+        debit_balance(deps, info.sender.clone(), &collateral, amount)?;
+
+        let asset_transfer = collateral.transfer_msg(&info.sender, amount)?;
+
+        Ok(Response::new()
+            .add_message(asset_transfer)
+            .add_attribute("action", "withdraw")
+            .add_attribute("account", info.sender)
+            .add_attribute("collateral", collateral.to_string())
+            .add_attribute("amount", amount))
+
+    }
+
+    pub fn credit_balance(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        payment: Payment,
+        account: Addr
+    ) -> Result<Response, ContractError> {
+
+        ensure_known_market(deps.storage, &info.sender)?;
+
+        let collateral = market_collateral(deps.storage, &info.sender)?;
+
+        let amount = paid_amount(&payment, &collateral)?;
+
+        add_to_balance(deps, account.clone(), &collateral, amount)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "credit_balance")
+            .add_attribute("market", info.sender)
+            .add_attribute("account", account)
+            .add_attribute("amount", amount))
+
+    }
+
+    /// Records `referrer` for `account` unless it already has one or has traded before
+    fn set_referrer(deps: DepsMut, account: &Addr, referrer: Addr) -> Result<(), ContractError> {
+
+        let referrer = deps.api.addr_validate(referrer.as_str())?;
+
+        if referrer == *account {
+            return Err(ContractError::SelfReferral {});
+        }
+
+        if REFERRERS.has(deps.storage, account.clone()) || UNIQUE_WALLETS.load(deps.storage, account.clone()).unwrap_or(false) {
+            return Ok(());
+        }
+
+        REFERRERS.save(deps.storage, account.clone(), &referrer)?;
+        REFEREES.save(deps.storage, (referrer.clone(), account.clone()), &true)?;
+
+        let referees = REFEREE_COUNTS.may_load(deps.storage, referrer.clone())?.unwrap_or_default();
+        REFEREE_COUNTS.save(deps.storage, referrer, &(referees + 1))?;
+
+        Ok(())
+
+    }
+
+    pub fn credit_referral(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        payment: Payment,
+        referrer: Addr
+    ) -> Result<Response, ContractError> {
+
+        ensure_known_market(deps.storage, &info.sender)?;
+
+        let collateral = market_collateral(deps.storage, &info.sender)?;
+
+        let amount = paid_amount(&payment, &collateral)?;
+
+        let key = (referrer.clone(), collateral.to_string());
+        let mut earnings = REFERRAL_EARNINGS.may_load(deps.storage, key.clone())?
+            .unwrap_or(ReferralEarnings { collateral, earned: Uint128::from(0u128), claimable: Uint128::from(0u128) });
+        earnings.earned += amount;
+        earnings.claimable += amount;
+        REFERRAL_EARNINGS.save(deps.storage, key, &earnings)?;
+
+        Ok(Response::new()
+            .add_event(Event::new("xionmarkets_event")
+                .add_attribute("market", info.sender)
+                .add_attribute("referrer", referrer)
+                .add_attribute("amount", amount)
+                .add_attribute("type", "referral")))
+
+    }
+
+    pub fn claim_referral_earnings(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        collateral: Option<Collateral>
+    ) -> Result<Response, ContractError> {
+
+        let collateral = match collateral {
+            Some(collateral) => collateral,
+            None => DETAILS.load(deps.storage)?.usdc
+        };
+
+        let key = (info.sender.clone(), collateral.to_string());
+
+        let mut earnings = match REFERRAL_EARNINGS.may_load(deps.storage, key.clone())? {
+            Some(earnings) if !earnings.claimable.is_zero() => earnings,
+            _ => return Err(ContractError::NoReferralEarnings {})
+        };
+
+        let amount = earnings.claimable;
+        earnings.claimable = Uint128::from(0u128);
+        REFERRAL_EARNINGS.save(deps.storage, key, &earnings)?;
+
+        Ok(Response::new()
+            .add_message(collateral.transfer_msg(&info.sender, amount)?)
+            .add_attribute("action", "claim_referral_earnings")
+            .add_attribute("referrer", info.sender)
+            .add_attribute("collateral", collateral.to_string())
+            .add_attribute("amount", amount))
+
+    }
+
+    pub fn set_referral_share(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        share_bps: u16
+    ) -> Result<Response, ContractError> {
+
+        ensure_role(deps.storage, &info.sender, Role::FeeManager)?;
+
+        if share_bps > 10_000 {
+            return Err(ContractError::InvalidReferralShare {});
+        }
+
+        REFERRAL_SHARE.save(deps.storage, &share_bps)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_referral_share")
+            .add_attribute("share_bps", share_bps.to_string()))
+
+    }
+
+    pub fn set_points_config(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        per_volume: Uint128,
+        per_lp_day: Uint128,
+        per_win: Uint128,
+        epoch_cap: Option<Uint128>
+    ) -> Result<Response, ContractError> {
+
+        ensure_role(deps.storage, &info.sender, Role::SuperAdmin)?;
+
+        // Epoch numbering stays fixed once points are configured
+        let genesis = match POINTS_CONFIG.may_load(deps.storage)? {
+            Some(config) => config.genesis,
+            None => env.block.time.seconds()
+        };
+
+        POINTS_CONFIG.save(deps.storage, &PointsConfig { per_volume, per_lp_day, per_win, epoch_cap, genesis })?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_points_config")
+            .add_attribute("genesis", genesis.to_string()))
+
+    }
+
+    pub(crate) fn epoch_at(config: &PointsConfig, time: u64) -> u64 {
+        time.saturating_sub(config.genesis) / EPOCH_LENGTH
+    }
+
+    /// Credits `points` to `account` in the current epoch, up to the epoch cap.
+    /// Fees addresses, current or former, earn nothing: what they hold are
+    /// protocol fees, not positions.
+    fn award_points(storage: &mut dyn Storage, config: &PointsConfig, now: u64, account: &Addr, points: Uint128) -> StdResult<()> {
+
+        if *account == DETAILS.load(storage)?.fees_address || FORMER_FEES_ADDRESSES.has(storage, account.clone()) {
+            return Ok(());
+        }
+
+        let epoch = epoch_at(config, now);
+
+        let earned = EPOCH_POINTS.may_load(storage, (epoch, account.clone()))?.unwrap_or_default();
+
+        let points = match config.epoch_cap {
+            Some(cap) => points.min(cap.saturating_sub(earned)),
+            None => points
+        };
+
+        if points.is_zero() {
+            return Ok(());
+        }
+
+        EPOCH_POINTS.save(storage, (epoch, account.clone()), &(earned + points))?;
+
+        let total = EPOCH_TOTALS.may_load(storage, epoch)?.unwrap_or_default();
+        EPOCH_TOTALS.save(storage, epoch, &(total + points))?;
+
+        let lifetime = TOTAL_POINTS.may_load(storage, account.clone())?.unwrap_or_default();
+        TOTAL_POINTS.save(storage, account.clone(), &(lifetime + points))?;
+
+        Ok(())
+
+    }
+
+    /// Applies `update` to the statistics of the current UTC day
+    pub(crate) fn add_daily_statistics(storage: &mut dyn Storage, env: &Env, update: impl FnOnce(&mut DailyStatistics)) -> StdResult<()> {
+
+        let day = env.block.time.seconds() / SECONDS_PER_DAY;
+
+        let mut daily = DAILY_STATISTICS.may_load(storage, day)?.unwrap_or(DailyStatistics { day, ..Default::default() });
+        update(&mut daily);
+
+        DAILY_STATISTICS.save(storage, day, &daily)
+
+    }
+
+    /// Adds to `account`'s lifetime trading totals and, once points are
+    /// configured, to its totals in the current epoch
+    fn add_account_stats(storage: &mut dyn Storage, now: u64, account: &Addr, volume: Uint128, trades: u64, pnl: Int128) -> StdResult<()> {
+
+        let mut scopes = vec![ALL_TIME];
+        if let Some(config) = POINTS_CONFIG.may_load(storage)? {
+            scopes.push(epoch_at(&config, now));
+        }
+
+        for scope in scopes {
+
+            let mut stats = ACCOUNT_STATS.may_load(storage, (scope, account.clone()))?.unwrap_or_default();
+
+            VOLUME_RANKING.remove(storage, (scope, stats.volume.u128(), account.clone()));
+            PNL_RANKING.remove(storage, (scope, stats.realized_pnl.i128(), account.clone()));
+
+            stats.volume += volume;
+            stats.trades += trades;
+            stats.realized_pnl += pnl;
+
+            VOLUME_RANKING.save(storage, (scope, stats.volume.u128(), account.clone()), &true)?;
+            PNL_RANKING.save(storage, (scope, stats.realized_pnl.i128(), account.clone()), &true)?;
+            ACCOUNT_STATS.save(storage, (scope, account.clone()), &stats)?;
+
+        }
+
+        Ok(())
+
+    }
+
+    /// One whole unit of `collateral` in base units
+    fn collateral_unit(storage: &dyn Storage, collateral: &Collateral) -> StdResult<Uint128> {
+
+        let decimals = COLLATERALS.may_load(storage, collateral.to_string())?
+            .map(|config| config.decimals)
+            .unwrap_or(DEFAULT_DECIMALS);
+
+        10u128.checked_pow(decimals.into())
+            .map(Uint128::from)
+            .ok_or_else(|| StdError::overflow(OverflowError::new(OverflowOperation::Pow, 10u128, decimals)))
+
+    }
+
+    /// `amount` of `collateral` in `STATS_DECIMALS`, so totals over
+    /// several collaterals add up
+    pub(crate) fn normalize_amount(storage: &dyn Storage, collateral: &Collateral, amount: Uint128) -> StdResult<Uint128> {
+
+        let unit = collateral_unit(storage, collateral)?;
+
+        Ok(amount.multiply_ratio(10u128.pow(STATS_DECIMALS.into()), unit))
+
+    }
+
+    /// Credits the points earned by `account`'s liquidity in `market` since the
+    /// last accrual and returns the position to update. Liquidity stops earning
+    /// once trading ends.
+    fn accrue_liquidity_points(storage: &mut dyn Storage, querier: &QuerierWrapper, env: &Env, account: &Addr, market: &Addr) -> StdResult<LiquidityPosition> {
+
+        let mut position = liquidity_position(storage, querier, account, market)?;
+
+        let market_end = MARKET_ENDS.may_load(storage, market.clone())?.unwrap_or(u64::MAX);
+        let now = env.block.time.seconds().min(market_end);
+
+        if let Some(config) = POINTS_CONFIG.may_load(storage)? {
+
+            let since = position.accrued_at.max(config.genesis);
+
+            if now > since && !position.amount.is_zero() {
+
+                let unit = collateral_unit(storage, &market_collateral(storage, market)?)?;
+
+                let points = position.amount.multiply_ratio(
+                    config.per_lp_day.checked_mul(Uint128::from(now - since))?,
+                    unit.checked_mul(Uint128::from(SECONDS_PER_DAY))?
+                );
+
+                award_points(storage, &config, env.block.time.seconds(), account, points)?;
+
+            }
+
+        }
+
+        position.accrued_at = position.accrued_at.max(now);
+
+        Ok(position)
+
+    }
+
+    /// `account`'s tracked liquidity in `market`. Liquidity provided before
+    /// positions were tracked is valued from the market's shares, earns
+    /// points from the points genesis and mining rewards from the market's
+    /// first funding.
+    pub(crate) fn liquidity_position(storage: &dyn Storage, querier: &QuerierWrapper, account: &Addr, market: &Addr) -> StdResult<LiquidityPosition> {
+
+        if let Some(position) = LIQUIDITY_POSITIONS.may_load(storage, (account.clone(), market.clone()))? {
+            return Ok(position);
+        }
+
+        let data: Data = querier.query_wasm_smart(
+            market.to_string(),
+            &QueryMarketMsg::GetInfo { account: account.clone() }
+        )?;
+
+        if data.shares.liquidity_shares.is_zero() {
+            return Ok(LiquidityPosition::default());
+        }
+
+        let removal: RemoveLiquiditySimulation = querier.query_wasm_smart(
+            market.to_string(),
+            &QueryMarketMsg::SimulateRemoveLiquidity { shares: data.shares.liquidity_shares }
+        )?;
+
+        Ok(LiquidityPosition {
+            amount: removal.amount_out,
+            shares: data.shares.liquidity_shares,
+            ..Default::default()
+        })
+
+    }
+
+    pub fn accrue_liquidity(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        markets: Vec<Addr>
+    ) -> Result<Response, ContractError> {
+
+        if markets.is_empty() {
+            return Err(ContractError::EmptyBatch {});
+        }
+
+        if markets.len() > MAX_BATCH_ORDERS {
+            return Err(ContractError::BatchTooLarge { max: MAX_BATCH_ORDERS });
+        }
+
+        for market in markets {
+
+            ensure_known_market(deps.storage, &market)?;
+
+            let position = accrue_liquidity_points(deps.storage, &deps.querier, &env, &info.sender, &market)?;
+            LIQUIDITY_POSITIONS.save(deps.storage, (info.sender.clone(), market), &position)?;
+
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "accrue_liquidity_points")
+            .add_attribute("account", info.sender))
+
+    }
+
+    pub fn fund_epoch_rewards(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        payment: Payment,
+        epoch: u64
+    ) -> Result<Response, ContractError> {
+
+        ensure_role(deps.storage, &info.sender, Role::FeeManager)?;
+
+        let config = POINTS_CONFIG.may_load(deps.storage)?.ok_or(ContractError::PointsNotConfigured {})?;
+
+        // Totals only stop changing once the epoch is over
+        if epoch >= epoch_at(&config, env.block.time.seconds()) {
+            return Err(ContractError::EpochNotFinished { epoch });
+        }
+
+        if EPOCH_TOTALS.may_load(deps.storage, epoch)?.unwrap_or_default().is_zero() {
+            return Err(ContractError::EmptyEpoch { epoch });
+        }
+
+        // The first funding picks the reward token, top-ups must use the same one
+        let mut reward = match EPOCH_REWARDS.may_load(deps.storage, epoch)? {
+            Some(reward) => reward,
+            None => {
+                let token = payment_collateral(deps.storage, &payment)?;
+                whitelisted_collateral(deps.storage, &token)?;
+                EpochReward { token, amount: Uint128::from(0u128) }
+            }
+        };
+
+        let amount = paid_amount(&payment, &reward.token)?;
+        reward.amount += amount;
+
+        EPOCH_REWARDS.save(deps.storage, epoch, &reward)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "fund_epoch_rewards")
+            .add_attribute("epoch", epoch.to_string())
+            .add_attribute("token", reward.token.to_string())
+            .add_attribute("amount", amount))
+
+    }
+
+    /// The reward of `epoch` and the part of it `account` has yet to claim
+    pub(crate) fn unclaimed_epoch_rewards(storage: &dyn Storage, epoch: u64, account: &Addr) -> StdResult<Option<(EpochReward, Uint128)>> {
+
+        let reward = match EPOCH_REWARDS.may_load(storage, epoch)? {
+            Some(reward) => reward,
+            None => return Ok(None)
+        };
+
+        let points = EPOCH_POINTS.may_load(storage, (epoch, account.clone()))?.unwrap_or_default();
+        let total = EPOCH_TOTALS.load(storage, epoch)?;
+        let claimed = EPOCH_CLAIMS.may_load(storage, (epoch, account.clone()))?.unwrap_or_default();
+
+        let unclaimed = reward.amount.multiply_ratio(points, total).saturating_sub(claimed);
+
+        Ok(Some((reward, unclaimed)))
+
+    }
+
+    pub fn claim_epoch_rewards(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        epochs: Vec<u64>
+    ) -> Result<Response, ContractError> {
+
+        let account = info.sender;
+
+        // Epochs funded with the same token are paid out together
+        let mut payouts: BTreeMap<String, (Collateral, Uint128)> = BTreeMap::new();
+
+        for epoch in epochs {
+
+            let (reward, unclaimed) = match unclaimed_epoch_rewards(deps.storage, epoch, &account)? {
+                Some((reward, unclaimed)) if !unclaimed.is_zero() => (reward, unclaimed),
+                _ => continue
+            };
+
+            let claimed = EPOCH_CLAIMS.may_load(deps.storage, (epoch, account.clone()))?.unwrap_or_default();
+            EPOCH_CLAIMS.save(deps.storage, (epoch, account.clone()), &(claimed + unclaimed))?;
+
+            payouts.entry(reward.token.to_string())
+                .or_insert((reward.token, Uint128::from(0u128)))
+                .1 += unclaimed;
+
+        }
+
+        if payouts.is_empty() {
+            return Err(ContractError::NoEpochRewards {});
+        }
+
+        let messages = payouts
+            .into_values()
+            .map(|(token, amount)| token.transfer_msg(&account, amount))
+            .collect::<StdResult<Vec<CosmosMsg>>>()?;
+
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_attribute("action", "claim_epoch_rewards")
+            .add_attribute("account", account))
+
+    }
+
+    pub fn submit_signed_order(
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        signer: Addr,
+        order: SignedOrder,
+        signature: Binary
+    ) -> Result<Response, ContractError> {
+
+        ensure_not_paused(deps.storage)?;
+
+        let pubkey = SIGNING_KEYS.may_load(deps.storage, signer.clone())?
+            .ok_or(ContractError::NoSigningKey {})?;
+
+        if env.block.time.seconds() > order.expiry {
+            return Err(ContractError::OrderExpired {});
+        }
+
+        let nonce = NONCES.load(deps.storage, signer.clone()).unwrap_or(0u64);
+        if order.nonce != nonce {
+            return Err(ContractError::InvalidNonce { expected: nonce, got: order.nonce });
+        }
+
+        let payload = SignedOrderPayload {
+            chain_id: env.block.chain_id.clone(),
+            factory: env.contract.address.clone(),
+            signer: signer.clone(),
+            order: order.clone()
+        };
+
+        let message_hash = Sha256::digest(to_json_vec(&payload)?);
+
+        let is_valid = deps.api
+            .secp256k1_verify(&message_hash, &signature, &pubkey)
+            .map_err(|_| ContractError::InvalidSignature {})?;
+
+        if !is_valid {
+            return Err(ContractError::InvalidSignature {});
+        }
+
+        NONCES.save(deps.storage, signer.clone(), &(nonce + 1u64))?;
+
+        let collateral = market_collateral(deps.storage, &order.market)?;
+
+        if order.buy_or_sell == Side::Buy {
+
+            debit_balance(deps.branch(), signer.clone(), &collateral, order.amount)?;
+
+        }
+
+        let external_msg = order_message(
+            deps,
+            order.market,
+            order.variant,
+            order.buy_or_sell,
+            order.amount,
+            Some(order.min_out),
+            signer.clone(),
+            &collateral,
+            true
+        )?;
+
+        Ok(Response::new()
+            .add_message(external_msg)
+            .add_attribute("action", "submit_signed_order")
+            .add_attribute("signer", signer)
+            .add_attribute("nonce", order.nonce.to_string())
+            .add_attribute("relayer", info.sender))
+
+    }
+
+    pub fn grant_trading(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        operator: Addr,
+        expires: u64,
+        max_notional: Uint128,
+        markets: Option<Vec<Addr>>
+    ) -> Result<Response, ContractError> {
+
+        deps.api.addr_validate(operator.as_str())?;
+
+        if operator == info.sender {
+            return Err(ContractError::SelfGrant {});
+        }
+
+        if expires <= env.block.time.seconds() {
+            return Err(ContractError::InvalidExpiry {});
+        }
+
+        if let Some(markets) = &markets {
+            for market in markets {
+                ensure_known_market(deps.storage, market)?;
+            }
+        }
+
+        let grant = TradingGrant {
+            granter: info.sender.clone(),
+            operator: operator.clone(),
+            expires,
+            max_notional,
+            used_notional: Uint128::from(0u128),
+            markets
+        };
+
+        TRADING_GRANTS.save(deps.storage, (info.sender.clone(), operator.clone()), &grant)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "grant_trading")
+            .add_attribute("granter", info.sender)
+            .add_attribute("operator", operator)
+            .add_attribute("expires", expires.to_string())
+            .add_attribute("max_notional", max_notional))
+
+    }
+
+    pub fn revoke_trading(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        operator: Addr
+    ) -> Result<Response, ContractError> {
+
+        let key = (info.sender.clone(), operator.clone());
+
+        if !TRADING_GRANTS.has(deps.storage, key.clone()) {
+            return Err(ContractError::NoTradingGrant {});
+        }
+
+        TRADING_GRANTS.remove(deps.storage, key);
+
+        Ok(Response::new()
+            .add_attribute("action", "revoke_trading")
+            .add_attribute("granter", info.sender)
+            .add_attribute("operator", operator))
+
+    }
+
+    /// Tracks a market created before the lifecycle, the collateral whitelist
+    /// or the category, search and status indexes existed. Run a page at a time by
+    /// `migrate_markets` so migrating the factory never queries every market.
+    fn backfill_market(deps: DepsMut, env: &Env, index: u128, market: &Addr) -> StdResult<()> {
+
+        let stored_status = MARKET_STATUS.may_load(deps.storage, market.clone())?;
+        let missing_collateral = !MARKET_COLLATERAL.has(deps.storage, market.clone());
+        let missing_index = !MARKET_INDEXES.has(deps.storage, market.clone());
+        let missing_status = match stored_status {
+            Some(status) => !STATUS_MARKETS.has(deps.storage, (status as u8, index)),
+            None => true
+        };
+
+        if !(missing_status || missing_collateral || missing_index) {
+            return Ok(());
+        }
+
+        let data: Data = deps.querier.query_wasm_smart(
+            market.to_string(),
+            &QueryMarketMsg::GetInfo { account: env.contract.address.clone() }
+        )?;
+
+        if missing_collateral {
+            MARKET_COLLATERAL.save(deps.storage, market.clone(), &data.information.usdc)?;
+            if let Some(unrecorded) = UNRECORDED_COLLATERALS.may_load(deps.storage)? {
+                UNRECORDED_COLLATERALS.save(deps.storage, &unrecorded.saturating_sub(1))?;
+            }
+        }
+
+        if missing_index {
+            index_market(deps.storage, index, market, &data.information.owner, data.information.market_end, &data.information.categories)?;
+        }
+
+        if missing_status {
+            MARKET_ENDS.save(deps.storage, market.clone(), &data.information.market_end)?;
+            set_market_status(deps.storage, market, stored_status.unwrap_or(data.information.inferred_status()))?;
+        }
+
+        Ok(())
+
+    }
+
+    pub fn migrate_markets(
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        new_code_id: u64,
+        start_after: Option<u128>,
+        limit: Option<u32>
+    ) -> Result<Response, ContractError> {
+
+        ensure_role(deps.storage, &info.sender, Role::SuperAdmin)?;
+
+        // The code id only changes through the timelocked config update
+        let details = DETAILS.load(deps.storage)?;
+        if new_code_id != details.market_code_id {
+            return Err(ContractError::CodeIdMismatch { expected: details.market_code_id, got: new_code_id });
+        }
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let markets = MARKETS
+            .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<(u128, Addr)>>>()?;
+
+        let mut response = Response::new();
+        let mut last_index = None;
+
+        for (index, market) in markets {
+
+            backfill_market(deps.branch(), &env, index, &market)?;
+
+            response = response.add_message(WasmMsg::Migrate {
+                contract_addr: market.to_string(),
+                new_code_id,
+                msg: to_json_binary(&MigrateMarketMsg {})?
+            });
+
+            last_index = Some(index);
+
+        }
+
+        Ok(response
+            .add_attribute("action", "migrate_markets")
+            .add_attribute("new_code_id", new_code_id.to_string())
+            .add_attribute("last_index", last_index.map(|index| index.to_string()).unwrap_or_default()))
+
+    }
+
+    pub fn update_config(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        fees_address: Option<Addr>,
+        market_code_id: Option<u64>,
+        usdc: Option<Collateral>
+    ) -> Result<Response, ContractError> {
+
+        ensure_role(deps.storage, &info.sender, config_role(&fees_address, &market_code_id, &usdc))?;
+
+        if PENDING_CONFIG.exists(deps.storage) {
+            return Err(ContractError::ConfigUpdatePending {});
+        }
+
+        if fees_address.is_none() && market_code_id.is_none() && usdc.is_none() {
+            return Err(ContractError::EmptyConfigUpdate {});
+        }
+
+        if let Some(fees_address) = &fees_address {
+            deps.api.addr_validate(fees_address.as_str())?;
+        }
+
+        if market_code_id == Some(0u64) {
+            return Err(ContractError::InvalidCodeId {});
+        }
+
+        if let Some(usdc) = &usdc {
+            whitelisted_collateral(deps.storage, usdc)?;
+            // Markets without a recorded collateral are priced in the default one
+            let unrecorded = UNRECORDED_COLLATERALS.may_load(deps.storage)?.unwrap_or_default();
+            if unrecorded > 0 {
+                return Err(ContractError::UnrecordedCollaterals { markets: unrecorded });
+            }
+        }
+
+        let executable_at = env.block.time.seconds() + CONFIG_TIMELOCK;
+
+        PENDING_CONFIG.save(deps.storage, &PendingConfig {
+            fees_address,
+            market_code_id,
+            usdc,
+            proposed_by: info.sender.clone(),
+            executable_at
+        })?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_config")
+            .add_attribute("proposed_by", info.sender)
+            .add_attribute("executable_at", executable_at.to_string()))
+
+    }
+
+    pub fn execute_config_update(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo
+    ) -> Result<Response, ContractError> {
+
+        let pending = PENDING_CONFIG.may_load(deps.storage)?
+            .ok_or(ContractError::NoPendingConfig {})?;
+
+        ensure_role(deps.storage, &info.sender, config_role(&pending.fees_address, &pending.market_code_id, &pending.usdc))?;
+
+        if env.block.time.seconds() < pending.executable_at {
+            return Err(ContractError::TimelockActive { executable_at: pending.executable_at });
+        }
+
+        let mut details = DETAILS.load(deps.storage)?;
+        let mut response = Response::new().add_attribute("action", "execute_config_update");
+
+        let config_event = |field: &str, old: String, new: String| {
+            Event::new("xionmarkets_event")
+                .add_attribute("type", "config_update")
+                .add_attribute("field", field)
+                .add_attribute("old", old)
+                .add_attribute("new", new)
+        };
+
+        if let Some(fees_address) = pending.fees_address {
+            response = response.add_event(config_event("fees_address", details.fees_address.to_string(), fees_address.to_string()));
+            // Fee shares the old address holds in markets are moved on collection
+            if fees_address != details.fees_address {
+                FORMER_FEES_ADDRESSES.save(deps.storage, details.fees_address.clone(), &env.block.time.seconds())?;
+                FORMER_FEES_ADDRESSES.remove(deps.storage, fees_address.clone());
+            }
+            details.fees_address = fees_address;
+        }
+
+        if let Some(market_code_id) = pending.market_code_id {
+            response = response.add_event(config_event("market_code_id", details.market_code_id.to_string(), market_code_id.to_string()));
+            details.market_code_id = market_code_id;
+        }
+
+        if let Some(usdc) = pending.usdc {
+            // It may have been removed from the whitelist since it was proposed
+            whitelisted_collateral(deps.storage, &usdc)?;
+            response = response.add_event(config_event("usdc", details.usdc.to_string(), usdc.to_string()));
+            details.usdc = usdc;
+        }
+
+        DETAILS.save(deps.storage, &details)?;
+        PENDING_CONFIG.remove(deps.storage);
+
+        Ok(response)
+
+    }
+
+    pub fn cancel_config_update(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo
+    ) -> Result<Response, ContractError> {
+
+        let pending = PENDING_CONFIG.may_load(deps.storage)?
+            .ok_or(ContractError::NoPendingConfig {})?;
+
+        ensure_role(deps.storage, &info.sender, config_role(&pending.fees_address, &pending.market_code_id, &pending.usdc))?;
+
+        PENDING_CONFIG.remove(deps.storage);
+
+        Ok(Response::new()
+            .add_attribute("action", "cancel_config_update")
+            .add_attribute("cancelled_by", info.sender))
+
+    }
+
+    pub fn set_collateral(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        collateral: Collateral,
+        decimals: u8,
+        min_liquidity: Uint128
+    ) -> Result<Response, ContractError> {
+
+        ensure_role(deps.storage, &info.sender, Role::SuperAdmin)?;
+
+        validate_collateral(deps.api, &collateral)?;
+
+        if min_liquidity.is_zero() {
+            return Err(ContractError::InvalidMinLiquidity {});
+        }
+
+        if decimals > MAX_DECIMALS {
+            return Err(ContractError::InvalidDecimals { max: MAX_DECIMALS });
+        }
+
+        COLLATERALS.save(deps.storage, collateral.to_string(), &CollateralConfig {
+            collateral: collateral.clone(),
+            decimals,
+            min_liquidity
+        })?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_collateral")
+            .add_attribute("collateral", collateral.to_string())
+            .add_attribute("decimals", decimals.to_string())
+            .add_attribute("min_liquidity", min_liquidity))
+
+    }
+
+    pub fn remove_collateral(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        collateral: Collateral
+    ) -> Result<Response, ContractError> {
+
+        ensure_role(deps.storage, &info.sender, Role::SuperAdmin)?;
+
+        whitelisted_collateral(deps.storage, &collateral)?;
+
+        if DETAILS.load(deps.storage)?.usdc == collateral {
+            return Err(ContractError::DefaultCollateral {});
+        }
+
+        COLLATERALS.remove(deps.storage, collateral.to_string());
+
+        Ok(Response::new()
+            .add_attribute("action", "remove_collateral")
+            .add_attribute("collateral", collateral.to_string()))
+
+    }
+
+    pub fn add_category(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        category: String
+    ) -> Result<Response, ContractError> {
+
+        ensure_role(deps.storage, &info.sender, Role::SuperAdmin)?;
+
+        if category.trim().is_empty() {
+            return Err(ContractError::InvalidCategory {});
+        }
+
+        CATEGORIES.save(deps.storage, category.clone(), &true)?;
+
+        if !CATEGORY_COUNTS.has(deps.storage, category.clone()) {
+            CATEGORY_COUNTS.save(deps.storage, category.clone(), &0u64)?;
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "add_category")
+            .add_attribute("category", category))
+
+    }
+
+    pub fn remove_category(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        category: String
+    ) -> Result<Response, ContractError> {
+
+        ensure_role(deps.storage, &info.sender, Role::SuperAdmin)?;
+
+        if !CATEGORIES.has(deps.storage, category.clone()) {
+            return Err(ContractError::UnknownCategory { category });
+        }
+
+        CATEGORIES.remove(deps.storage, category.clone());
+
+        // Categories with markets stay listed
+        if CATEGORY_COUNTS.may_load(deps.storage, category.clone())?.unwrap_or_default() == 0 {
+            CATEGORY_COUNTS.remove(deps.storage, category.clone());
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "remove_category")
+            .add_attribute("category", category))
+
+    }
+
+    /// Adds the market at `index` to the search indexes. Markets already
+    /// indexed are skipped, so it can be rerun safely.
+    pub(crate) fn index_market(
+        storage: &mut dyn Storage,
+        index: u128,
+        market: &Addr,
+        owner: &Addr,
+        market_end: u64,
+        categories: &[String]
+    ) -> StdResult<()> {
+
+        if MARKET_INDEXES.has(storage, market.clone()) {
+            return Ok(());
+        }
+
+        MARKET_INDEXES.save(storage, market.clone(), &index)?;
+        OWNER_MARKETS.save(storage, (owner.clone(), index), market)?;
+        END_DATE_MARKETS.save(storage, (market_end, index), market)?;
+
+        let volume = VOLUMES.may_load(storage, market.clone())?.unwrap_or_default();
+        let volume = normalize_amount(storage, &market_collateral(storage, market)?, volume)?;
+        VOLUME_MARKETS.save(storage, (volume.u128(), index), market)?;
+
+        for category in categories {
+
+            if CATEGORY_MARKETS.has(storage, (category.clone(), index)) {
+                continue;
+            }
+
+            CATEGORY_MARKETS.save(storage, (category.clone(), index), market)?;
+
+            let count = CATEGORY_COUNTS.may_load(storage, category.clone())?.unwrap_or_default();
+            CATEGORY_COUNTS.save(storage, category.clone(), &(count + 1))?;
+
+        }
+
+        Ok(())
+
+    }
+
+    /// Stores `market`'s status and moves it to the matching `STATUS_MARKETS`
+    /// entry. Markets not indexed yet are added by `backfill_market`.
+    pub(crate) fn set_market_status(storage: &mut dyn Storage, market: &Addr, status: MarketStatus) -> StdResult<()> {
+
+        let previous = MARKET_STATUS.may_load(storage, market.clone())?;
+
+        MARKET_STATUS.save(storage, market.clone(), &status)?;
+
+        if let Some(index) = MARKET_INDEXES.may_load(storage, market.clone())? {
+
+            if let Some(previous) = previous {
+                STATUS_MARKETS.remove(storage, (previous as u8, index));
+            }
+
+            STATUS_MARKETS.save(storage, (status as u8, index), market)?;
+
+        }
+
+        Ok(())
+
+    }
+
+    fn whitelisted_collateral(storage: &dyn Storage, collateral: &Collateral) -> Result<CollateralConfig, ContractError> {
+
+        COLLATERALS.may_load(storage, collateral.to_string())?
+            .filter(|config| config.collateral == *collateral)
+            .ok_or(ContractError::CollateralNotWhitelisted { collateral: collateral.to_string() })
+
+    }
+
+    /// `SuperAdmin` holds every role
+    pub(crate) fn has_role(storage: &dyn Storage, account: &Addr, role: Role) -> StdResult<bool> {
+
+        let roles = ROLES.may_load(storage, account.clone())?.unwrap_or_default();
+
+        Ok(roles.contains(&Role::SuperAdmin) || roles.contains(&role))
+
+    }
+
+    pub(crate) fn ensure_role(storage: &dyn Storage, account: &Addr, role: Role) -> Result<(), ContractError> {
+
+        if !has_role(storage, account, role)? {
+            return Err(ContractError::MissingRole { role });
+        }
+
+        Ok(())
+
+    }
+
+    fn ensure_not_paused(storage: &dyn Storage) -> Result<(), ContractError> {
+
+        if PAUSED.may_load(storage)?.unwrap_or(false) {
+            return Err(ContractError::Paused {});
+        }
+
+        Ok(())
+
+    }
+
+    /// Changing only the fees address is left to fee managers
+    fn config_role(fees_address: &Option<Addr>, market_code_id: &Option<u64>, usdc: &Option<Collateral>) -> Role {
+
+        if fees_address.is_some() && market_code_id.is_none() && usdc.is_none() {
+            Role::FeeManager
+        }
+        else {
+            Role::SuperAdmin
+        }
+
+    }
+
+    pub(crate) fn add_role(storage: &mut dyn Storage, account: &Addr, role: Role) -> StdResult<()> {
+
+        let mut roles = ROLES.may_load(storage, account.clone())?.unwrap_or_default();
+
+        if roles.contains(&role) {
+            return Ok(());
+        }
+
+        if role == Role::SuperAdmin {
+            let super_admins = SUPER_ADMINS.may_load(storage)?.unwrap_or(0u32);
+            SUPER_ADMINS.save(storage, &(super_admins + 1))?;
+        }
+
+        roles.push(role);
+
+        save_roles(storage, account, roles)
+
+    }
+
+    fn remove_role(storage: &mut dyn Storage, account: &Addr, role: Role) -> Result<(), ContractError> {
+
+        let mut roles = ROLES.may_load(storage, account.clone())?.unwrap_or_default();
+
+        if !roles.contains(&role) {
+            return Err(ContractError::RoleNotGranted { role });
+        }
+
+        if role == Role::SuperAdmin {
+
+            let super_admins = SUPER_ADMINS.may_load(storage)?.unwrap_or(0u32);
+
+            if super_admins <= 1 {
+                return Err(ContractError::LastSuperAdmin {});
+            }
+
+            SUPER_ADMINS.save(storage, &(super_admins - 1))?;
+
+        }
+
+        roles.retain(|held| *held != role);
+
+        save_roles(storage, account, roles)?;
+
+        Ok(())
+
+    }
+
+    fn save_roles(storage: &mut dyn Storage, account: &Addr, roles: Vec<Role>) -> StdResult<()> {
+
+        if roles.is_empty() {
+            ROLES.remove(storage, account.clone());
+            return Ok(());
+        }
+
+        ROLES.save(storage, account.clone(), &roles)
+
+    }
+
+    /// Returns the account an action is performed for. When `on_behalf_of` names
+    /// another account, the sender must hold a live grant from it covering `market`
+    /// and `notional` is counted against the grant's limit.
+    fn trading_account(
+        deps: DepsMut,
+        env: &Env,
+        sender: Addr,
+        on_behalf_of: Option<Addr>,
+        market: &Addr,
+        notional: Uint128
+    ) -> Result<Addr, ContractError> {
+
+        let granter = match on_behalf_of {
+            Some(granter) if granter != sender => granter,
+            _ => return Ok(sender)
+        };
+
+        let mut grant = TRADING_GRANTS.may_load(deps.storage, (granter.clone(), sender.clone()))?
+            .ok_or(ContractError::NoTradingGrant {})?;
+
+        if env.block.time.seconds() >= grant.expires {
+            return Err(ContractError::GrantExpired {});
+        }
+
+        if let Some(markets) = &grant.markets {
+            if !markets.contains(market) {
+                return Err(ContractError::GrantMarketNotAllowed {});
+            }
+        }
+
+        if grant.used_notional + notional > grant.max_notional {
+            return Err(ContractError::GrantLimitExceeded {
+                remaining: grant.max_notional - grant.used_notional,
+                want: notional
+            });
+        }
+
+        grant.used_notional += notional;
+
+        TRADING_GRANTS.save(deps.storage, (granter.clone(), sender), &grant)?;
+
+        Ok(granter)
+
+    }
+
+    pub(crate) fn add_to_balance(deps: DepsMut, account: Addr, collateral: &Collateral, amount: Uint128) -> StdResult<()> {
+
+        let key = (account, collateral.to_string());
+
+        let balance = BALANCES.load(deps.storage, key.clone()).unwrap_or(Uint128::from(0u128));
+
+        BALANCES.save(deps.storage, key, &(balance + amount))?;
+
+        Ok(())
+
+    }
+
+    fn debit_balance(deps: DepsMut, account: Addr, collateral: &Collateral, amount: Uint128) -> Result<(), ContractError> {
+
+        let key = (account, collateral.to_string());
+
+        let balance = BALANCES.load(deps.storage, key.clone()).unwrap_or(Uint128::from(0u128));
+
+        if balance < amount {
+            return Err(ContractError::InsufficientBalance { have: balance, want: amount });
+        }
+
+        BALANCES.save(deps.storage, key, &(balance - amount))?;
+
+        Ok(())
+
+    }
+
+    fn order_message(
+        deps: DepsMut,
+        market: Addr,
+        variant: Outcome,
+        buy_or_sell: Side,
+        amount: Uint128,
+        min_out: Option<Uint128>,
+        receiver: Addr,
+        collateral: &Collateral,
+        credit_balance: bool
+    ) -> Result<WasmMsg, ContractError> {
+
+        ensure_known_market(deps.storage, &market)?;
+
+        let share_bps = REFERRAL_SHARE.may_load(deps.storage)?.unwrap_or_default();
+
+        let referral = match REFERRERS.may_load(deps.storage, receiver.clone())? {
+            Some(referrer) if share_bps > 0 => Some(Referral { referrer, share_bps }),
+            _ => None
+        };
+
+        let msg = to_json_binary(&ExecuteMarketMsg::PlaceOrder {
+            variant,
+            buy_or_sell,
+            amount,
+            receiver: receiver.clone(),
+            min_out,
+            credit_balance,
+            referral
+        })?;
+
+        // Buys carry their amount to the market, sells carry nothing
+        let paid = if buy_or_sell == Side::Buy { amount } else { Uint128::from(0u128) };
+
+        let external_msg = collateral.execute_msg(&market, msg, paid)?;
+
+        Ok(external_msg)
+
+    }
+
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        1u64 => reply::instantiate_reply(deps, env, msg),
+        id if id >= BATCH_ORDER_REPLY_ID => reply::batch_order_reply(deps, env, msg),
+        _ => Ok(Response::default()),
+    }
+}
+
+pub mod reply {
+    use super::*;
+    pub fn instantiate_reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+        
+        let temp_information: TempInformation = TEMP_INFORMATION.load(deps.storage)?;
+        let mut statistics: Statistics = STATISTICS.load(deps.storage)?;
+
+        let res = parse_reply_instantiate_data(msg).map_err(|e| {
+            StdError::generic_err(format!("parse reply instantiate data error: {}", e))
+        })?;
+
+        let contract_address = Addr::unchecked(res.contract_address);
+
+        let media: Vec<String> = temp_information.media;
+
+        statistics.total_pools += Uint128::from(1u128);
+        statistics.active_events += Uint128::from(1u128);
+        STATISTICS.save(deps.storage, &statistics)?;
+
+        execute_msg::add_daily_statistics(deps.storage, &env, |daily| daily.markets_created += Uint128::from(1u128))?;
+
+        KNOWN_MARKETS.save(deps.storage, contract_address.clone(), &true)?;
+        ACTIVE_MARKETS.save(deps.storage, statistics.active_events.u128(), &contract_address.clone())?;
+        MARKETS.save(deps.storage, statistics.total_pools.u128(), &contract_address.clone())?;
+
+        execute_msg::index_market(
+            deps.storage,
+            statistics.total_pools.u128(),
+            &contract_address,
+            &temp_information.owner,
+            temp_information.market_end,
+            &temp_information.categories
+        )?;
+        
+        MEDIA.save(deps.storage, contract_address.clone(), &media)?;
+
+        execute_msg::set_market_status(deps.storage, &contract_address, MarketStatus::Created)?;
+        MARKET_ENDS.save(deps.storage, contract_address.clone(), &temp_information.market_end)?;
+        MARKET_COLLATERAL.save(deps.storage, contract_address.clone(), &temp_information.usdc)?;
+        
         Ok(Response::new()
             .add_attribute("contract_address", contract_address.clone())
         )
         
     }
-}
\ No newline at end of file
+
+    pub fn batch_order_reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+
+        let pending: PendingBatchOrder = BATCH_ORDERS.load(deps.storage, msg.id)?;
+        BATCH_ORDERS.remove(deps.storage, msg.id);
+
+        let error = match msg.result {
+            SubMsgResult::Ok(_) => return Ok(Response::new()),
+            SubMsgResult::Err(error) => error
+        };
+
+        let mut response = Response::new()
+            .add_event(Event::new("xionmarkets_event")
+                .add_attribute("type", "batch_order_failed")
+                .add_attribute("account", pending.account.clone())
+                .add_attribute("index", (msg.id - BATCH_ORDER_REPLY_ID).to_string())
+                .add_attribute("error", error));
+
+        if pending.refund > Uint128::from(0u128) {
+
+            if pending.use_balance {
+                execute_msg::add_to_balance(deps, pending.account, &pending.collateral, pending.refund)?;
+            }
+            else {
+
+                response = response.add_message(pending.collateral.transfer_msg(&pending.account, pending.refund)?);
+
+            }
+
+        }
+
+        Ok(response)
+
+    }
+}
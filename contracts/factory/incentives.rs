@@ -0,0 +1,229 @@
+use crate::state::{LIQUIDITY_REWARDS, LIQUIDITY_POSITIONS};
+
+use packages::factory::{LiquidityPosition, LiquidityRewards, Role};
+
+use packages::collateral::Collateral;
+
+use packages::market::{Data, QueryMsg as QueryMarketMsg};
+
+use cosmwasm_std::{Addr, CosmosMsg, Decimal256, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage, Uint128, Uint256};
+
+use crate::ContractError;
+
+use crate::execute::{execute_msg, Payment, MAX_BATCH_ORDERS};
+
+use std::collections::BTreeMap;
+
+/// Liquidity mining. Markets report every change to an account's liquidity
+/// shares into its `LIQUIDITY_POSITIONS` record, and each market's reward
+/// budget is split between the shares with a reward-per-share accumulator.
+pub mod incentives_msg {
+
+    use super::*;
+
+    pub fn fund_liquidity_rewards(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        payment: Payment,
+        market: Addr,
+        reward_per_second: Uint128
+    ) -> Result<Response, ContractError> {
+
+        execute_msg::ensure_role(deps.storage, &info.sender, Role::MarketCreator)?;
+        execute_msg::ensure_known_market(deps.storage, &market)?;
+
+        if reward_per_second.is_zero() {
+            return Err(ContractError::InvalidRewardRate {});
+        }
+
+        let now = env.block.time.seconds();
+
+        // The first funding picks the reward token, top-ups must use the same one
+        let mut rewards = match LIQUIDITY_REWARDS.may_load(deps.storage, market.clone())? {
+            Some(mut rewards) => {
+                update_rewards(&mut rewards, now);
+                rewards
+            },
+            None => {
+                let data: Data = deps.querier.query_wasm_smart(
+                    market.to_string(),
+                    &QueryMarketMsg::GetInfo { account: env.contract.address.clone() }
+                )?;
+
+                LiquidityRewards {
+                    token: execute_msg::payment_collateral(deps.storage, &payment)?,
+                    reward_per_second,
+                    end: now,
+                    last_update: now,
+                    reward_per_share: Decimal256::zero(),
+                    total_shares: data.information.liquidity_shares
+                }
+            }
+        };
+
+        let amount = execute_msg::paid_amount(&payment, &rewards.token)?;
+
+        // What is left of the current budget is emitted at the new rate. A
+        // remainder smaller than one second of rewards stays in the factory.
+        let remaining = rewards.reward_per_second.checked_mul(Uint128::from(rewards.end.saturating_sub(now))).map_err(StdError::from)?;
+        let duration = u64::try_from(((remaining + amount) / reward_per_second).u128()).unwrap_or(u64::MAX);
+
+        if duration == 0 {
+            return Err(ContractError::RewardBudgetTooSmall {});
+        }
+
+        rewards.reward_per_second = reward_per_second;
+        rewards.end = now.saturating_add(duration);
+
+        LIQUIDITY_REWARDS.save(deps.storage, market.clone(), &rewards)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "fund_liquidity_rewards")
+            .add_attribute("market", market)
+            .add_attribute("token", rewards.token.to_string())
+            .add_attribute("amount", amount)
+            .add_attribute("end", rewards.end.to_string()))
+
+    }
+
+    pub fn claim_liquidity_rewards(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        markets: Vec<Addr>
+    ) -> Result<Response, ContractError> {
+
+        if markets.is_empty() {
+            return Err(ContractError::EmptyBatch {});
+        }
+
+        if markets.len() > MAX_BATCH_ORDERS {
+            return Err(ContractError::BatchTooLarge { max: MAX_BATCH_ORDERS });
+        }
+
+        let account = info.sender;
+        let now = env.block.time.seconds();
+
+        // Markets rewarding the same token are paid out together
+        let mut payouts: BTreeMap<String, (Collateral, Uint128)> = BTreeMap::new();
+
+        for market in markets {
+
+            let mut rewards = match LIQUIDITY_REWARDS.may_load(deps.storage, market.clone())? {
+                Some(rewards) => rewards,
+                None => continue
+            };
+
+            let mut position = execute_msg::liquidity_position(deps.storage, &deps.querier, &account, &market)?;
+
+            update_rewards(&mut rewards, now);
+            settle(&rewards, &mut position)?;
+
+            let pending = position.pending;
+            position.pending = Uint128::from(0u128);
+
+            LIQUIDITY_REWARDS.save(deps.storage, market.clone(), &rewards)?;
+            LIQUIDITY_POSITIONS.save(deps.storage, (account.clone(), market), &position)?;
+
+            if pending.is_zero() {
+                continue;
+            }
+
+            payouts.entry(rewards.token.to_string())
+                .or_insert((rewards.token, Uint128::from(0u128)))
+                .1 += pending;
+
+        }
+
+        if payouts.is_empty() {
+            return Err(ContractError::NoLiquidityRewards {});
+        }
+
+        let messages = payouts
+            .into_values()
+            .map(|(token, amount)| token.transfer_msg(&account, amount))
+            .collect::<StdResult<Vec<CosmosMsg>>>()?;
+
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_attribute("action", "claim_liquidity_rewards")
+            .add_attribute("account", account))
+
+    }
+
+    /// Settles the rewards `position` earned in `market` with the shares it
+    /// held until now, and records the market's new total shares
+    pub(crate) fn settle_liquidity_rewards(
+        storage: &mut dyn Storage,
+        env: &Env,
+        market: &Addr,
+        position: &mut LiquidityPosition,
+        total_shares: Uint128
+    ) -> StdResult<()> {
+
+        if let Some(mut rewards) = LIQUIDITY_REWARDS.may_load(storage, market.clone())? {
+            update_rewards(&mut rewards, env.block.time.seconds());
+            settle(&rewards, position)?;
+            rewards.total_shares = total_shares;
+            LIQUIDITY_REWARDS.save(storage, market.clone(), &rewards)?;
+        }
+
+        Ok(())
+
+    }
+
+    pub(crate) fn pending_liquidity_rewards(deps: Deps, env: &Env, market: Addr, account: Addr) -> StdResult<Uint128> {
+
+        let mut rewards = match LIQUIDITY_REWARDS.may_load(deps.storage, market.clone())? {
+            Some(rewards) => rewards,
+            None => return Ok(Uint128::from(0u128))
+        };
+
+        let mut position = execute_msg::liquidity_position(deps.storage, &deps.querier, &account, &market)?;
+
+        update_rewards(&mut rewards, env.block.time.seconds());
+        settle(&rewards, &mut position)?;
+
+        Ok(position.pending)
+
+    }
+
+    /// Emits the rewards due since the last update. Nothing is emitted while
+    /// the market has no liquidity, the program runs that much longer instead.
+    fn update_rewards(rewards: &mut LiquidityRewards, now: u64) {
+
+        if now <= rewards.last_update {
+            return;
+        }
+
+        if rewards.total_shares.is_zero() {
+            if rewards.last_update < rewards.end {
+                rewards.end += now - rewards.last_update;
+            }
+        }
+        else {
+            let until = now.min(rewards.end);
+            if until > rewards.last_update {
+                let emitted = rewards.reward_per_second.full_mul(until - rewards.last_update);
+                rewards.reward_per_share += Decimal256::from_ratio(emitted, rewards.total_shares);
+            }
+        }
+
+        rewards.last_update = now;
+
+    }
+
+    /// Moves the rewards earned by `position` since it was last settled to `pending`
+    fn settle(rewards: &LiquidityRewards, position: &mut LiquidityPosition) -> StdResult<()> {
+
+        let earned = Uint256::from(position.shares) * (rewards.reward_per_share - position.reward_per_share_paid);
+
+        position.pending += Uint128::try_from(earned)?;
+        position.reward_per_share_paid = rewards.reward_per_share;
+
+        Ok(())
+
+    }
+
+}
@@ -1,5 +1,7 @@
 pub mod contract;
 pub mod execute;
+pub mod incentives;
+pub mod query;
 mod error;
 pub mod state;
 pub use crate::error::ContractError;
\ No newline at end of file
@@ -0,0 +1,751 @@
+use crate::state::{DETAILS, VOLUMES, MEDIA, MARKETS, ACTIVE_MARKETS, STATISTICS, COMPLETED_MARKETS, TRADING_GRANTS, ACCOUNT_MARKETS, MARKET_STATUS, MARKET_ENDS, STATUS_MARKETS, ROLES, COLLATERALS, COLLATERAL_VOLUMES, REFEREES, REFEREE_COUNTS, REFERRAL_VOLUMES, REFERRAL_EARNINGS, POINTS_CONFIG, TOTAL_POINTS, EPOCH_POINTS, EPOCH_TOTALS, EPOCH_REWARDS, ACCOUNT_STATS, VOLUME_RANKING, PNL_RANKING, ALL_TIME, DAILY_STATISTICS, CATEGORIES, CATEGORY_MARKETS, CATEGORY_COUNTS, OWNER_MARKETS, END_DATE_MARKETS, VOLUME_MARKETS};
+
+use packages::factory::{MarketInfo, MarketList, TradingGrant, MarketTrade, Portfolio, PortfolioEntry, Role, RoleAssignment, CollateralVolume, ReferralEarnings, ReferralStats, AccountPoints, EpochInfo, LeaderboardEntry, LeaderboardMetric, LeaderboardPage, DailyStatistics, CategoryInfo, MarketType, MarketFilter, MarketSort};
+
+use cosmwasm_std::{
+    to_json_binary, Binary, Deps, Env, StdResult, WasmQuery, Uint128, Addr, QueryRequest, Order
+};
+
+use packages::market::{Data, Quote, QueryMsg as QueryMarketMsg, Information, MarketStatus, Outcome, Shares, Side, Trade, PositionInfo};
+
+use crate::execute::{execute_msg, EPOCH_LENGTH};
+use crate::ContractError;
+
+use packages::collateral::CollateralConfig;
+
+use cw_storage_plus::{Bound, KeyDeserialize, PrimaryKey};
+
+pub(crate) const DEFAULT_LIMIT: u32 = 10;
+pub(crate) const MAX_LIMIT: u32 = 30;
+
+/// Most index rows a filtered listing reads in one call
+pub(crate) const MAX_SCAN: usize = 300;
+
+const MAX_HISTORY_DAYS: u64 = 366;
+
+pub mod query_msg {
+
+    use super::*;
+
+    pub fn fees_address(_deps: Deps, _env: Env) -> StdResult<Addr> {
+
+        let details = DETAILS.load(_deps.storage)?;
+
+        let fees_address = details.fees_address;
+
+        Ok(fees_address)
+
+    }
+
+    pub fn is_admin(_deps: Deps, _env: Env, account: Addr) -> StdResult<bool> {
+
+        let is_admin = execute_msg::has_role(_deps.storage, &account, Role::SuperAdmin)?;
+
+        Ok(is_admin)
+
+    }
+
+    pub fn list_collaterals(_deps: Deps, _env: Env, start_after: Option<String>, limit: Option<u32>) -> StdResult<Vec<CollateralConfig>> {
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        COLLATERALS
+            .range(_deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, config)| config))
+            .collect()
+
+    }
+
+    pub fn get_collateral_volumes(_deps: Deps, _env: Env, start_after: Option<String>, limit: Option<u32>) -> StdResult<Vec<CollateralVolume>> {
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        COLLATERAL_VOLUMES
+            .range(_deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, volume)| volume))
+            .collect()
+
+    }
+
+    pub fn get_points(_deps: Deps, _env: Env, account: Addr) -> StdResult<AccountPoints> {
+
+        let current_epoch = match POINTS_CONFIG.may_load(_deps.storage)? {
+            Some(config) => execute_msg::epoch_at(&config, _env.block.time.seconds()),
+            None => 0
+        };
+
+        Ok(AccountPoints {
+            total: TOTAL_POINTS.may_load(_deps.storage, account.clone())?.unwrap_or_default(),
+            current_epoch,
+            epoch_points: EPOCH_POINTS.may_load(_deps.storage, (current_epoch, account))?.unwrap_or_default()
+        })
+
+    }
+
+    pub fn get_epoch(_deps: Deps, _env: Env, epoch: u64) -> Result<EpochInfo, ContractError> {
+
+        let config = POINTS_CONFIG.may_load(_deps.storage)?
+            .ok_or(ContractError::PointsNotConfigured {})?;
+
+        let start = config.genesis + epoch * EPOCH_LENGTH;
+
+        Ok(EpochInfo {
+            epoch,
+            start,
+            end: start + EPOCH_LENGTH,
+            total_points: EPOCH_TOTALS.may_load(_deps.storage, epoch)?.unwrap_or_default(),
+            reward: EPOCH_REWARDS.may_load(_deps.storage, epoch)?
+        })
+
+    }
+
+    pub fn get_referral_stats(_deps: Deps, _env: Env, referrer: Addr) -> StdResult<ReferralStats> {
+
+        let earnings = REFERRAL_EARNINGS
+            .prefix(referrer.clone())
+            .range(_deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, earnings)| earnings))
+            .collect::<StdResult<Vec<ReferralEarnings>>>()?;
+
+        Ok(ReferralStats {
+            referees: REFEREE_COUNTS.may_load(_deps.storage, referrer.clone())?.unwrap_or_default(),
+            volume: REFERRAL_VOLUMES.may_load(_deps.storage, referrer)?.unwrap_or_default(),
+            earnings
+        })
+
+    }
+
+    pub fn list_referees(_deps: Deps, _env: Env, referrer: Addr, start_after: Option<Addr>, limit: Option<u32>) -> StdResult<Vec<Addr>> {
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        REFEREES
+            .prefix(referrer)
+            .range(_deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(referee, _)| referee))
+            .collect()
+
+    }
+
+    pub fn get_statistics_history(_deps: Deps, _env: Env, from_day: u64, to_day: u64) -> Result<Vec<DailyStatistics>, ContractError> {
+
+        if to_day >= from_day && to_day - from_day >= MAX_HISTORY_DAYS {
+            return Err(ContractError::HistoryTooLong { max: MAX_HISTORY_DAYS });
+        }
+
+        (from_day..=to_day)
+            .map(|day| Ok(DAILY_STATISTICS.may_load(_deps.storage, day)?.unwrap_or(DailyStatistics { day, ..Default::default() })))
+            .collect()
+
+    }
+
+    pub fn leaderboard(_deps: Deps, _env: Env, metric: LeaderboardMetric, epoch: Option<u64>, start_after: Option<Binary>, limit: Option<u32>) -> StdResult<LeaderboardPage> {
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let scope = epoch.unwrap_or(ALL_TIME);
+
+        // The cursor is the raw (score, account) ranking key of the last entry
+        let end = start_after.map(|cursor| cursor.to_vec());
+
+        let keys = match metric {
+            LeaderboardMetric::Volume => VOLUME_RANKING
+                .sub_prefix(scope)
+                .keys_raw(_deps.storage, None, end.map(Bound::ExclusiveRaw), Order::Descending)
+                .take(limit)
+                .collect::<Vec<Vec<u8>>>(),
+            LeaderboardMetric::RealizedPnl => PNL_RANKING
+                .sub_prefix(scope)
+                .keys_raw(_deps.storage, None, end.map(Bound::ExclusiveRaw), Order::Descending)
+                .take(limit)
+                .collect::<Vec<Vec<u8>>>()
+        };
+
+        let entries = keys
+            .iter()
+            .map(|key| {
+                let account = match metric {
+                    LeaderboardMetric::Volume => <(u128, Addr)>::from_slice(key)?.1,
+                    LeaderboardMetric::RealizedPnl => <(i128, Addr)>::from_slice(key)?.1
+                };
+
+                Ok(LeaderboardEntry {
+                    stats: ACCOUNT_STATS.load(_deps.storage, (scope, account.clone()))?,
+                    account
+                })
+            })
+            .collect::<StdResult<Vec<LeaderboardEntry>>>()?;
+
+        let next_start_after = match keys.last() {
+            Some(key) if keys.len() == limit => Some(Binary::from(key.as_slice())),
+            _ => None
+        };
+
+        Ok(LeaderboardPage { entries, next_start_after })
+
+    }
+
+    pub fn list_roles(_deps: Deps, _env: Env, start_after: Option<Addr>, limit: Option<u32>) -> StdResult<Vec<RoleAssignment>> {
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        ROLES
+            .range(_deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(account, roles)| RoleAssignment { account, roles }))
+            .collect()
+
+    }
+
+    pub fn quote(_deps: Deps, _env: Env, market: Addr, variant: Outcome, buy_or_sell: Side, amount: Uint128) -> StdResult<Quote> {
+
+        let msg = QueryMarketMsg::Quote {
+            variant,
+            buy_or_sell,
+            amount
+        };
+        
+        let query_msg = QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: market.to_string(),
+            msg: to_json_binary(&msg)?,
+        });
+
+        let quote: Quote = _deps.querier.query(&query_msg)?;
+        
+        Ok(quote)
+
+    }
+
+    pub fn get_market_info(_deps: Deps, _env: Env, contract_address: Addr, account: Addr) -> StdResult<MarketInfo> {
+
+        let msg = QueryMarketMsg::GetInfo {
+            account
+        };
+        
+        let query_msg = QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: contract_address.to_string(),
+            msg: to_json_binary(&msg)?,
+        });
+
+        let data: Data = _deps.querier.query(&query_msg)?;
+
+        let information: Information = data.information;
+        let shares: Shares = data.shares;
+
+        let volume: Uint128 = VOLUMES.load(_deps.storage, contract_address.clone()).unwrap_or(Uint128::from(0u128));
+        let media_: Vec<String> = MEDIA.load(_deps.storage, contract_address.clone()).unwrap_or(vec![String::from(""), String::from("")]);
+
+        let media: [String; 2] = media_.try_into().unwrap_or_default();
+
+        let market_info: MarketInfo = MarketInfo {
+            information,
+            shares,
+            volume,
+            media
+        };
+
+        Ok(market_info)
+
+    }
+
+    pub fn fetch_markets(_deps: Deps, _env: Env, page_: u128, items_per_page: u128, account: Addr, market_type: MarketType) -> Result<MarketList, ContractError> {
+
+        if page_ == 0u128 {
+            return Err(ContractError::ZeroPage {});
+        }
+        if items_per_page == 0u128 {
+            return Err(ContractError::ZeroItemsPerPage {});
+        }
+
+        let statistics = STATISTICS.load(_deps.storage)?;
+
+        let total_markets = match market_type {
+            MarketType::All => statistics.total_pools,
+            MarketType::Active => statistics.active_events,
+            MarketType::Completed => statistics.completed_events
+        };
+
+        let number_of_pages = if total_markets % Uint128::from(items_per_page) == Uint128::from(0u128) {
+            total_markets / Uint128::from(items_per_page)
+        }
+        else {
+            (total_markets / Uint128::from(items_per_page)) + Uint128::from(1u128)
+        };
+
+        let page = Uint128::from(page_);
+
+        if page > number_of_pages {
+            return Err(ContractError::PageOutOfBounds {});
+        }
+
+        if total_markets == Uint128::from(0u128) {
+            return Err(ContractError::NoMarkets {});
+        }
+
+        let start_index = total_markets - ((page - Uint128::from(1u128)) * Uint128::from(items_per_page));
+
+        let end_index = if start_index < Uint128::from(items_per_page) {
+            Uint128::from(1u128)
+        }
+        else {
+            (start_index - Uint128::from(items_per_page)) + Uint128::from(1u128)
+        };
+
+        let number_of_items = (start_index - end_index) + Uint128::from(1u128);
+
+        if number_of_items == Uint128::from(0u128) {
+            return Err(ContractError::EmptyPage {});
+        }
+
+        let mut paginated_markets: Vec<Information> = vec![];
+        let mut contract_addresses: Vec<Addr> = vec![];
+        let mut indexes: Vec<u128> = vec![];
+
+        let mut current_index = start_index;
+
+        while current_index >= end_index {
+
+            let index = current_index.u128();
+
+            let market_at = match market_type {
+                MarketType::All => MARKETS.load(_deps.storage, index)?,
+                MarketType::Active => ACTIVE_MARKETS.load(_deps.storage, index)?,
+                MarketType::Completed => COMPLETED_MARKETS.load(_deps.storage, index)?
+            };
+            
+            let msg = QueryMarketMsg::GetInfo {
+                account: account.clone()
+            };
+
+            let query_msg = &QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: market_at.to_string(),
+                msg: to_json_binary(&msg)?,
+            });
+
+            let data: Data = _deps.querier.query(query_msg)?;
+
+            let information: Information = data.information;
+
+            paginated_markets.push(information);
+            contract_addresses.push(market_at);
+            indexes.push(index);
+
+            current_index = Uint128::from(current_index.u128() - 1u128);
+
+        }
+
+        let market_list: MarketList = MarketList {
+            information: paginated_markets,
+            contracts: contract_addresses,
+            indexes,
+            next_start_after: None
+        };
+
+        Ok(market_list)
+
+    }
+
+    pub fn fetch_markets_by_status(_deps: Deps, _env: Env, status: MarketStatus, account: Addr, start_after: Option<u128>, limit: Option<u32>) -> StdResult<MarketList> {
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let end = start_after.map(Bound::exclusive);
+        let now = _env.block.time.seconds();
+
+        let mut market_list = MarketList {
+            information: vec![],
+            contracts: vec![],
+            indexes: vec![],
+            next_start_after: None
+        };
+
+        // Trading closes with time, so those markets are still stored as open
+        let stored = if status == MarketStatus::TradingClosed { MarketStatus::Open } else { status };
+
+        for (scanned, item) in STATUS_MARKETS.prefix(stored as u8).range(_deps.storage, None, end, Order::Descending).enumerate() {
+
+            let (index, market) = item?;
+
+            let market_end = MARKET_ENDS.may_load(_deps.storage, market.clone())?.unwrap_or(u64::MAX);
+
+            if stored.at(now, market_end) == status {
+
+                let query_msg = QueryRequest::Wasm(WasmQuery::Smart {
+                    contract_addr: market.to_string(),
+                    msg: to_json_binary(&QueryMarketMsg::GetInfo { account: account.clone() })?,
+                });
+
+                let data: Data = _deps.querier.query(&query_msg)?;
+
+                market_list.information.push(data.information);
+                market_list.contracts.push(market);
+                market_list.indexes.push(index);
+
+            }
+
+            if market_list.contracts.len() == limit || scanned + 1 == MAX_SCAN {
+                market_list.next_start_after = Some(index);
+                break;
+            }
+
+        }
+
+        Ok(market_list)
+
+    }
+
+    pub fn fetch_markets_by_category(_deps: Deps, _env: Env, category: String, status: Option<MarketStatus>, start_after: Option<u128>, limit: Option<u32>) -> StdResult<MarketList> {
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let end = start_after.map(Bound::exclusive);
+        let now = _env.block.time.seconds();
+
+        let mut market_list = MarketList {
+            information: vec![],
+            contracts: vec![],
+            indexes: vec![],
+            next_start_after: None
+        };
+
+        for (scanned, item) in CATEGORY_MARKETS.prefix(category).range(_deps.storage, None, end, Order::Descending).enumerate() {
+
+            let (index, market) = item?;
+
+            let matches = match &status {
+                Some(status) => {
+                    let stored = MARKET_STATUS.may_load(_deps.storage, market.clone())?.unwrap_or_default();
+                    let market_end = MARKET_ENDS.may_load(_deps.storage, market.clone())?.unwrap_or(u64::MAX);
+                    stored.at(now, market_end) == *status
+                },
+                None => true
+            };
+
+            if matches {
+
+                let data: Data = _deps.querier.query_wasm_smart(
+                    market.to_string(),
+                    &QueryMarketMsg::GetInfo { account: _env.contract.address.clone() }
+                )?;
+
+                market_list.information.push(data.information);
+                market_list.contracts.push(market);
+                market_list.indexes.push(index);
+
+            }
+
+            if market_list.contracts.len() == limit || scanned + 1 == MAX_SCAN {
+                market_list.next_start_after = Some(index);
+                break;
+            }
+
+        }
+
+        Ok(market_list)
+
+    }
+
+    pub fn search_markets(_deps: Deps, _env: Env, filter: MarketFilter, sort_by: MarketSort, descending: bool, start_after: Option<u128>, limit: Option<u32>) -> StdResult<MarketList> {
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let now = _env.block.time.seconds();
+        let order = if descending { Order::Descending } else { Order::Ascending };
+
+        let cursor = match start_after {
+            Some(index) => Some((index, MARKETS.load(_deps.storage, index)?)),
+            None => None
+        };
+
+        // Markets in sort order, walking the narrowest index available
+        let candidates: Box<dyn Iterator<Item = StdResult<(u128, Addr)>>> = match sort_by {
+            MarketSort::Created => {
+                let (min, max) = page_bounds(order, cursor.map(|(index, _)| Bound::exclusive(index)), None, None);
+                match &filter.owner {
+                    Some(owner) => Box::new(OWNER_MARKETS.prefix(owner.clone()).range(_deps.storage, min, max, order)),
+                    None => Box::new(MARKETS.range(_deps.storage, min, max, order))
+                }
+            },
+            MarketSort::EndDate => {
+                let cursor = match cursor {
+                    Some((index, market)) => Some(Bound::exclusive((MARKET_ENDS.may_load(_deps.storage, market)?.unwrap_or_default(), index))),
+                    None => None
+                };
+                let (min, max) = page_bounds(
+                    order,
+                    cursor,
+                    filter.ends_after.map(|time| Bound::inclusive((time, 0u128))),
+                    filter.ends_before.map(|time| Bound::exclusive((time, 0u128)))
+                );
+                Box::new(END_DATE_MARKETS
+                    .range(_deps.storage, min, max, order)
+                    .map(|item| item.map(|((_, index), market)| (index, market))))
+            },
+            MarketSort::Volume => {
+                let cursor = match cursor {
+                    Some((index, market)) => {
+                        let volume = VOLUMES.may_load(_deps.storage, market.clone())?.unwrap_or_default();
+                        let collateral = execute_msg::market_collateral(_deps.storage, &market)?;
+                        Some(Bound::exclusive((execute_msg::normalize_amount(_deps.storage, &collateral, volume)?.u128(), index)))
+                    },
+                    None => None
+                };
+                let (min, max) = page_bounds(order, cursor, None, None);
+                Box::new(VOLUME_MARKETS
+                    .range(_deps.storage, min, max, order)
+                    .map(|item| item.map(|((_, index), market)| (index, market))))
+            }
+        };
+
+        let mut market_list = MarketList {
+            information: vec![],
+            contracts: vec![],
+            indexes: vec![],
+            next_start_after: None
+        };
+
+        for (scanned, item) in candidates.enumerate() {
+
+            let (index, market) = item?;
+
+            if let Some(information) = search_match(_deps, &_env, &filter, now, index, &market)? {
+                market_list.information.push(information);
+                market_list.contracts.push(market);
+                market_list.indexes.push(index);
+            }
+
+            if market_list.contracts.len() == limit || scanned + 1 == MAX_SCAN {
+                market_list.next_start_after = Some(index);
+                break;
+            }
+
+        }
+
+        Ok(market_list)
+
+    }
+
+    /// The information of `market` if it passes `filter`, index lookups first
+    /// so the market is only queried when they all match
+    fn search_match(_deps: Deps, _env: &Env, filter: &MarketFilter, now: u64, index: u128, market: &Addr) -> StdResult<Option<Information>> {
+
+        if let Some(owner) = &filter.owner {
+            if !OWNER_MARKETS.has(_deps.storage, (owner.clone(), index)) {
+                return Ok(None);
+            }
+        }
+
+        if let Some(category) = &filter.category {
+            if !CATEGORY_MARKETS.has(_deps.storage, (category.clone(), index)) {
+                return Ok(None);
+            }
+        }
+
+        let market_end = MARKET_ENDS.may_load(_deps.storage, market.clone())?.unwrap_or(u64::MAX);
+
+        if filter.ends_after.is_some_and(|time| market_end < time) || filter.ends_before.is_some_and(|time| market_end >= time) {
+            return Ok(None);
+        }
+
+        if let Some(status) = &filter.status {
+            let stored = MARKET_STATUS.may_load(_deps.storage, market.clone())?.unwrap_or_default();
+            if stored.at(now, market_end) != *status {
+                return Ok(None);
+            }
+        }
+
+        let data: Data = _deps.querier.query_wasm_smart(
+            market.to_string(),
+            &QueryMarketMsg::GetInfo { account: _env.contract.address.clone() }
+        )?;
+
+        let liquidity = data.information.yes_liquidity + data.information.no_liquidity;
+
+        if filter.min_liquidity.is_some_and(|min| liquidity < min) || filter.max_liquidity.is_some_and(|max| liquidity > max) {
+            return Ok(None);
+        }
+
+        if let Some(outcome) = filter.outcome {
+            if !data.information.resolved || data.information.resolved_to != Uint128::from(outcome) {
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(data.information))
+
+    }
+
+    /// Range bounds of a page, the cursor replacing the bound the iteration starts from
+    fn page_bounds<'a, K: PrimaryKey<'a>>(order: Order, cursor: Option<Bound<'a, K>>, min: Option<Bound<'a, K>>, max: Option<Bound<'a, K>>) -> (Option<Bound<'a, K>>, Option<Bound<'a, K>>) {
+
+        match order {
+            Order::Ascending => (cursor.or(min), max),
+            Order::Descending => (min, cursor.or(max))
+        }
+
+    }
+
+    pub fn list_categories(_deps: Deps, _env: Env, start_after: Option<String>, limit: Option<u32>) -> StdResult<Vec<CategoryInfo>> {
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        CATEGORY_COUNTS
+            .range(_deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(name, markets)| CategoryInfo {
+                registered: CATEGORIES.has(_deps.storage, name.clone()),
+                name,
+                markets
+            }))
+            .collect()
+
+    }
+
+    pub fn list_trading_grants(_deps: Deps, _env: Env, granter: Addr, start_after: Option<Addr>, limit: Option<u32>) -> StdResult<Vec<TradingGrant>> {
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let grants = TRADING_GRANTS
+            .prefix(granter)
+            .range(_deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, grant)| grant))
+            .collect::<StdResult<Vec<TradingGrant>>>()?;
+
+        Ok(grants)
+
+    }
+
+    pub fn get_account_trades(_deps: Deps, _env: Env, account: Addr, start_after: Option<(Addr, u64)>, limit: Option<u32>) -> StdResult<Vec<MarketTrade>> {
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        // The cursor's market may still hold older trades, so it is included
+        let start = start_after.as_ref().map(|(market, _)| Bound::inclusive(market.clone()));
+
+        let markets = ACCOUNT_MARKETS
+            .prefix(account.clone())
+            .keys(_deps.storage, start, None, Order::Ascending);
+
+        let mut account_trades: Vec<MarketTrade> = vec![];
+
+        for market in markets {
+
+            let market = market?;
+
+            let trades_after = match &start_after {
+                Some((cursor_market, id)) if *cursor_market == market => Some(*id),
+                _ => None
+            };
+
+            let trades: Vec<Trade> = _deps.querier.query_wasm_smart(
+                market.to_string(),
+                &QueryMarketMsg::GetAccountTrades {
+                    account: account.clone(),
+                    start_after: trades_after,
+                    limit: Some((limit - account_trades.len()) as u32)
+                }
+            )?;
+
+            account_trades.extend(trades.into_iter().map(|trade| MarketTrade {
+                market: market.clone(),
+                trade
+            }));
+
+            if account_trades.len() >= limit {
+                break;
+            }
+
+        }
+
+        Ok(account_trades)
+
+    }
+
+    pub fn get_portfolio(deps: Deps, _env: Env, account: Addr, start_after: Option<Addr>, limit: Option<u32>) -> StdResult<Portfolio> {
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        // One extra key tells whether another page follows
+        let markets = ACCOUNT_MARKETS
+            .prefix(account.clone())
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit + 1)
+            .collect::<StdResult<Vec<Addr>>>()?;
+
+        let mut portfolio = Portfolio { entries: vec![], unavailable: vec![], next_start_after: None };
+
+        if limit > 0 && markets.len() > limit {
+            portfolio.next_start_after = Some(markets[limit - 1].clone());
+        }
+
+        for market in markets.into_iter().take(limit) {
+
+            // One market failing to answer must not hide the rest of the portfolio
+            let data: Data = match deps.querier.query_wasm_smart(
+                market.to_string(),
+                &QueryMarketMsg::GetInfo { account: account.clone() }
+            ) {
+                Ok(data) => data,
+                Err(_) => {
+                    portfolio.unavailable.push(market);
+                    continue;
+                }
+            };
+
+            let shares = data.shares;
+            let information = data.information;
+
+            if shares.yes_shares.is_zero() && shares.no_shares.is_zero() && shares.liquidity_shares.is_zero() {
+                continue;
+            }
+
+            let position: PositionInfo = match deps.querier.query_wasm_smart(
+                market.to_string(),
+                &QueryMarketMsg::GetPosition { account: account.clone() }
+            ) {
+                Ok(position) => position,
+                Err(_) => {
+                    portfolio.unavailable.push(market);
+                    continue;
+                }
+            };
+
+            let liquidity_value = if information.liquidity_shares.is_zero() {
+                Uint128::from(0u128)
+            }
+            else {
+                (information.yes_liquidity + information.no_liquidity).multiply_ratio(shares.liquidity_shares, information.liquidity_shares)
+            };
+
+            let claimable = if !information.resolved {
+                Uint128::from(0u128)
+            }
+            else if information.resolved_to == Uint128::from(Outcome::Yes) {
+                position.yes_value
+            }
+            else {
+                position.no_value
+            };
+
+            portfolio.entries.push(PortfolioEntry {
+                market,
+                title: information.title,
+                yes_shares: shares.yes_shares,
+                no_shares: shares.no_shares,
+                liquidity_shares: shares.liquidity_shares,
+                position_value: position.yes_value + position.no_value,
+                liquidity_value,
+                resolved: information.resolved,
+                claimable
+            });
+
+        }
+
+        Ok(portfolio)
+
+    }
+
+}
@@ -1,7 +1,9 @@
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Binary, Uint128};
 use cw_storage_plus::{Item, Map};
 
-use packages::factory::{Details, Statistics, TempInformation};
+use packages::factory::{AccountStats, DailyStatistics, Details, Statistics, TempInformation, TradingGrant, PendingBatchOrder, PendingConfig, PendingSuperAdmin, Role, CollateralVolume, ReferralEarnings, PointsConfig, EpochReward, LiquidityPosition, LiquidityRewards};
+use packages::market::MarketStatus;
+use packages::collateral::{Collateral, CollateralConfig};
 
 pub const ACTIVE_MARKETS: Map<u128, Addr> = Map::new("active_markets");
 pub const MARKETS: Map<u128, Addr> = Map::new("all_markets");
@@ -10,8 +12,85 @@ pub const VOLUMES: Map<Addr, Uint128> = Map::new("volumes");
 pub const KNOWN_MARKETS: Map<Addr, bool> = Map::new("known_markets");
 pub const UNIQUE_WALLETS: Map<Addr, bool> = Map::new("unique_wallets");
 pub const MEDIA: Map<Addr, Vec<String>> = Map::new("media");
+/// Registered categories, new markets may only use these once any is registered
+pub const CATEGORIES: Map<String, bool> = Map::new("categories");
+/// Markets keyed by category and market index
+pub const CATEGORY_MARKETS: Map<(String, u128), Addr> = Map::new("category_markets");
+pub const CATEGORY_COUNTS: Map<String, u64> = Map::new("category_counts");
+/// Index of each market in `MARKETS`
+pub const MARKET_INDEXES: Map<Addr, u128> = Map::new("market_indexes");
+/// Search indexes keyed by owner, end date or volume, then market index
+pub const OWNER_MARKETS: Map<(Addr, u128), Addr> = Map::new("owner_markets");
+pub const END_DATE_MARKETS: Map<(u64, u128), Addr> = Map::new("end_date_markets");
+pub const VOLUME_MARKETS: Map<(u128, u128), Addr> = Map::new("volume_markets");
+/// Admin flags from before roles existed, moved to `ROLES` on migrate
 pub const ADMINS_MAP: Map<Addr, bool> = Map::new("admins_map");
 pub const TEMP_INFORMATION: Item<TempInformation> = Item::new("information");
 pub const STATISTICS: Item<Statistics> = Item::new("statistics");
+/// Statistics bucketed by UTC day, keyed by days since the Unix epoch
+pub const DAILY_STATISTICS: Map<u64, DailyStatistics> = Map::new("daily_statistics");
 pub const DETAILS: Item<Details> = Item::new("details");
-pub const INCENTIVES: Map<Addr, u64> = Map::new("incentives");
\ No newline at end of file
+pub const INCENTIVES: Map<Addr, u64> = Map::new("incentives");
+/// Internal balances keyed by account and collateral denom or token address
+pub const BALANCES: Map<(Addr, String), Uint128> = Map::new("balances");
+pub const NONCES: Map<Addr, u64> = Map::new("nonces");
+pub const SIGNING_KEYS: Map<Addr, Binary> = Map::new("signing_keys");
+
+pub const TRADING_GRANTS: Map<(Addr, Addr), TradingGrant> = Map::new("trading_grants");
+pub const BATCH_ORDERS: Map<u64, PendingBatchOrder> = Map::new("batch_orders");
+pub const ACCOUNT_MARKETS: Map<(Addr, Addr), bool> = Map::new("account_markets");
+pub const MARKET_STATUS: Map<Addr, MarketStatus> = Map::new("market_status");
+pub const MARKET_ENDS: Map<Addr, u64> = Map::new("market_ends");
+/// Markets keyed by stored status (`MarketStatus as u8`) and market index.
+/// Open markets past their end date are still stored as open.
+pub const STATUS_MARKETS: Map<(u8, u128), Addr> = Map::new("status_markets");
+pub const MARKET_COLLATERAL: Map<Addr, Collateral> = Map::new("market_collateral");
+/// Markets created before `MARKET_COLLATERAL` that `MigrateMarkets` has not
+/// recorded yet. Until it reaches 0 the default collateral cannot change.
+pub const UNRECORDED_COLLATERALS: Item<u128> = Item::new("unrecorded_collaterals");
+/// Fees addresses replaced by a config update, with the time they were replaced.
+/// Markets move the fee shares these still hold to the current one on `CollectFees`.
+pub const FORMER_FEES_ADDRESSES: Map<Addr, u64> = Map::new("former_fees_addresses");
+pub const PENDING_CONFIG: Item<PendingConfig> = Item::new("pending_config");
+pub const ROLES: Map<Addr, Vec<Role>> = Map::new("roles");
+pub const SUPER_ADMINS: Item<u32> = Item::new("super_admins");
+pub const PENDING_SUPER_ADMIN: Item<PendingSuperAdmin> = Item::new("pending_super_admin");
+pub const PAUSED: Item<bool> = Item::new("paused");
+/// Whitelisted collaterals keyed by denom or token address
+pub const COLLATERALS: Map<String, CollateralConfig> = Map::new("collaterals");
+pub const COLLATERAL_VOLUMES: Map<String, CollateralVolume> = Map::new("collateral_volumes");
+/// Protocol fee revenue per market, in the market's collateral
+pub const FEE_REVENUE: Map<Addr, Uint128> = Map::new("fee_revenue");
+
+/// The referrer of each trading account, set on its first trade
+pub const REFERRERS: Map<Addr, Addr> = Map::new("referrers");
+/// Referees keyed by referrer and referee
+pub const REFEREES: Map<(Addr, Addr), bool> = Map::new("referees");
+pub const REFEREE_COUNTS: Map<Addr, u64> = Map::new("referee_counts");
+pub const REFERRAL_VOLUMES: Map<Addr, Uint128> = Map::new("referral_volumes");
+/// Referral earnings keyed by referrer and denom or token address
+pub const REFERRAL_EARNINGS: Map<(Addr, String), ReferralEarnings> = Map::new("referral_earnings");
+/// Referrers' cut of the fee going to the fees address in basis points, 0 when unset
+pub const REFERRAL_SHARE: Item<u16> = Item::new("referral_share");
+
+pub const POINTS_CONFIG: Item<PointsConfig> = Item::new("points_config");
+pub const TOTAL_POINTS: Map<Addr, Uint128> = Map::new("total_points");
+/// Points keyed by epoch and account
+pub const EPOCH_POINTS: Map<(u64, Addr), Uint128> = Map::new("epoch_points");
+pub const EPOCH_TOTALS: Map<u64, Uint128> = Map::new("epoch_totals");
+pub const EPOCH_REWARDS: Map<u64, EpochReward> = Map::new("epoch_rewards");
+/// Rewards already claimed, keyed by epoch and account
+pub const EPOCH_CLAIMS: Map<(u64, Addr), Uint128> = Map::new("epoch_claims");
+/// Liquidity earning points and liquidity mining rewards, keyed by account and market
+pub const LIQUIDITY_POSITIONS: Map<(Addr, Addr), LiquidityPosition> = Map::new("liquidity_positions");
+
+/// Liquidity mining programs keyed by market
+pub const LIQUIDITY_REWARDS: Map<Addr, LiquidityRewards> = Map::new("liquidity_rewards");
+
+/// Account trading totals keyed by scope and account. The scope is a points
+/// epoch, or `ALL_TIME` for lifetime totals.
+pub const ACCOUNT_STATS: Map<(u64, Addr), AccountStats> = Map::new("account_stats");
+/// Leaderboard indexes keyed by scope, value and account
+pub const VOLUME_RANKING: Map<(u64, u128, Addr), bool> = Map::new("volume_ranking");
+pub const PNL_RANKING: Map<(u64, i128, Addr), bool> = Map::new("pnl_ranking");
+pub const ALL_TIME: u64 = u64::MAX;
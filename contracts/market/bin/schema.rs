@@ -1,11 +1,12 @@
 use cosmwasm_schema::write_api;
 
-use packages::market::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use packages::market::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 
 fn main() {
     write_api! {
         instantiate: InstantiateMsg,
         execute: ExecuteMsg,
         query: QueryMsg,
+        migrate: MigrateMsg,
     }
 }
@@ -2,13 +2,20 @@ use crate::state::{INFORMATION, TOTAL_ORDERS};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    from_json, to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response,
     Uint128,
 };
-use packages::market::{ExecuteMsg, InstantiateMsg, Information, QueryMsg};
+use cw20::Cw20ReceiveMsg;
+use packages::collateral::Collateral;
+use packages::market::{ExecuteMsg, InstantiateMsg, Information, MarketStatus, MigrateMsg, QueryMsg};
+use cw2::{get_contract_version, set_contract_version, ContractVersion};
 
 use crate::execute::execute_msg;
 use crate::query::query_msg;
+use crate::ContractError;
+
+const CONTRACT_NAME: &str = concat!("crates.io:", env!("CARGO_PKG_NAME"));
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -16,7 +23,7 @@ pub fn instantiate(
     env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
 
     let market_info: Information = Information {
         title: msg.title,
@@ -32,14 +39,21 @@ pub fn instantiate(
         categories: msg.categories,
         liquidity_shares: Uint128::from(0u128),
         usdc: msg.usdc,
+        decimals: msg.decimals,
+        min_liquidity: msg.min_liquidity,
         owner: msg.owner,
         resolved: false,
         factory: msg.factory,
-        resolved_to: Uint128::from(0u128)
+        resolved_to: Uint128::from(0u128),
+        status: MarketStatus::Created,
+        proposed_to: None,
+        proposed_at: None
     };
 
     INFORMATION.save(deps.storage, &market_info)?;
 
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     TOTAL_ORDERS.save(deps.storage, &Uint128::from(0u128))?;
 
     Ok(Response::new())
@@ -47,7 +61,7 @@ pub fn instantiate(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::InitializeLiquidity {
             yes_price,
@@ -69,24 +83,103 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
         ExecuteMsg::RemoveLiquidity { shares, receiver } => {
             execute_msg::remove_liquidity(deps, env, info, shares, receiver)
         },
-        ExecuteMsg::Claim { variant, receiver } => {
-            execute_msg::claim(deps, env, info, variant, receiver)
+        ExecuteMsg::Claim { variant, receiver, credit_balance } => {
+            execute_msg::claim(deps, env, info, variant, receiver, credit_balance)
         },
         ExecuteMsg::ResolveMarket { variant, receiver, market_index } => {
             execute_msg::resolve_market(deps, env, info, variant, receiver, market_index)
         },
-        ExecuteMsg::PlaceOrder { variant, buy_or_sell, amount, receiver } => {
-            execute_msg::place_order(deps, env, info, variant, buy_or_sell, amount, receiver)
+        ExecuteMsg::PlaceOrder { variant, buy_or_sell, amount, receiver, min_out, credit_balance, referral } => {
+            execute_msg::place_order(deps, env, info, variant, buy_or_sell, amount, receiver, min_out, credit_balance, referral)
+        },
+        ExecuteMsg::ProposeResolution { variant, receiver } => {
+            execute_msg::propose_resolution(deps, env, info, variant, receiver)
+        },
+        ExecuteMsg::FinalizeMarket { receiver } => {
+            execute_msg::finalize_market(deps, env, info, receiver)
+        },
+        ExecuteMsg::CancelMarket { receiver, market_index } => {
+            execute_msg::cancel_market(deps, env, info, receiver, market_index)
+        },
+        ExecuteMsg::CollectFees { former_fees_addresses } => execute_msg::collect_fees(deps, env, info, former_fees_addresses),
+        ExecuteMsg::Receive(wrapper) => receive(deps, env, info, wrapper)
+    }
+}
+
+/// Unwraps a message sent by the factory together with CW20 collateral and
+/// handles it as if the factory had called the market directly.
+fn receive(deps: DepsMut, env: Env, info: MessageInfo, wrapper: Cw20ReceiveMsg) -> Result<Response, ContractError> {
+
+    let information = INFORMATION.load(deps.storage)?;
+
+    let collateral = Collateral::CW20Token { contract_address: info.sender };
+
+    if information.usdc != collateral || wrapper.sender != information.factory {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let msg: ExecuteMsg = from_json(&wrapper.msg)?;
+
+    if let ExecuteMsg::Receive(_) = msg {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let info = MessageInfo {
+        sender: information.factory,
+        funds: vec![]
+    };
+
+    execute(deps, env, info, msg)
+
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+
+    match get_contract_version(deps.storage) {
+        Ok(stored) => ensure_upgrade(&stored)?,
+        // Markets deployed before versioning have no lifecycle status yet
+        Err(_) => {
+            let mut information = INFORMATION.load(deps.storage)?;
+            information.status = information.inferred_status();
+            INFORMATION.save(deps.storage, &information)?;
         }
     }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("version", CONTRACT_VERSION))
+
+}
+
+fn ensure_upgrade(stored: &ContractVersion) -> Result<(), ContractError> {
+
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidMigration { previous_contract: stored.contract.clone() });
+    }
+
+    if semver::Version::parse(&stored.version)? > semver::Version::parse(CONTRACT_VERSION)? {
+        return Err(ContractError::CannotDowngrade { previous: stored.version.clone(), current: CONTRACT_VERSION.to_string() });
+    }
+
+    Ok(())
+
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    let response = match msg {
         QueryMsg::GetInfo { account } => to_json_binary(&query_msg::get_info(deps, env, account)?),
         QueryMsg::Quote { variant, buy_or_sell, amount } => to_json_binary(&execute_msg::quote(deps, env, variant, buy_or_sell, amount)?),
         QueryMsg::GetTotalOrders { } => to_json_binary(&query_msg::get_total_orders(deps, env)?),
         QueryMsg::GetOrders { page, items_per_page } => to_json_binary(&query_msg::get_orders(deps, env, page, items_per_page)?),
-    }
+        QueryMsg::SimulateAddLiquidity { amount } => to_json_binary(&query_msg::simulate_add_liquidity(deps, env, amount)?),
+        QueryMsg::SimulateRemoveLiquidity { shares } => to_json_binary(&query_msg::simulate_remove_liquidity(deps, env, shares)?),
+        QueryMsg::GetPosition { account } => to_json_binary(&query_msg::get_position(deps, env, account)?),
+        QueryMsg::GetAccountTrades { account, start_after, limit } => to_json_binary(&query_msg::get_account_trades(deps, env, account, start_after, limit)?),
+    };
+
+    Ok(response?)
 }
\ No newline at end of file
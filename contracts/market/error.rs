@@ -1,4 +1,5 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
+use packages::market::{MarketStatus, Outcome};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,8 +7,87 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Semver(#[from] semver::Error),
+
     #[error("Unauthorized")]
     Unauthorized {},
-    // Add any other custom errors you like here.
-    // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
+
+    #[error("Cannot migrate from {previous_contract}")]
+    InvalidMigration { previous_contract: String },
+
+    #[error("Cannot migrate from version {previous} to older version {current}")]
+    CannotDowngrade { previous: String, current: String },
+
+    #[error("Only owner or admins can {action}")]
+    NotOwnerOrAdmin { action: String },
+
+    #[error("Market has already been resolved")]
+    MarketResolved {},
+
+    #[error("Cannot {action} while market is {status:?}")]
+    InvalidStatus { action: String, status: MarketStatus },
+
+    #[error("No resolution has been proposed")]
+    NoProposal {},
+
+    #[error("Can only resolve to the proposed outcome {proposed}")]
+    ProposalMismatch { proposed: Outcome },
+
+    #[error("Resolution proposal can be challenged until {resolvable_at}")]
+    ChallengePeriodActive { resolvable_at: u64 },
+
+    #[error("Page must be greater than 0")]
+    ZeroPage {},
+
+    #[error("Items per page must be greater than 0")]
+    ZeroItemsPerPage {},
+
+    #[error("Page is out of bounds")]
+    PageOutOfBounds {},
+
+    #[error("No order exists at the moment")]
+    NoOrders {},
+
+    #[error("No order found for this page")]
+    EmptyPage {},
+
+    #[error("Liquidity has not been initialized")]
+    LiquidityNotInitialized {},
+
+    #[error("Amount must be greater than 0")]
+    ZeroAmount {},
+
+    #[error("Price impact {impact} exceeds the maximum of {max}")]
+    ImpactTooHigh { impact: Uint128, max: Uint128 },
+
+    #[error("Amount out {amount_out} is less than the minimum expected {min_out}")]
+    SlippageExceeded { amount_out: Uint128, min_out: Uint128 },
+
+    #[error("Insufficient shares: have {have}, want {want}")]
+    InsufficientShares { have: Uint128, want: Uint128 },
+
+    #[error("Liquidity must be at least {min}")]
+    LiquidityTooLow { min: Uint128 },
+
+    #[error("There must be at least {min} liquidity leftover")]
+    MinimumLiquidity { min: Uint128 },
+
+    #[error("Initial probability must be up to 10% and less than or equal to 90%")]
+    InvalidInitialPrice {},
+
+    #[error("Amount is too small")]
+    AmountTooSmall {},
+
+    #[error("Must own shares to claim")]
+    NothingToClaim {},
+
+    #[error("Can only claim the winning outcome")]
+    NotWinningOutcome {},
+
+    #[error("All winning shares must be claimed before finalizing")]
+    UnclaimedShares {},
+
+    #[error("Cannot cancel a market that has been traded")]
+    MarketTraded {},
 }
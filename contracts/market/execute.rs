@@ -1,20 +1,21 @@
-use crate::state::{INFORMATION, SHARES, TOTAL_ORDERS, ORDER_LIST};
+#![allow(clippy::too_many_arguments)]
+
+use crate::state::{INFORMATION, SHARES, TOTAL_ORDERS, ORDER_LIST, POSITIONS, trades};
 
 #[cfg(not(feature = "library"))]
+use cosmwasm_std::{Deps, to_json_binary, DepsMut, Env, MessageInfo, Response, StdResult, QueryRequest, WasmQuery, Binary, Storage};
 
-use cosmwasm_std::{Deps, to_json_binary, DepsMut, BalanceResponse, Env, MessageInfo, Response, StdError, StdResult, QueryRequest, WasmQuery, Binary, BankQuery, Coin, BankMsg};
+use packages::market::{Shares, Quote, Order, Information, MarketStatus, Outcome, Referral, Side, Trade, RESOLVE_DURATION};
 
-use packages::market::{Shares, Quote, Order};
+use crate::ContractError;
 
-use packages::factory::{ExecuteMsg as ExecuteFactoryMsg, QueryMsg as QueryFactoryMsg};
+use packages::factory::{ExecuteMsg as ExecuteFactoryMsg, QueryMsg as QueryFactoryMsg, Role};
 
 pub mod execute_msg {
 
-    use cosmwasm_std::{Addr, CosmosMsg, Isqrt, Uint128, WasmMsg};
-
-    const MULTIPLIER:u128 = 10u128.pow(8);
+    use cosmwasm_std::{Addr, CosmosMsg, Int128, Isqrt, Uint128, WasmMsg};
 
-    const RESOLVE_DURATION:u64 = 180; // 5 minutes
+    pub(crate) const MULTIPLIER:u128 = 10u128.pow(8);
 
     use super::*;
 
@@ -25,45 +26,30 @@ pub mod execute_msg {
         yes_price: Uint128,
         liquidity: Uint128,
         receiver: Addr
-    ) -> StdResult<Response> {
+    ) -> Result<Response, ContractError> {
 
         let mut info = INFORMATION.load(deps.storage)?;
 
         let account = info_.sender;
 
-        let msg = QueryFactoryMsg::IsAdmin { account: receiver.clone() };
-        
-        let query_msg = QueryRequest::Wasm(WasmQuery::Smart {
-            contract_addr: info.factory.to_string(),
-            msg: to_json_binary(&msg)?,
-        });
-
-        let is_admin: bool = deps.querier.query(&query_msg)?;
-
-        if receiver.clone() != info.owner && !is_admin  {
-            return Err(StdError::generic_err("Only owner or admins can initialize price"));
-        }
+        ensure_owner_or_admin(deps.as_ref(), &info, &receiver, Role::MarketCreator, "initialize price")?;
 
         if account != info.factory {
-            return Err(StdError::generic_err("Only callable by factory"));
+            return Err(ContractError::Unauthorized {});
         }
 
-        if liquidity < Uint128::from(10u128 * 10u128.pow(6u32)) {
-            return Err(StdError::generic_err("Liquidity must be up to 10 USD"));
-        }
-        if info.resolved {
-            return Err(StdError::generic_err("Market has already been resolved"));
-        }
-        if info.yes_price != Uint128::from(0u128) {
-            return Err(StdError::generic_err("Liquidity has already been initialized"));
+        if liquidity < info.min_liquidity {
+            return Err(ContractError::LiquidityTooLow { min: info.min_liquidity });
         }
+        ensure_status(&info, &env, &[MarketStatus::Created], "initialize liquidity")?;
         if yes_price < Uint128::from(MULTIPLIER) / Uint128::from(10u128) || yes_price > Uint128::from(9u128) * (Uint128::from(MULTIPLIER) / Uint128::from(10u128)) {
-            return Err(StdError::generic_err("Initial probability must be up to 10% and less than or equal to 90%"));
+            return Err(ContractError::InvalidInitialPrice {});
         }
 
         let no_price = Uint128::from(MULTIPLIER) - yes_price;
         info.yes_price = yes_price;
         info.no_price = no_price;
+        info.status = MarketStatus::Open;
 
         let timestamp = env.block.time.seconds();
 
@@ -92,7 +78,27 @@ pub mod execute_msg {
         INFORMATION.save(deps.storage, &info)?;
         SHARES.save(deps.storage, receiver.clone(), &shares)?;
 
-        Ok(Response::new())
+        Ok(Response::new().add_message(liquidity_hook(&info, &receiver, &shares)?))
+
+    }
+
+    /// Reports `receiver`'s liquidity shares, the market's total shares and
+    /// the pool they split to the factory, which values the account's
+    /// liquidity for points and splits liquidity mining rewards by them
+    fn liquidity_hook(info: &Information, receiver: &Addr, shares: &Shares) -> StdResult<CosmosMsg> {
+
+        let msg = ExecuteFactoryMsg::RecordStats {
+            amount: shares.liquidity_shares,
+            account: receiver.clone(),
+            stat_type: String::from("liquidity"),
+            data: vec![info.liquidity_shares, info.yes_liquidity + info.no_liquidity]
+        };
+
+        Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: info.factory.to_string(),
+            msg: to_json_binary(&msg)?,
+            funds: vec![]
+        }))
 
     }
 
@@ -110,34 +116,26 @@ pub mod execute_msg {
 
     pub fn add_liquidity(
         deps: DepsMut,
-        _env: Env,
+        env: Env,
         info_: MessageInfo,
         amount: Uint128,
         receiver: Addr
-    ) -> StdResult<Response> {
+    ) -> Result<Response, ContractError> {
 
         let mut info = INFORMATION.load(deps.storage)?;
 
         let account = info_.sender;
 
         if account != info.factory {
-            return Err(StdError::generic_err("Only callable by factory"));
+            return Err(ContractError::Unauthorized {});
         }
 
-        if info.yes_price == Uint128::from(0u128) {
-            return Err(StdError::generic_err("Liquidity has not been initialized"));
-        }
-        if info.resolved {
-            return Err(StdError::generic_err("Market has been resolved. Cannot add liquidity."));
-        }
+        ensure_status(&info, &env, &[MarketStatus::Open], "add liquidity")?;
 
-        let yes_liquidity = (info.yes_price * amount) / Uint128::from(MULTIPLIER);
-        let no_liquidity = (info.no_price * amount) / Uint128::from(MULTIPLIER);
-
-        let shares_to_give = min((yes_liquidity * info.liquidity_shares) / info.yes_liquidity, (no_liquidity * info.liquidity_shares) / info.no_liquidity);
+        let (yes_liquidity, no_liquidity, shares_to_give) = liquidity_to_add(&info, amount);
 
         if shares_to_give == Uint128::from(0u128) {
-            return Err(StdError::generic_err("Must use higher deposit limit."));
+            return Err(ContractError::AmountTooSmall {});
         }
 
         let mut shares: Shares = SHARES.load(deps.storage, receiver.clone()).unwrap_or_else(|_| Shares::new());
@@ -151,7 +149,7 @@ pub mod execute_msg {
         SHARES.save(deps.storage, receiver.clone(), &shares)?;
         INFORMATION.save(deps.storage, &info)?;
 
-        Ok(Response::new())
+        Ok(Response::new().add_message(liquidity_hook(&info, &receiver, &shares)?))
 
     }
 
@@ -161,74 +159,113 @@ pub mod execute_msg {
         info_: MessageInfo,
         shares_: Uint128,
         receiver: Addr
-    ) -> StdResult<Response> {
+    ) -> Result<Response, ContractError> {
 
         let mut info = INFORMATION.load(deps.storage)?;
 
         let account = info_.sender;
 
         if account != info.factory {
-            return Err(StdError::generic_err("Only callable by factory"));
+            return Err(ContractError::Unauthorized {});
         }
 
+        ensure_status(&info, &_env, &[
+            MarketStatus::Open,
+            MarketStatus::TradingClosed,
+            MarketStatus::ResolutionProposed,
+            MarketStatus::Resolved,
+            MarketStatus::Finalized,
+            MarketStatus::Cancelled
+        ], "remove liquidity")?;
+
         let mut shares: Shares = SHARES.load(deps.storage, receiver.clone()).unwrap_or_else(|_| Shares::new());
 
         if shares.liquidity_shares < shares_ {
-            return Err(StdError::generic_err("User must own up to the specified amount of shares"));
+            return Err(ContractError::InsufficientShares { have: shares.liquidity_shares, want: shares_ });
         }
 
-        let yes_to_remove = (shares_ * info.yes_liquidity) / info.liquidity_shares;
-        let no_to_remove = (shares_ * info.no_liquidity) / info.liquidity_shares;
+        let usdc_balance = usdc_balance(deps.as_ref(), &_env, &info)?;
 
-        info.liquidity_shares -= shares_;
-        shares.liquidity_shares -= shares_;
-
-        let mut amount_to_remove = yes_to_remove + no_to_remove;
+        let removal = liquidity_to_remove(&info, shares_, usdc_balance);
 
-        if amount_to_remove == Uint128::from(0u128) {
-            return Err(StdError::generic_err("Shares too minute for withdrawal."));
+        if removal.amount == Uint128::from(0u128) {
+            return Err(ContractError::AmountTooSmall {});
         }
 
-        let usdc_balance: Uint128 = {
-            let request = QueryRequest::Bank(BankQuery::Balance {
-                denom: info.usdc.to_string(),
-                address: _env.contract.address.to_string()
-            });
-            let response: BalanceResponse = deps.querier.query(&request)?;
-            response.amount.amount
-        };
-
-        let total_balance = usdc_balance;
-
-        if amount_to_remove > total_balance {
-            amount_to_remove = total_balance;
+        if removal.below_minimum {
+            return Err(ContractError::MinimumLiquidity { min: info.min_liquidity });
         }
 
-        if !info.resolved && ((info.yes_liquidity + info.no_liquidity) - amount_to_remove < Uint128::from(10u128 * 10u128.pow(6u32))) {
-            return Err(StdError::generic_err("There must be at least 10 USDC leftover"));
-        }
+        info.liquidity_shares -= shares_;
+        shares.liquidity_shares -= shares_;
 
-        info.yes_liquidity -= yes_to_remove;
-        info.no_liquidity -= no_to_remove;
+        info.yes_liquidity -= removal.yes;
+        info.no_liquidity -= removal.no;
 
         let mut messages = vec![];
 
-        let xfer_funds = Coin {
-            denom: info.usdc.clone(),
-            amount: amount_to_remove
-        };
-
-        let asset_transfer = CosmosMsg::Bank(BankMsg::Send {
-            to_address: receiver.to_string(),
-            amount: vec![xfer_funds],
-        });
+        let asset_transfer = info.usdc.transfer_msg(&receiver, removal.amount)?;
         
         messages.push(asset_transfer);
+        messages.push(liquidity_hook(&info, &receiver, &shares)?);
 
         SHARES.save(deps.storage, receiver.clone(), &shares)?;
         INFORMATION.save(deps.storage, &info)?;
 
-        Ok(Response::new().add_messages(messages))
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_attribute("action", "remove_liquidity")
+            .add_attribute("amount", removal.amount)
+            .add_attribute("capped_to_balance", removal.capped.to_string()))
+
+    }
+
+    /// Splits a deposit of `amount` across both pools at current prices and
+    /// returns (yes liquidity, no liquidity, liquidity shares minted).
+    pub(crate) fn liquidity_to_add(info: &Information, amount: Uint128) -> (Uint128, Uint128, Uint128) {
+
+        let yes_liquidity = (info.yes_price * amount) / Uint128::from(MULTIPLIER);
+        let no_liquidity = (info.no_price * amount) / Uint128::from(MULTIPLIER);
+
+        let shares_to_give = min((yes_liquidity * info.liquidity_shares) / info.yes_liquidity, (no_liquidity * info.liquidity_shares) / info.no_liquidity);
+
+        (yes_liquidity, no_liquidity, shares_to_give)
+
+    }
+
+    pub(crate) struct LiquidityRemoval {
+        pub yes: Uint128,
+        pub no: Uint128,
+        pub amount: Uint128,
+        pub capped: bool,
+        pub below_minimum: bool
+    }
+
+    /// Pool amounts released by burning `shares`. The payout is capped to the
+    /// contract's USDC `balance`.
+    pub(crate) fn liquidity_to_remove(info: &Information, shares: Uint128, balance: Uint128) -> LiquidityRemoval {
+
+        let yes = (shares * info.yes_liquidity) / info.liquidity_shares;
+        let no = (shares * info.no_liquidity) / info.liquidity_shares;
+
+        let capped = yes + no > balance;
+        let amount = if capped { balance } else { yes + no };
+
+        let below_minimum = !info.status.is_settled() && ((info.yes_liquidity + info.no_liquidity) - amount < info.min_liquidity);
+
+        LiquidityRemoval {
+            yes,
+            no,
+            amount,
+            capped,
+            below_minimum
+        }
+
+    }
+
+    pub(crate) fn usdc_balance(deps: Deps, env: &Env, info: &Information) -> StdResult<Uint128> {
+
+        info.usdc.query_balance(&deps.querier, &env.contract.address)
 
     }
 
@@ -236,9 +273,10 @@ pub mod execute_msg {
         deps: DepsMut,
         _env: Env,
         info_: MessageInfo,
-        variant: Uint128,
-        receiver: Addr
-    ) -> StdResult<Response> {
+        variant: Outcome,
+        receiver: Addr,
+        credit_balance: bool
+    ) -> Result<Response, ContractError> {
 
         let mut info = INFORMATION.load(deps.storage)?;
 
@@ -247,90 +285,174 @@ pub mod execute_msg {
         let mut shares: Shares = SHARES.load(deps.storage, receiver.clone()).unwrap_or_else(|_| Shares::new());
 
         if account != info.factory {
-            return Err(StdError::generic_err("Only callable by factory"));
+            return Err(ContractError::Unauthorized {});
         }
 
-        if !info.resolved {
-            return Err(StdError::generic_err("Market must be resolved for user to claim"));
-        }
+        ensure_status(&info, &_env, &[MarketStatus::Resolved], "claim")?;
 
-        if variant != info.resolved_to {
-            return Err(StdError::generic_err("Can only claim from resolved (won) market"));
+        let winning = if info.resolved_to == Uint128::from(Outcome::Yes) { Outcome::Yes } else { Outcome::No };
+        let losing_shares = if winning == Outcome::Yes { shares.no_shares } else { shares.yes_shares };
+
+        if variant != winning && losing_shares.is_zero() {
+            return Err(ContractError::NotWinningOutcome {});
         }
 
         let mut messages = vec![];
+        let mut pnl = Int128::zero();
 
-        if variant == Uint128::from(1u128) {
+        if variant == winning {
 
-            let owned_shares = shares.yes_shares;
+            let owned_shares = if winning == Outcome::Yes { shares.yes_shares } else { shares.no_shares };
 
             if owned_shares == Uint128::from(0u128) {
-                return Err(StdError::generic_err("Must own shares to claim"));
+                return Err(ContractError::NothingToClaim {});
             }
 
-            let expected_usdc = (owned_shares * info.yes_price) / Uint128::from(MULTIPLIER);
-
-            let xfer_asset = Coin {
-                denom: info.usdc.clone(),
-                amount: expected_usdc.clone()
-            };
-    
-            let asset_transfer = CosmosMsg::Bank(BankMsg::Send {
-                to_address: receiver.clone().to_string(),
-                amount: vec![xfer_asset],
-            });
+            let price = if winning == Outcome::Yes { info.yes_price } else { info.no_price };
+            let expected_usdc = (owned_shares * price) / Uint128::from(MULTIPLIER);
 
-            messages.push(asset_transfer);
+            messages.push(payout_msg(&info, &receiver, expected_usdc, credit_balance)?);
 
             let total_liquidity = info.yes_liquidity + info.no_liquidity;
 
             let yes_to_remove = (info.yes_liquidity * expected_usdc) / total_liquidity;
             let no_to_remove = (info.no_liquidity * expected_usdc) / total_liquidity;
-            
-            info.yes_shares -= owned_shares;
+
+            if winning == Outcome::Yes {
+                info.yes_shares -= owned_shares;
+                shares.yes_shares -= owned_shares;
+            }
+            else {
+                info.no_shares -= owned_shares;
+                shares.no_shares -= owned_shares;
+            }
+
             info.yes_liquidity -= yes_to_remove;
             info.no_liquidity -= no_to_remove;
-            shares.yes_shares -= owned_shares;
+
+            pnl += realize_position(deps.storage, &receiver, winning, owned_shares, owned_shares, expected_usdc)?;
 
         }
-        else if variant == Uint128::from(0u128) {
 
-            let owned_shares = shares.no_shares;
+        // Losing shares are worthless once resolved, so they are burned and
+        // their whole cost is realized as a loss
+        if !losing_shares.is_zero() {
 
-            if owned_shares == Uint128::from(0u128) {
-                return Err(StdError::generic_err("Must own shares to claim"));
+            let losing = if winning == Outcome::Yes { Outcome::No } else { Outcome::Yes };
+
+            if losing == Outcome::Yes {
+                info.yes_shares -= losing_shares;
+                shares.yes_shares = Uint128::from(0u128);
+            }
+            else {
+                info.no_shares -= losing_shares;
+                shares.no_shares = Uint128::from(0u128);
             }
 
-            let expected_usdc = (owned_shares * info.no_price) / Uint128::from(MULTIPLIER);
+            pnl += realize_position(deps.storage, &receiver, losing, losing_shares, losing_shares, Uint128::from(0u128))?;
 
-            let xfer_asset = Coin {
-                denom: info.usdc.clone(),
-                amount: expected_usdc.clone()
-            };
-    
-            let asset_transfer = CosmosMsg::Bank(BankMsg::Send {
-                to_address: receiver.clone().to_string(),
-                amount: vec![xfer_asset],
-            });
+        }
+
+        messages.push(pnl_msg(&info, &receiver, pnl)?);
 
-            messages.push(asset_transfer);
+        SHARES.save(deps.storage, receiver.clone(), &shares)?;
+        INFORMATION.save(deps.storage, &info)?;
+
+        Ok(Response::new().add_messages(messages))
+
+    }
+
+    /// Redeems the fees address's winning shares at the resolved price. Does
+    /// nothing when it holds none, so the factory can sweep markets in bulk.
+    pub fn collect_fees(
+        deps: DepsMut,
+        env: Env,
+        info_: MessageInfo,
+        former_fees_addresses: Vec<Addr>
+    ) -> Result<Response, ContractError> {
+
+        let mut info = INFORMATION.load(deps.storage)?;
+
+        if info_.sender != info.factory {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        ensure_status(&info, &env, &[MarketStatus::Resolved], "collect fees")?;
+
+        let msg = QueryFactoryMsg::FeesAddress {};
+
+        let query_msg = QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: info.factory.to_string(),
+            msg: to_json_binary(&msg)?,
+        });
+
+        let fees_address: Addr = deps.querier.query(&query_msg)?;
+
+        let mut shares: Shares = SHARES.load(deps.storage, fees_address.clone()).unwrap_or_else(|_| Shares::new());
+
+        // Fee shares accrued before the fees address was rotated
+        for former in former_fees_addresses {
+            if former == fees_address {
+                continue;
+            }
+            if let Some(former_shares) = SHARES.may_load(deps.storage, former.clone())? {
+                shares.yes_shares += former_shares.yes_shares;
+                shares.no_shares += former_shares.no_shares;
+                SHARES.remove(deps.storage, former);
+            }
+        }
+
+        let (owned_shares, price) = if info.resolved_to == Uint128::from(Outcome::Yes) {
+            (shares.yes_shares, info.yes_price)
+        }
+        else {
+            (shares.no_shares, info.no_price)
+        };
+
+        // Losing fee shares are worthless once resolved, so both sides are burned
+        info.yes_shares -= shares.yes_shares;
+        info.no_shares -= shares.no_shares;
+        shares.yes_shares = Uint128::from(0u128);
+        shares.no_shares = Uint128::from(0u128);
+
+        let collected = (owned_shares * price) / Uint128::from(MULTIPLIER);
+
+        let mut messages = vec![];
+
+        if !collected.is_zero() {
+
+            messages.push(info.usdc.transfer_msg(&fees_address, collected)?);
 
             let total_liquidity = info.yes_liquidity + info.no_liquidity;
 
-            let yes_to_remove = (info.yes_liquidity * expected_usdc) / total_liquidity;
-            let no_to_remove = (info.no_liquidity * expected_usdc) / total_liquidity;
+            let yes_to_remove = (info.yes_liquidity * collected) / total_liquidity;
+            let no_to_remove = (info.no_liquidity * collected) / total_liquidity;
 
-            info.no_shares -= owned_shares;
             info.yes_liquidity -= yes_to_remove;
             info.no_liquidity -= no_to_remove;
-            shares.no_shares -= owned_shares;
+
+            let msg = ExecuteFactoryMsg::RecordStats {
+                amount: collected,
+                account: fees_address.clone(),
+                stat_type: String::from("fees"),
+                data: vec![]
+            };
+
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: info.factory.to_string(),
+                msg: to_json_binary(&msg)?,
+                funds: vec![]
+            }));
 
         }
 
-        SHARES.save(deps.storage, receiver.clone(), &shares)?;
+        SHARES.save(deps.storage, fees_address, &shares)?;
         INFORMATION.save(deps.storage, &info)?;
 
-        Ok(Response::new().add_messages(messages))
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_attribute("action", "collect_fees")
+            .add_attribute("collected", collected))
 
     }
 
@@ -338,53 +460,47 @@ pub mod execute_msg {
         deps: DepsMut,
         env: Env,
         info_: MessageInfo,
-        variant: Uint128,
+        variant: Outcome,
         receiver: Addr,
         market_index: u128
-    ) -> StdResult<Response> {
+    ) -> Result<Response, ContractError> {
 
         let mut info = INFORMATION.load(deps.storage)?;
 
         let account = info_.sender;
 
         if account != info.factory {
-            return Err(StdError::generic_err("Only callable by factory"));
+            return Err(ContractError::Unauthorized {});
         }
 
-        let msg = QueryFactoryMsg::IsAdmin { account: receiver.clone() };
-        
-        let query_msg = QueryRequest::Wasm(WasmQuery::Smart {
-            contract_addr: info.factory.to_string(),
-            msg: to_json_binary(&msg)?,
-        });
+        ensure_owner_or_admin(deps.as_ref(), &info, &receiver, Role::Resolver, "resolve a market")?;
 
-        let is_admin: bool = deps.querier.query(&query_msg)?;
+        ensure_status(&info, &env, &[MarketStatus::ResolutionProposed], "resolve")?;
 
-        if receiver.clone() != info.owner && !is_admin  {
-            return Err(StdError::generic_err("Only owner or admins can resolve a market"));
-        }
+        let proposed = info.proposed_to.ok_or(ContractError::NoProposal {})?;
 
-        if env.block.time.seconds() < info.market_end {
-            return Err(StdError::generic_err("Can only resolve when market has reached deadline"));
+        if variant != proposed {
+            return Err(ContractError::ProposalMismatch { proposed });
         }
-        if variant > Uint128::from(1u128) {
-            return Err(StdError::generic_err("Variant must only be 0 (No) or 1 (Yes)"));
-        }
-        if info.resolved {
-            return Err(StdError::generic_err("Market already resolved"));
+
+        let resolvable_at = info.proposed_at.ok_or(ContractError::NoProposal {})? + RESOLVE_DURATION;
+
+        if env.block.time.seconds() < resolvable_at {
+            return Err(ContractError::ChallengePeriodActive { resolvable_at });
         }
 
-        if variant == Uint128::from(1u128) {
+        if variant == Outcome::Yes {
             info.yes_price = Uint128::from(MULTIPLIER);
             info.no_price = Uint128::from(0u128);
         }
-        else if variant == Uint128::from(0u128) {
+        else if variant == Outcome::No {
             info.no_price = Uint128::from(MULTIPLIER);
             info.yes_price = Uint128::from(0u128);
         }
 
         info.resolved = true;
-        info.resolved_to = variant;
+        info.resolved_to = variant.into();
+        info.status = MarketStatus::Resolved;
 
         let data: Vec<Uint128> = vec![Uint128::from(market_index)];
 
@@ -407,15 +523,164 @@ pub mod execute_msg {
 
     }
 
+    pub fn propose_resolution(
+        deps: DepsMut,
+        env: Env,
+        info_: MessageInfo,
+        variant: Outcome,
+        receiver: Addr
+    ) -> Result<Response, ContractError> {
+
+        let mut info = INFORMATION.load(deps.storage)?;
+
+        if info_.sender != info.factory {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        ensure_owner_or_admin(deps.as_ref(), &info, &receiver, Role::Resolver, "propose a resolution")?;
+
+        ensure_status(&info, &env, &[MarketStatus::TradingClosed, MarketStatus::ResolutionProposed], "propose a resolution")?;
+
+        // Repeating the current proposal does not restart the challenge period
+        if info.proposed_to != Some(variant) {
+            info.proposed_to = Some(variant);
+            info.proposed_at = Some(env.block.time.seconds());
+        }
+
+        info.status = MarketStatus::ResolutionProposed;
+
+        INFORMATION.save(deps.storage, &info)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "propose_resolution")
+            .add_attribute("variant", variant.to_string()))
+
+    }
+
+    /// Closes a resolved market once every winning share has been claimed
+    pub fn finalize_market(
+        deps: DepsMut,
+        env: Env,
+        info_: MessageInfo,
+        receiver: Addr
+    ) -> Result<Response, ContractError> {
+
+        let mut info = INFORMATION.load(deps.storage)?;
+
+        if info_.sender != info.factory {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        ensure_owner_or_admin(deps.as_ref(), &info, &receiver, Role::Resolver, "finalize a market")?;
+
+        ensure_status(&info, &env, &[MarketStatus::Resolved], "finalize")?;
+
+        let unclaimed = if info.resolved_to == Uint128::from(Outcome::Yes) { info.yes_shares } else { info.no_shares };
+
+        if unclaimed != Uint128::from(0u128) {
+            return Err(ContractError::UnclaimedShares {});
+        }
+
+        info.status = MarketStatus::Finalized;
+
+        INFORMATION.save(deps.storage, &info)?;
+
+        Ok(Response::new().add_attribute("action", "finalize_market"))
+
+    }
+
+    /// Cancels a market that has not traded yet. Liquidity providers can then
+    /// withdraw everything without the minimum liquidity floor.
+    pub fn cancel_market(
+        deps: DepsMut,
+        env: Env,
+        info_: MessageInfo,
+        receiver: Addr,
+        market_index: u128
+    ) -> Result<Response, ContractError> {
+
+        let mut info = INFORMATION.load(deps.storage)?;
+
+        if info_.sender != info.factory {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        ensure_owner_or_admin(deps.as_ref(), &info, &receiver, Role::MarketCreator, "cancel a market")?;
+
+        ensure_status(&info, &env, &[MarketStatus::Created, MarketStatus::Open], "cancel")?;
+
+        if info.yes_shares != Uint128::from(0u128) || info.no_shares != Uint128::from(0u128) {
+            return Err(ContractError::MarketTraded {});
+        }
+
+        info.status = MarketStatus::Cancelled;
+
+        INFORMATION.save(deps.storage, &info)?;
+
+        let msg = ExecuteFactoryMsg::RecordStats {
+            amount: Uint128::from(0u128),
+            account: receiver.clone(),
+            stat_type: String::from("cancel"),
+            data: vec![Uint128::from(market_index)]
+        };
+
+        let execute_msg = WasmMsg::Execute {
+            contract_addr: info.factory.to_string(),
+            msg: to_json_binary(&msg)?,
+            funds: vec![]
+        };
+
+        Ok(Response::new().add_message(execute_msg))
+
+    }
+
+    fn ensure_status(info: &Information, env: &Env, allowed: &[MarketStatus], action: &str) -> Result<MarketStatus, ContractError> {
+
+        let status = info.current_status(env.block.time.seconds());
+
+        if !allowed.contains(&status) {
+            if status == MarketStatus::Resolved || status == MarketStatus::Finalized {
+                return Err(ContractError::MarketResolved {});
+            }
+            return Err(ContractError::InvalidStatus { action: action.to_string(), status });
+        }
+
+        Ok(status)
+
+    }
+
+    /// Admins are accounts holding `role` in the factory
+    fn ensure_owner_or_admin(deps: Deps, info: &Information, receiver: &Addr, role: Role, action: &str) -> Result<(), ContractError> {
+
+        let msg = QueryFactoryMsg::HasRole { account: receiver.clone(), role };
+
+        let query_msg = QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: info.factory.to_string(),
+            msg: to_json_binary(&msg)?,
+        });
+
+        let is_admin: bool = deps.querier.query(&query_msg)?;
+
+        if *receiver != info.owner && !is_admin {
+            return Err(ContractError::NotOwnerOrAdmin { action: action.to_string() });
+        }
+
+        Ok(())
+
+    }
+
     pub fn place_order(
         deps: DepsMut,
         env: Env,
         info_: MessageInfo,
-        variant: Uint128,
-        buy_or_sell: Uint128,
+        variant: Outcome,
+        buy_or_sell: Side,
         amount: Uint128,
-        receiver: Addr
-    ) -> StdResult<Response> {
+        receiver: Addr,
+        min_out: Option<Uint128>,
+        credit_balance: bool,
+        referral: Option<Referral>
+    ) -> Result<Response, ContractError> {
 
         let mut info = INFORMATION.load(deps.storage)?;
 
@@ -426,29 +691,17 @@ pub mod execute_msg {
 
         let timestamp = env.block.time.seconds();
 
-        if timestamp >= info.market_end && timestamp < (info.market_end + RESOLVE_DURATION) {
-            return Err(StdError::generic_err("Cannot trade within resolution window"));
-        }
-
         if account != info.factory {
-            return Err(StdError::generic_err("Only callable by factory"));
+            return Err(ContractError::Unauthorized {});
         }
 
         if amount == Uint128::from(0u128) {
-            return Err(StdError::generic_err("Amount must be greater than 0"));
+            return Err(ContractError::ZeroAmount {});
         }
 
         let mut shares: Shares = SHARES.load(deps.storage, receiver.clone()).unwrap_or_else(|_| Shares::new());
 
-        if info.resolved {
-            return Err(StdError::generic_err("Market has already been resolved"));
-        }
-        if variant > Uint128::from(1u128) {
-            return Err(StdError::generic_err("Variant must be 1 for Yes or 0 for No"));
-        }
-        if buy_or_sell > Uint128::from(1u128) {
-            return Err(StdError::generic_err("Variant must be 1 for Buy or 0 for Sell"));
-        }
+        ensure_status(&info, &env, &[MarketStatus::Open], "place orders")?;
 
         let max_impact = Uint128::from(5 * 10u128.pow(2u32)); // 5% max price impact
 
@@ -469,17 +722,19 @@ pub mod execute_msg {
 
         let fees_address: Addr = deps.querier.query(&query_msg)?;
 
-        if variant == Uint128::from(1u128) { // Yes
+        if variant == Outcome::Yes { // Yes
 
-            if buy_or_sell == Uint128::from(1u128) { // Buy
+            if buy_or_sell == Side::Buy { // Buy
 
                 let quote:Quote = quote(deps.as_ref(), env.clone(), variant, buy_or_sell, amount)?;
 
                 if quote.impact > max_impact {
-                    return Err(StdError::generic_err("Price impact must not be more than 5%"));
+                    return Err(ContractError::ImpactTooHigh { impact: quote.impact, max: max_impact });
                 }
 
-                let data:Vec<Uint128> = vec![variant, buy_or_sell, info.yes_price];
+                check_min_out(&quote, min_out)?;
+
+                let data:Vec<Uint128> = vec![variant.into(), buy_or_sell.into(), info.yes_price];
 
                 let msg = ExecuteFactoryMsg::RecordStats {
                     amount,
@@ -496,16 +751,25 @@ pub mod execute_msg {
 
                 shares.yes_shares += quote.amount_out;
 
+                add_position_cost(deps.storage, &receiver, variant, amount)?;
+
                 SHARES.save(deps.storage, receiver.clone(), &shares)?;
 
                 let mut fee_shares: Shares = SHARES.load(deps.storage, fees_address.clone()).unwrap_or_else(|_| Shares::new());
 
-                fee_shares.yes_shares += quote.fees / Uint128::from(2u128);
-                info.yes_shares += quote.amount_out + (quote.fees / Uint128::from(2u128));
+                let protocol_fee = quote.fees / Uint128::from(2u128);
+                // The referrer's cut is carved out of the fee shares and paid from the
+                // pool in collateral, the same as on sells
+                let referral_amount = referral_cut(&referral, protocol_fee);
+
+                fee_shares.yes_shares += protocol_fee - referral_amount;
+                info.yes_shares += quote.amount_out + protocol_fee - referral_amount;
 
                 SHARES.save(deps.storage, fees_address.clone(), &fee_shares)?;
 
-                info.yes_liquidity += amount;
+                messages.extend(referral_msg(&info, &referral, referral_amount)?);
+
+                info.yes_liquidity += amount - referral_amount;
                 info.yes_price = quote.price;
                 info.no_price = Uint128::from(MULTIPLIER) - quote.price;
 
@@ -514,8 +778,14 @@ pub mod execute_msg {
                     price: info.yes_price
                 })?;
 
+                record_trade(deps.storage, total_orders.u128(), &receiver, variant, buy_or_sell, amount, &quote, timestamp)?;
+
             }
-            else if buy_or_sell == Uint128::from(0u128) { // Sell
+            else if buy_or_sell == Side::Sell { // Sell
+
+                if shares.yes_shares < amount {
+                    return Err(ContractError::InsufficientShares { have: shares.yes_shares, want: amount });
+                }
 
                 shares.yes_shares -= amount;
 
@@ -524,10 +794,15 @@ pub mod execute_msg {
                 let quote:Quote = quote(deps.as_ref(), env, variant, buy_or_sell, amount)?;
 
                 if quote.impact > max_impact {
-                    return Err(StdError::generic_err("Price impact must not be more than 5%"));
+                    return Err(ContractError::ImpactTooHigh { impact: quote.impact, max: max_impact });
                 }
 
-                let data: Vec<Uint128> = vec![variant, buy_or_sell, info.yes_price];
+                check_min_out(&quote, min_out)?;
+
+                let protocol_fee = quote.fees / Uint128::from(2u128);
+                let referral_amount = referral_cut(&referral, protocol_fee);
+
+                let data: Vec<Uint128> = vec![variant.into(), buy_or_sell.into(), info.yes_price, protocol_fee - referral_amount];
 
                 let msg = ExecuteFactoryMsg::RecordStats {
                     amount: quote.amount_out,
@@ -553,46 +828,38 @@ pub mod execute_msg {
                     price: info.yes_price
                 })?;
 
-                SHARES.save(deps.storage, receiver.clone(), &shares)?;
+                record_trade(deps.storage, total_orders.u128(), &receiver, variant, buy_or_sell, amount, &quote, timestamp)?;
 
-                let xfer_fees = Coin {
-                    denom: info.usdc.clone(),
-                    amount: (quote.fees / Uint128::from(2u128))
-                };
-        
-                let transfer_to_fees_address = CosmosMsg::Bank(BankMsg::Send {
-                    to_address: fees_address.clone().to_string(),
-                    amount: vec![xfer_fees],
-                });
+                let pnl = realize_position(deps.storage, &receiver, variant, shares.yes_shares + amount, amount, quote.amount_out)?;
+                messages.push(pnl_msg(&info, &receiver, pnl)?);
+
+                SHARES.save(deps.storage, receiver.clone(), &shares)?;
 
-                messages.push(transfer_to_fees_address);
+                // Nothing is left for the fees address when referrers get the whole fee
+                if protocol_fee > referral_amount {
+                    messages.push(info.usdc.transfer_msg(&fees_address, protocol_fee - referral_amount)?);
+                }
 
-                let xfer_account = Coin {
-                    denom: info.usdc.clone(),
-                    amount: quote.amount_out.clone()
-                };
-        
-                let transfer_to_account = CosmosMsg::Bank(BankMsg::Send {
-                    to_address: receiver.clone().to_string(),
-                    amount: vec![xfer_account],
-                });
+                messages.extend(referral_msg(&info, &referral, referral_amount)?);
 
-                messages.push(transfer_to_account);
+                messages.push(payout_msg(&info, &receiver, quote.amount_out, credit_balance)?);
 
             }
 
         }
-        else if variant == Uint128::from(0u128) { // No
+        else if variant == Outcome::No { // No
 
-            if buy_or_sell == Uint128::from(1u128) { // Buy
+            if buy_or_sell == Side::Buy { // Buy
 
                 let quote:Quote = quote(deps.as_ref(), env.clone(), variant, buy_or_sell, amount)?;
 
                 if quote.impact > max_impact {
-                    return Err(StdError::generic_err("Price impact must not be more than 5%"));
+                    return Err(ContractError::ImpactTooHigh { impact: quote.impact, max: max_impact });
                 }
 
-                let data: Vec<Uint128> = vec![variant, buy_or_sell, info.no_price];
+                check_min_out(&quote, min_out)?;
+
+                let data: Vec<Uint128> = vec![variant.into(), buy_or_sell.into(), info.no_price];
 
                 let msg = ExecuteFactoryMsg::RecordStats {
                     amount,
@@ -609,16 +876,25 @@ pub mod execute_msg {
 
                 shares.no_shares += quote.amount_out;
 
+                add_position_cost(deps.storage, &receiver, variant, amount)?;
+
                 SHARES.save(deps.storage, receiver.clone(), &shares)?;
 
                 let mut fee_shares: Shares = SHARES.load(deps.storage, fees_address.clone()).unwrap_or_else(|_| Shares::new());
 
-                fee_shares.no_shares += quote.fees / Uint128::from(2u128);
-                info.no_shares += quote.amount_out + (quote.fees / Uint128::from(2u128));
+                let protocol_fee = quote.fees / Uint128::from(2u128);
+                // The referrer's cut is carved out of the fee shares and paid from the
+                // pool in collateral, the same as on sells
+                let referral_amount = referral_cut(&referral, protocol_fee);
+
+                fee_shares.no_shares += protocol_fee - referral_amount;
+                info.no_shares += quote.amount_out + protocol_fee - referral_amount;
 
                 SHARES.save(deps.storage, fees_address.clone(), &fee_shares)?;
 
-                info.no_liquidity += amount;
+                messages.extend(referral_msg(&info, &referral, referral_amount)?);
+
+                info.no_liquidity += amount - referral_amount;
                 info.no_price = quote.price;
                 info.yes_price = Uint128::from(MULTIPLIER) - quote.price;
 
@@ -627,8 +903,14 @@ pub mod execute_msg {
                     price: info.yes_price
                 })?;
 
+                record_trade(deps.storage, total_orders.u128(), &receiver, variant, buy_or_sell, amount, &quote, timestamp)?;
+
             }
-            else if buy_or_sell == Uint128::from(0u128) { // Sell
+            else if buy_or_sell == Side::Sell { // Sell
+
+                if shares.no_shares < amount {
+                    return Err(ContractError::InsufficientShares { have: shares.no_shares, want: amount });
+                }
 
                 shares.no_shares -= amount;
 
@@ -637,10 +919,15 @@ pub mod execute_msg {
                 let quote:Quote = quote(deps.as_ref(), env, variant, buy_or_sell, amount)?;
 
                 if quote.impact > max_impact {
-                    return Err(StdError::generic_err("Price impact must not be more than 5%"));
+                    return Err(ContractError::ImpactTooHigh { impact: quote.impact, max: max_impact });
                 }
 
-                let data: Vec<Uint128> = vec![variant, buy_or_sell, info.no_price];
+                check_min_out(&quote, min_out)?;
+
+                let protocol_fee = quote.fees / Uint128::from(2u128);
+                let referral_amount = referral_cut(&referral, protocol_fee);
+
+                let data: Vec<Uint128> = vec![variant.into(), buy_or_sell.into(), info.no_price, protocol_fee - referral_amount];
 
                 let msg = ExecuteFactoryMsg::RecordStats {
                     amount: quote.amount_out,
@@ -666,29 +953,19 @@ pub mod execute_msg {
                     price: info.yes_price
                 })?;
 
-                let xfer_fees = Coin {
-                    denom: info.usdc.clone(),
-                    amount: (quote.fees / Uint128::from(2u128))
-                };
-        
-                let transfer_to_fees_address = CosmosMsg::Bank(BankMsg::Send {
-                    to_address: fees_address.clone().to_string(),
-                    amount: vec![xfer_fees],
-                });
+                record_trade(deps.storage, total_orders.u128(), &receiver, variant, buy_or_sell, amount, &quote, timestamp)?;
 
-                messages.push(transfer_to_fees_address);
+                let pnl = realize_position(deps.storage, &receiver, variant, shares.no_shares + amount, amount, quote.amount_out)?;
+                messages.push(pnl_msg(&info, &receiver, pnl)?);
 
-                let xfer_account = Coin {
-                    denom: info.usdc.clone(),
-                    amount: quote.amount_out.clone()
-                };
-        
-                let transfer_to_account = CosmosMsg::Bank(BankMsg::Send {
-                    to_address: receiver.clone().to_string(),
-                    amount: vec![xfer_account],
-                });
+                // Nothing is left for the fees address when referrers get the whole fee
+                if protocol_fee > referral_amount {
+                    messages.push(info.usdc.transfer_msg(&fees_address, protocol_fee - referral_amount)?);
+                }
+
+                messages.extend(referral_msg(&info, &referral, referral_amount)?);
 
-                messages.push(transfer_to_account);
+                messages.push(payout_msg(&info, &receiver, quote.amount_out, credit_balance)?);
 
             }
 
@@ -698,7 +975,7 @@ pub mod execute_msg {
 
         TOTAL_ORDERS.save(deps.storage, &total_orders)?;
 
-        let response = if messages.len() > 0 {
+        let response = if !messages.is_empty() {
             Response::new().add_messages(messages).add_message(execute_msg)
         }
         else {
@@ -709,13 +986,174 @@ pub mod execute_msg {
 
     }
 
+    fn add_position_cost(storage: &mut dyn Storage, account: &Addr, variant: Outcome, cost: Uint128) -> StdResult<()> {
+
+        let mut position = POSITIONS.load(storage, account.clone()).unwrap_or_default();
+
+        if variant == Outcome::Yes {
+            position.yes_cost += cost;
+        }
+        else {
+            position.no_cost += cost;
+        }
+
+        POSITIONS.save(storage, account.clone(), &position)
+
+    }
+
+    /// Releases the cost basis of `sold` out of `held` shares pro rata and
+    /// books the difference to `proceeds` as realized PnL, which is returned.
+    fn realize_position(
+        storage: &mut dyn Storage,
+        account: &Addr,
+        variant: Outcome,
+        held: Uint128,
+        sold: Uint128,
+        proceeds: Uint128
+    ) -> StdResult<Int128> {
+
+        let mut position = POSITIONS.load(storage, account.clone()).unwrap_or_default();
+
+        let cost = if variant == Outcome::Yes {
+            &mut position.yes_cost
+        }
+        else {
+            &mut position.no_cost
+        };
+
+        let released = if held.is_zero() {
+            *cost
+        }
+        else {
+            cost.multiply_ratio(sold, held)
+        };
+
+        *cost -= released;
+
+        let pnl = Int128::try_from(proceeds)? - Int128::try_from(released)?;
+
+        position.realized_pnl += pnl;
+
+        POSITIONS.save(storage, account.clone(), &position)?;
+
+        Ok(pnl)
+
+    }
+
+    /// Reports PnL realized by `account` to the factory for its leaderboards.
+    /// The amount is unsigned, `data` holds 1 for a loss and 0 for a profit.
+    fn pnl_msg(info: &Information, account: &Addr, pnl: Int128) -> StdResult<CosmosMsg> {
+
+        let msg = ExecuteFactoryMsg::RecordStats {
+            amount: pnl.unsigned_abs(),
+            account: account.clone(),
+            stat_type: String::from("pnl"),
+            data: vec![Uint128::from(u128::from(pnl.is_negative()))]
+        };
+
+        Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: info.factory.to_string(),
+            msg: to_json_binary(&msg)?,
+            funds: vec![]
+        }))
+
+    }
+
+    fn record_trade(
+        storage: &mut dyn Storage,
+        id: u128,
+        account: &Addr,
+        variant: Outcome,
+        buy_or_sell: Side,
+        amount: Uint128,
+        quote: &Quote,
+        timestamp: u64
+    ) -> StdResult<()> {
+
+        // Buy fees are charged in shares, value them at the fill price
+        let (usdc, shares, fees) = if buy_or_sell == Side::Buy {
+            (amount, quote.amount_out, (quote.fees * quote.price) / Uint128::from(MULTIPLIER))
+        }
+        else {
+            (quote.amount_out, amount, quote.fees)
+        };
+
+        let trade = Trade {
+            id: id as u64,
+            account: account.clone(),
+            variant,
+            buy_or_sell,
+            amount: usdc,
+            shares,
+            fees,
+            price: quote.price,
+            timestamp
+        };
+
+        trades().save(storage, id as u64, &trade)
+
+    }
+
+    /// Pays `amount` USDC out to `receiver`, either directly or into the
+    /// receiver's internal balance held by the factory.
+    fn payout_msg(info: &Information, receiver: &Addr, amount: Uint128, credit_balance: bool) -> StdResult<CosmosMsg> {
+
+        if !credit_balance {
+            return info.usdc.transfer_msg(receiver, amount);
+        }
+
+        // Serializes the same as the factory's CW20 `ReceiveMsg::CreditBalance`
+        let msg = to_json_binary(&ExecuteFactoryMsg::CreditBalance {
+            account: receiver.clone()
+        })?;
+
+        Ok(CosmosMsg::Wasm(info.usdc.execute_msg(&info.factory, msg, amount)?))
+
+    }
+
+    /// The referrer's cut of `fee`, in the same unit
+    fn referral_cut(referral: &Option<Referral>, fee: Uint128) -> Uint128 {
+
+        match referral {
+            Some(referral) => fee.multiply_ratio(referral.share_bps, 10_000u128),
+            None => Uint128::from(0u128)
+        }
+
+    }
+
+    /// Sends a referrer's cut to the factory, which keeps it until claimed
+    fn referral_msg(info: &Information, referral: &Option<Referral>, amount: Uint128) -> StdResult<Option<CosmosMsg>> {
+
+        let referral = match referral {
+            Some(referral) if !amount.is_zero() => referral,
+            _ => return Ok(None)
+        };
+
+        // Serializes the same as the factory's CW20 `ReceiveMsg::CreditReferral`
+        let msg = to_json_binary(&ExecuteFactoryMsg::CreditReferral {
+            referrer: referral.referrer.clone()
+        })?;
+
+        Ok(Some(CosmosMsg::Wasm(info.usdc.execute_msg(&info.factory, msg, amount)?)))
+
+    }
+
+    fn check_min_out(quote: &Quote, min_out: Option<Uint128>) -> Result<(), ContractError> {
+        if let Some(min_out) = min_out {
+            if quote.amount_out < min_out {
+                return Err(ContractError::SlippageExceeded { amount_out: quote.amount_out, min_out });
+            }
+        }
+        Ok(())
+    }
+
     pub fn calculate_impact(amount_a: Uint128, amount_b: Uint128) -> Uint128 {
         let new_sum = amount_a + amount_b;
         let ratio = (Uint128::from(10u128.pow(4u32)) * new_sum) / amount_a;
         ratio - Uint128::from(10u128.pow(4u32))
     }
 
-    pub fn quote(deps: Deps, _env: Env, variant: Uint128, buy_or_sell: Uint128, amount: Uint128) -> StdResult<Quote> {
+    pub fn quote(deps: Deps, _env: Env, variant: Outcome, buy_or_sell: Side, amount: Uint128) -> StdResult<Quote> {
 
         let mut amount_out = Uint128::from(0u128);
         let mut impact = Uint128::from(0u128);
@@ -724,16 +1162,9 @@ pub mod execute_msg {
 
         let info = INFORMATION.load(deps.storage)?;
 
-        if variant > Uint128::from(1u128) {
-            return Err(StdError::generic_err("Variant must be 1 for Yes or 0 for No"));
-        }
-        if buy_or_sell > Uint128::from(1u128) {
-            return Err(StdError::generic_err("Variant must be 1 for Buy or 0 for Sell"));
-        }
-
-        if variant == Uint128::from(1u128) { // Yes
+        if variant == Outcome::Yes { // Yes
 
-            if buy_or_sell == Uint128::from(1u128) { // Buy
+            if buy_or_sell == Side::Buy { // Buy
 
                 impact = calculate_impact(info.yes_liquidity, amount);
 
@@ -753,12 +1184,12 @@ pub mod execute_msg {
                 amount_out -= fees;
                 
             }
-            else if buy_or_sell == Uint128::from(0u128) { // Sell
+            else if buy_or_sell == Side::Sell { // Sell
 
                 let expected_amount = (amount * info.yes_price) / Uint128::from(MULTIPLIER);
                 impact = calculate_impact(info.yes_liquidity, expected_amount);
 
-                let new_yes_liquidity = info.yes_liquidity - expected_amount;
+                let new_yes_liquidity = info.yes_liquidity.checked_sub(expected_amount)?;
                 let new_no_liquidity = info.no_liquidity;
 
                 let new_yes_price = (Uint128::from(MULTIPLIER) * new_yes_liquidity) / (new_yes_liquidity + new_no_liquidity);
@@ -774,9 +1205,9 @@ pub mod execute_msg {
             }
 
         }
-        else if variant == Uint128::from(0u128) { // No
+        else if variant == Outcome::No { // No
             
-            if buy_or_sell == Uint128::from(1u128) { // Buy
+            if buy_or_sell == Side::Buy { // Buy
 
                 impact = calculate_impact(info.no_liquidity, amount);
 
@@ -796,13 +1227,13 @@ pub mod execute_msg {
                 amount_out -= fees;
 
             }
-            else if buy_or_sell == Uint128::from(0u128) { // Sell
+            else if buy_or_sell == Side::Sell { // Sell
 
                 let expected_amount = (amount * info.no_price) / Uint128::from(MULTIPLIER);
 
                 impact = calculate_impact(info.no_liquidity, expected_amount);
 
-                let new_no_liquidity = info.no_liquidity - expected_amount;
+                let new_no_liquidity = info.no_liquidity.checked_sub(expected_amount)?;
                 let new_yes_liquidity = info.yes_liquidity;
 
                 let new_no_price = (Uint128::from(MULTIPLIER) * new_no_liquidity) / (new_no_liquidity + new_yes_liquidity);
@@ -1,6 +1,12 @@
-use crate::state::{INFORMATION, SHARES, TOTAL_ORDERS, ORDER_LIST};
-use cosmwasm_std::{Deps, Env, StdResult, StdError, Uint128};
-use packages::market::{Information, Shares, Order, Data};
+use crate::execute::execute_msg::{quote, liquidity_to_add, liquidity_to_remove, usdc_balance, MULTIPLIER};
+use crate::state::{INFORMATION, SHARES, TOTAL_ORDERS, ORDER_LIST, POSITIONS, trades};
+use cosmwasm_std::{Deps, Env, Int128, StdResult, Uint128};
+use cw_storage_plus::Bound;
+use crate::ContractError;
+use packages::market::{Information, Shares, Order, Data, Trade, PositionInfo, AddLiquiditySimulation, RemoveLiquiditySimulation, Outcome, Side};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
 
 pub mod query_msg {
 
@@ -11,7 +17,8 @@ pub mod query_msg {
     };
 
     pub fn get_info(deps: Deps, _env: Env, account: Addr) -> StdResult<Data> {
-        let information: Information = INFORMATION.load(deps.storage).unwrap();
+        let mut information: Information = INFORMATION.load(deps.storage)?;
+        information.status = information.current_status(_env.block.time.seconds());
         let shares: Shares = SHARES.load(deps.storage, account).unwrap_or_else(|_| Shares::new());
         let data = Data {
             information,
@@ -20,13 +27,13 @@ pub mod query_msg {
         Ok(data)
     }
 
-    pub fn get_orders(_deps: Deps, _env: Env, page_: u128, items_per_page: u128) -> StdResult<(u64, Vec<Order>)> {
+    pub fn get_orders(_deps: Deps, _env: Env, page_: u128, items_per_page: u128) -> Result<(u64, Vec<Order>), ContractError> {
 
         if page_ == 0u128 {
-            return Err(StdError::generic_err("Page must be greater than 0"));
+            return Err(ContractError::ZeroPage {});
         }
         if items_per_page == 0u128 {
-            return Err(StdError::generic_err("Items per page must be greater than 0"));
+            return Err(ContractError::ZeroItemsPerPage {});
         }
 
         let total_orders = TOTAL_ORDERS.load(_deps.storage)?;
@@ -41,11 +48,11 @@ pub mod query_msg {
         let page = Uint128::from(page_);
 
         if page > number_of_pages {
-            return Err(StdError::generic_err("Page is out of bounds"));
+            return Err(ContractError::PageOutOfBounds {});
         }
 
         if total_orders == Uint128::from(0u128) {
-            return Err(StdError::generic_err("No order exists at the moment"));
+            return Err(ContractError::NoOrders {});
         }
 
         let start_index = total_orders - ((page - Uint128::from(1u128)) * Uint128::from(items_per_page));
@@ -60,7 +67,7 @@ pub mod query_msg {
         let number_of_items = (start_index - end_index) + Uint128::from(1u128);
 
         if number_of_items == Uint128::from(0u128) {
-            return Err(StdError::generic_err("No order found for this page"));
+            return Err(ContractError::EmptyPage {});
         }
 
         let mut orders: Vec<Order> = vec![];
@@ -91,4 +98,127 @@ pub mod query_msg {
 
     }
 
-}
\ No newline at end of file
+    pub fn simulate_add_liquidity(deps: Deps, _env: Env, amount: Uint128) -> Result<AddLiquiditySimulation, ContractError> {
+
+        let info: Information = INFORMATION.load(deps.storage)?;
+
+        if info.yes_price == Uint128::from(0u128) {
+            return Err(ContractError::LiquidityNotInitialized {});
+        }
+
+        let (yes_liquidity, no_liquidity, shares_minted) = liquidity_to_add(&info, amount);
+
+        Ok(AddLiquiditySimulation {
+            shares_minted,
+            yes_liquidity: info.yes_liquidity + yes_liquidity,
+            no_liquidity: info.no_liquidity + no_liquidity,
+            liquidity_shares: info.liquidity_shares + shares_minted
+        })
+
+    }
+
+    pub fn simulate_remove_liquidity(deps: Deps, env: Env, shares: Uint128) -> Result<RemoveLiquiditySimulation, ContractError> {
+
+        let info: Information = INFORMATION.load(deps.storage)?;
+
+        if info.liquidity_shares == Uint128::from(0u128) {
+            return Err(ContractError::LiquidityNotInitialized {});
+        }
+        if shares > info.liquidity_shares {
+            return Err(ContractError::InsufficientShares { have: info.liquidity_shares, want: shares });
+        }
+
+        let removal = liquidity_to_remove(&info, shares, usdc_balance(deps, &env, &info)?);
+
+        Ok(RemoveLiquiditySimulation {
+            amount_out: removal.amount,
+            yes_liquidity: info.yes_liquidity - removal.yes,
+            no_liquidity: info.no_liquidity - removal.no,
+            liquidity_shares: info.liquidity_shares - shares,
+            capped_to_balance: removal.capped,
+            below_minimum_liquidity: removal.below_minimum
+        })
+
+    }
+
+    pub fn get_position(deps: Deps, env: Env, account: Addr) -> StdResult<PositionInfo> {
+
+        let info: Information = INFORMATION.load(deps.storage)?;
+        let shares: Shares = SHARES.load(deps.storage, account.clone()).unwrap_or_else(|_| Shares::new());
+        let position = POSITIONS.load(deps.storage, account).unwrap_or_default();
+
+        let average_price = |cost: Uint128, held: Uint128| if held.is_zero() {
+            Uint128::from(0u128)
+        }
+        else {
+            cost.multiply_ratio(MULTIPLIER, held)
+        };
+
+        let yes_value = shares.yes_shares.multiply_ratio(info.yes_price, MULTIPLIER);
+        let no_value = shares.no_shares.multiply_ratio(info.no_price, MULTIPLIER);
+
+        // Resolved shares can only be claimed, at their marked value. Sales the
+        // pool cannot cover would fail or divide by zero in `quote`.
+        let liquidation_value = |variant: Outcome, held: Uint128, value: Uint128| -> StdResult<Option<Uint128>> {
+
+            if info.resolved || held.is_zero() {
+                return Ok(Some(value));
+            }
+
+            let (liquidity, price, other_liquidity) = match variant {
+                Outcome::Yes => (info.yes_liquidity, info.yes_price, info.no_liquidity),
+                Outcome::No => (info.no_liquidity, info.no_price, info.yes_liquidity)
+            };
+
+            let expected_amount = match held.checked_mul(price) {
+                Ok(amount) => amount / Uint128::from(MULTIPLIER),
+                Err(_) => return Ok(None)
+            };
+
+            if liquidity.is_zero() || expected_amount > liquidity || expected_amount == liquidity + other_liquidity {
+                return Ok(None);
+            }
+
+            quote(deps, env.clone(), variant, Side::Sell, held).map(|quote| Some(quote.amount_out))
+
+        };
+
+        let unrealized_pnl = Int128::try_from(yes_value + no_value)? - Int128::try_from(position.yes_cost + position.no_cost)?;
+
+        Ok(PositionInfo {
+            yes_shares: shares.yes_shares,
+            no_shares: shares.no_shares,
+            yes_cost: position.yes_cost,
+            no_cost: position.no_cost,
+            yes_average_price: average_price(position.yes_cost, shares.yes_shares),
+            no_average_price: average_price(position.no_cost, shares.no_shares),
+            yes_value,
+            no_value,
+            yes_liquidation_value: liquidation_value(Outcome::Yes, shares.yes_shares, yes_value)?,
+            no_liquidation_value: liquidation_value(Outcome::No, shares.no_shares, no_value)?,
+            unrealized_pnl,
+            realized_pnl: position.realized_pnl
+        })
+
+    }
+
+    /// Trades of `account`, newest first
+    pub fn get_account_trades(deps: Deps, _env: Env, account: Addr, start_after: Option<u64>, limit: Option<u32>) -> StdResult<Vec<Trade>> {
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let end = start_after.map(Bound::exclusive);
+
+        let account_trades = trades()
+            .idx
+            .account
+            .prefix(account)
+            .range(deps.storage, None, end, cosmwasm_std::Order::Descending)
+            .take(limit)
+            .map(|item| item.map(|(_, trade)| trade))
+            .collect::<StdResult<Vec<Trade>>>()?;
+
+        Ok(account_trades)
+
+    }
+
+}
@@ -1,6 +1,6 @@
-use cw_storage_plus::{Item, Map};
+use cw_storage_plus::{Item, Map, IndexedMap, MultiIndex, IndexList, Index};
 use cosmwasm_std::{Addr, Uint128};
-use packages::market::{Information, Shares, Order};
+use packages::market::{Information, Shares, Order, Trade, Position};
 
 pub const INFORMATION: Item<Information> = Item::new("information");
 
@@ -8,4 +8,25 @@ pub const TOTAL_ORDERS: Item<Uint128> = Item::new("total_orders");
 
 pub const ORDER_LIST: Map<u128, Order> = Map::new("order_list");
 
-pub const SHARES: Map<Addr, Shares> = Map::new("shares");
\ No newline at end of file
+pub const SHARES: Map<Addr, Shares> = Map::new("shares");
+
+pub const POSITIONS: Map<Addr, Position> = Map::new("positions");
+
+pub struct TradeIndexes<'a> {
+    pub account: MultiIndex<'a, Addr, Trade, u64>
+}
+
+impl<'a> IndexList<Trade> for TradeIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Trade>> + '_> {
+        let v: Vec<&dyn Index<Trade>> = vec![&self.account];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Trades keyed by order index, indexed by trading account
+pub fn trades<'a>() -> IndexedMap<'a, u64, Trade, TradeIndexes<'a>> {
+    let indexes = TradeIndexes {
+        account: MultiIndex::new(|_pk, trade| trade.account.clone(), "trades", "trades__account")
+    };
+    IndexedMap::new("trades", indexes)
+}
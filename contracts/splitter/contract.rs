@@ -0,0 +1,90 @@
+use crate::state::{CONFIG, DISTRIBUTION_COUNT, WITHDRAWAL_COUNT};
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use packages::splitter::{Config, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use cw2::{get_contract_version, set_contract_version, ContractVersion};
+
+use crate::execute::execute_msg;
+use crate::query::query_msg;
+use crate::ContractError;
+
+const CONTRACT_NAME: &str = concat!("crates.io:", env!("CARGO_PKG_NAME"));
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+
+    execute_msg::validate_recipients(deps.api, &msg.recipients)?;
+    execute_msg::validate_collaterals(deps.api, &msg.collaterals)?;
+
+    let config = Config {
+        owner: deps.api.addr_validate(msg.owner.as_str())?,
+        recipients: msg.recipients,
+        collaterals: msg.collaterals
+    };
+
+    CONFIG.save(deps.storage, &config)?;
+    DISTRIBUTION_COUNT.save(deps.storage, &0u64)?;
+    WITHDRAWAL_COUNT.save(deps.storage, &0u64)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new())
+
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Distribute { collateral } => execute_msg::distribute(deps, env, info, collateral),
+        ExecuteMsg::Withdraw { collateral, amount } => execute_msg::withdraw(deps, env, info, collateral, amount),
+        ExecuteMsg::UpdateRecipients { recipients } => execute_msg::update_recipients(deps, env, info, recipients),
+        ExecuteMsg::UpdateCollaterals { collaterals } => execute_msg::update_collaterals(deps, env, info, collaterals),
+        ExecuteMsg::UpdateOwner { owner } => execute_msg::update_owner(deps, env, info, owner)
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+
+    ensure_upgrade(&get_contract_version(deps.storage)?)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("version", CONTRACT_VERSION))
+
+}
+
+fn ensure_upgrade(stored: &ContractVersion) -> Result<(), ContractError> {
+
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidMigration { previous_contract: stored.contract.clone() });
+    }
+
+    if semver::Version::parse(&stored.version)? > semver::Version::parse(CONTRACT_VERSION)? {
+        return Err(ContractError::CannotDowngrade { previous: stored.version.clone(), current: CONTRACT_VERSION.to_string() });
+    }
+
+    Ok(())
+
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_json_binary(&CONFIG.load(deps.storage)?),
+        QueryMsg::Undistributed { collateral } => to_json_binary(&query_msg::undistributed(deps, env, collateral)?),
+        QueryMsg::Claimable { recipient, collateral } => to_json_binary(&query_msg::claimable(deps, env, recipient, collateral)?),
+        QueryMsg::ListClaimable { recipient, start_after, limit } => to_json_binary(&query_msg::list_claimable(deps, env, recipient, start_after, limit)?),
+        QueryMsg::Distributions { start_after, limit } => to_json_binary(&query_msg::distributions(deps, env, start_after, limit)?),
+        QueryMsg::Withdrawals { recipient, start_after, limit } => to_json_binary(&query_msg::withdrawals(deps, env, recipient, start_after, limit)?),
+    }
+}
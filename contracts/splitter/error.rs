@@ -0,0 +1,53 @@
+use cosmwasm_std::{Addr, StdError, Uint128};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Semver(#[from] semver::Error),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Cannot migrate from {previous_contract}")]
+    InvalidMigration { previous_contract: String },
+
+    #[error("Cannot migrate from version {previous} to older version {current}")]
+    CannotDowngrade { previous: String, current: String },
+
+    #[error("At least one recipient is required")]
+    NoRecipients {},
+
+    #[error("No more than {max} recipients are allowed")]
+    TooManyRecipients { max: usize },
+
+    #[error("Recipient weights must be greater than 0")]
+    ZeroWeight {},
+
+    #[error("Recipient {address} is listed more than once")]
+    DuplicateRecipient { address: Addr },
+
+    #[error("No more than {max} collaterals are allowed")]
+    TooManyCollaterals { max: usize },
+
+    #[error("Collateral {collateral} is listed more than once")]
+    DuplicateCollateral { collateral: String },
+
+    #[error("Collateral {collateral} is not whitelisted")]
+    CollateralNotWhitelisted { collateral: String },
+
+    #[error("Funds must not be attached")]
+    FundsNotAllowed {},
+
+    #[error("Nothing to distribute")]
+    NothingToDistribute {},
+
+    #[error("Nothing to withdraw")]
+    NothingToWithdraw {},
+
+    #[error("Insufficient balance: have {have}, want {want}")]
+    InsufficientBalance { have: Uint128, want: Uint128 },
+}
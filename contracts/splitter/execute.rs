@@ -0,0 +1,323 @@
+use std::collections::BTreeSet;
+
+use crate::state::{CONFIG, OUTSTANDING, CLAIMABLE, DISTRIBUTION_COUNT, DISTRIBUTIONS, WITHDRAWAL_COUNT, WITHDRAWALS};
+
+use cosmwasm_std::{Addr, Api, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage, Uint128};
+
+use packages::collateral::Collateral;
+use packages::splitter::{ClaimableBalance, Config, Distribution, DistributionShare, Recipient, Withdrawal};
+
+use crate::ContractError;
+
+pub mod execute_msg {
+
+    pub(crate) const MAX_RECIPIENTS: usize = 10;
+
+    /// Bounds the distributions run by `update_recipients`
+    pub(crate) const MAX_COLLATERALS: usize = 10;
+
+    use super::*;
+
+    pub(crate) fn validate_recipients(api: &dyn Api, recipients: &[Recipient]) -> Result<(), ContractError> {
+
+        if recipients.is_empty() {
+            return Err(ContractError::NoRecipients {});
+        }
+
+        if recipients.len() > MAX_RECIPIENTS {
+            return Err(ContractError::TooManyRecipients { max: MAX_RECIPIENTS });
+        }
+
+        let mut seen = BTreeSet::new();
+
+        for recipient in recipients {
+
+            api.addr_validate(recipient.address.as_str())?;
+
+            if recipient.weight == 0 {
+                return Err(ContractError::ZeroWeight {});
+            }
+
+            if !seen.insert(recipient.address.clone()) {
+                return Err(ContractError::DuplicateRecipient { address: recipient.address.clone() });
+            }
+
+        }
+
+        Ok(())
+
+    }
+
+    pub(crate) fn validate_collaterals(api: &dyn Api, collaterals: &[Collateral]) -> Result<(), ContractError> {
+
+        if collaterals.len() > MAX_COLLATERALS {
+            return Err(ContractError::TooManyCollaterals { max: MAX_COLLATERALS });
+        }
+
+        let mut seen = BTreeSet::new();
+
+        for collateral in collaterals {
+
+            if let Collateral::CW20Token { contract_address } = collateral {
+                api.addr_validate(contract_address.as_str())?;
+            }
+
+            if !seen.insert(collateral.to_string()) {
+                return Err(ContractError::DuplicateCollateral { collateral: collateral.to_string() });
+            }
+
+        }
+
+        Ok(())
+
+    }
+
+    fn nonpayable(info: &MessageInfo) -> Result<(), ContractError> {
+
+        if !info.funds.is_empty() {
+            return Err(ContractError::FundsNotAllowed {});
+        }
+
+        Ok(())
+
+    }
+
+    /// Balance of `collateral` that arrived since the last distribution.
+    /// Leftovers from rounding are carried into the next distribution.
+    pub(crate) fn undistributed(deps: Deps, env: &Env, collateral: &Collateral) -> StdResult<Uint128> {
+
+        let balance = collateral.query_balance(&deps.querier, &env.contract.address)?;
+        let outstanding = OUTSTANDING.may_load(deps.storage, collateral.to_string())?.unwrap_or_default();
+
+        Ok(balance.saturating_sub(outstanding))
+
+    }
+
+    pub fn distribute(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        collateral: Collateral
+    ) -> Result<Response, ContractError> {
+
+        nonpayable(&info)?;
+
+        let config = CONFIG.load(deps.storage)?;
+
+        if !config.collaterals.contains(&collateral) {
+            return Err(ContractError::CollateralNotWhitelisted { collateral: collateral.to_string() });
+        }
+
+        let amount = undistributed(deps.as_ref(), &env, &collateral)?;
+
+        let distribution = split(deps.storage, &env, &config, &collateral, amount)?
+            .ok_or(ContractError::NothingToDistribute {})?;
+
+        Ok(Response::new()
+            .add_attribute("action", "distribute")
+            .add_attribute("id", distribution.id.to_string())
+            .add_attribute("collateral", collateral.to_string())
+            .add_attribute("amount", distribution.amount))
+
+    }
+
+    /// Credits `amount` to the recipients by weight and records the distribution.
+    /// Returns `None` when every share rounds down to zero.
+    fn split(
+        storage: &mut dyn Storage,
+        env: &Env,
+        config: &Config,
+        collateral: &Collateral,
+        amount: Uint128
+    ) -> StdResult<Option<Distribution>> {
+
+        let total_weight: u128 = config.recipients.iter().map(|recipient| u128::from(recipient.weight)).sum();
+
+        let mut shares = vec![];
+        let mut distributed = Uint128::from(0u128);
+
+        for recipient in &config.recipients {
+
+            let share = amount.multiply_ratio(recipient.weight, total_weight);
+
+            if share.is_zero() {
+                continue;
+            }
+
+            let key = (recipient.address.clone(), collateral.to_string());
+            let mut claimable = CLAIMABLE.may_load(storage, key.clone())?
+                .unwrap_or(ClaimableBalance { collateral: collateral.clone(), amount: Uint128::from(0u128) });
+            claimable.amount += share;
+            CLAIMABLE.save(storage, key, &claimable)?;
+
+            distributed += share;
+
+            shares.push(DistributionShare { address: recipient.address.clone(), amount: share });
+
+        }
+
+        if distributed.is_zero() {
+            return Ok(None);
+        }
+
+        let outstanding = OUTSTANDING.may_load(storage, collateral.to_string())?.unwrap_or_default();
+        OUTSTANDING.save(storage, collateral.to_string(), &(outstanding + distributed))?;
+
+        let id = DISTRIBUTION_COUNT.load(storage)? + 1;
+        DISTRIBUTION_COUNT.save(storage, &id)?;
+
+        let distribution = Distribution {
+            id,
+            collateral: collateral.clone(),
+            amount: distributed,
+            shares,
+            timestamp: env.block.time.seconds()
+        };
+
+        DISTRIBUTIONS.save(storage, id, &distribution)?;
+
+        Ok(Some(distribution))
+
+    }
+
+    pub fn withdraw(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        collateral: Collateral,
+        amount: Option<Uint128>
+    ) -> Result<Response, ContractError> {
+
+        nonpayable(&info)?;
+
+        let recipient = info.sender;
+
+        let key = (recipient.clone(), collateral.to_string());
+
+        let have = CLAIMABLE.may_load(deps.storage, key.clone())?
+            .map(|claimable| claimable.amount)
+            .unwrap_or_default();
+
+        let amount = amount.unwrap_or(have);
+
+        if amount.is_zero() {
+            return Err(ContractError::NothingToWithdraw {});
+        }
+
+        if amount > have {
+            return Err(ContractError::InsufficientBalance { have, want: amount });
+        }
+
+        if amount == have {
+            CLAIMABLE.remove(deps.storage, key);
+        }
+        else {
+            CLAIMABLE.save(deps.storage, key, &ClaimableBalance { collateral: collateral.clone(), amount: have - amount })?;
+        }
+
+        let outstanding = OUTSTANDING.load(deps.storage, collateral.to_string())?;
+        OUTSTANDING.save(deps.storage, collateral.to_string(), &(outstanding - amount))?;
+
+        let id = WITHDRAWAL_COUNT.load(deps.storage)? + 1;
+        WITHDRAWAL_COUNT.save(deps.storage, &id)?;
+
+        WITHDRAWALS.save(deps.storage, (recipient.clone(), id), &Withdrawal {
+            id,
+            recipient: recipient.clone(),
+            collateral: collateral.clone(),
+            amount,
+            timestamp: env.block.time.seconds()
+        })?;
+
+        Ok(Response::new()
+            .add_message(collateral.transfer_msg(&recipient, amount)?)
+            .add_attribute("action", "withdraw")
+            .add_attribute("recipient", recipient)
+            .add_attribute("collateral", collateral.to_string())
+            .add_attribute("amount", amount))
+
+    }
+
+    pub fn update_recipients(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        recipients: Vec<Recipient>
+    ) -> Result<Response, ContractError> {
+
+        let mut config = CONFIG.load(deps.storage)?;
+
+        if info.sender != config.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        validate_recipients(deps.api, &recipients)?;
+
+        let mut response = Response::new().add_attribute("action", "update_recipients");
+
+        // Fees received under the current weights are split by them
+        for collateral in &config.collaterals {
+
+            let amount = undistributed(deps.as_ref(), &env, collateral)?;
+
+            if let Some(distribution) = split(deps.storage, &env, &config, collateral, amount)? {
+                response = response.add_attribute("distribution", distribution.id.to_string());
+            }
+
+        }
+
+        config.recipients = recipients;
+
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(response)
+
+    }
+
+    pub fn update_collaterals(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        collaterals: Vec<Collateral>
+    ) -> Result<Response, ContractError> {
+
+        let mut config = CONFIG.load(deps.storage)?;
+
+        if info.sender != config.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        validate_collaterals(deps.api, &collaterals)?;
+
+        config.collaterals = collaterals;
+
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new().add_attribute("action", "update_collaterals"))
+
+    }
+
+    pub fn update_owner(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        owner: Addr
+    ) -> Result<Response, ContractError> {
+
+        let mut config = CONFIG.load(deps.storage)?;
+
+        if info.sender != config.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        config.owner = deps.api.addr_validate(owner.as_str())?;
+
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_owner")
+            .add_attribute("owner", config.owner))
+
+    }
+
+}
@@ -0,0 +1,7 @@
+pub mod contract;
+mod error;
+mod execute;
+mod query;
+pub mod state;
+
+pub use crate::error::ContractError;
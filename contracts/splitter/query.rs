@@ -0,0 +1,67 @@
+use crate::execute::execute_msg;
+use crate::state::{CLAIMABLE, DISTRIBUTIONS, WITHDRAWALS};
+use cosmwasm_std::{Addr, Deps, Env, Order, StdResult, Uint128};
+use cw_storage_plus::Bound;
+use packages::collateral::Collateral;
+use packages::splitter::{ClaimableBalance, Distribution, Withdrawal};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+pub mod query_msg {
+
+    use super::*;
+
+    pub fn undistributed(deps: Deps, env: Env, collateral: Collateral) -> StdResult<Uint128> {
+        execute_msg::undistributed(deps, &env, &collateral)
+    }
+
+    pub fn claimable(deps: Deps, _env: Env, recipient: Addr, collateral: Collateral) -> StdResult<Uint128> {
+        Ok(CLAIMABLE
+            .may_load(deps.storage, (recipient, collateral.to_string()))?
+            .map(|claimable| claimable.amount)
+            .unwrap_or_default())
+    }
+
+    pub fn list_claimable(deps: Deps, _env: Env, recipient: Addr, start_after: Option<String>, limit: Option<u32>) -> StdResult<Vec<ClaimableBalance>> {
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        CLAIMABLE
+            .prefix(recipient)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, claimable)| claimable))
+            .collect()
+
+    }
+
+    pub fn distributions(deps: Deps, _env: Env, start_after: Option<u64>, limit: Option<u32>) -> StdResult<Vec<Distribution>> {
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        DISTRIBUTIONS
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, distribution)| distribution))
+            .collect()
+
+    }
+
+    pub fn withdrawals(deps: Deps, _env: Env, recipient: Addr, start_after: Option<u64>, limit: Option<u32>) -> StdResult<Vec<Withdrawal>> {
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        WITHDRAWALS
+            .prefix(recipient)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, withdrawal)| withdrawal))
+            .collect()
+
+    }
+
+}
@@ -0,0 +1,19 @@
+use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Uint128};
+use packages::splitter::{ClaimableBalance, Config, Distribution, Withdrawal};
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+/// Distributed but not yet withdrawn, keyed by denom or token address
+pub const OUTSTANDING: Map<String, Uint128> = Map::new("outstanding");
+
+pub const CLAIMABLE: Map<(Addr, String), ClaimableBalance> = Map::new("claimable");
+
+pub const DISTRIBUTION_COUNT: Item<u64> = Item::new("distribution_count");
+
+pub const DISTRIBUTIONS: Map<u64, Distribution> = Map::new("distributions");
+
+pub const WITHDRAWAL_COUNT: Item<u64> = Item::new("withdrawal_count");
+
+/// Withdrawals keyed by recipient and withdrawal id
+pub const WITHDRAWALS: Map<(Addr, u64), Withdrawal> = Map::new("withdrawals");
@@ -0,0 +1,153 @@
+use std::fmt;
+
+use cosmwasm_std::{
+    to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, QuerierWrapper, StdResult, Uint128, WasmMsg
+};
+use cosmwasm_schema::cw_serde;
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Decimals assumed for markets created before collaterals were configurable
+pub const DEFAULT_DECIMALS: u8 = 6;
+
+/// Most decimals a whitelisted collateral may have
+pub const MAX_DECIMALS: u8 = 18;
+
+/// Minimum liquidity assumed for markets created before collaterals were configurable
+pub const DEFAULT_MIN_LIQUIDITY: u128 = 10u128 * 10u128.pow(6u32); // 10 USDC
+
+pub fn default_decimals() -> u8 {
+    DEFAULT_DECIMALS
+}
+
+pub fn default_min_liquidity() -> Uint128 {
+    Uint128::from(DEFAULT_MIN_LIQUIDITY)
+}
+
+/// A whitelisted collateral. `min_liquidity` is in the collateral's base units
+/// and applies to markets created with it.
+#[cw_serde]
+pub struct CollateralConfig {
+    pub collateral: Collateral,
+    pub decimals: u8,
+    pub min_liquidity: Uint128
+}
+
+/// The asset markets are traded in, either a native bank denom or a CW20 token.
+///
+/// Deserializes from a plain denom string as well, which is how the
+/// collateral was stored before CW20 tokens were supported.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Collateral {
+    Native { denom: String },
+    CW20Token { contract_address: Addr }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TaggedCollateral {
+    Native { denom: String },
+    CW20Token { contract_address: Addr }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CollateralRepr {
+    Denom(String),
+    Tagged(TaggedCollateral)
+}
+
+impl<'de> Deserialize<'de> for Collateral {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match CollateralRepr::deserialize(deserializer)? {
+            CollateralRepr::Denom(denom) => Collateral::Native { denom },
+            CollateralRepr::Tagged(TaggedCollateral::Native { denom }) => Collateral::Native { denom },
+            CollateralRepr::Tagged(TaggedCollateral::CW20Token { contract_address }) => Collateral::CW20Token { contract_address }
+        })
+    }
+}
+
+impl Collateral {
+
+    pub fn native(denom: impl Into<String>) -> Self {
+        Collateral::Native { denom: denom.into() }
+    }
+
+    pub fn is_native(&self) -> bool {
+        matches!(self, Collateral::Native { .. })
+    }
+
+    pub fn query_balance(&self, querier: &QuerierWrapper, account: &Addr) -> StdResult<Uint128> {
+
+        match self {
+            Collateral::Native { denom } => Ok(querier.query_balance(account, denom)?.amount),
+            Collateral::CW20Token { contract_address } => {
+                let response: BalanceResponse = querier.query_wasm_smart(
+                    contract_address,
+                    &Cw20QueryMsg::Balance { address: account.to_string() }
+                )?;
+                Ok(response.balance)
+            }
+        }
+
+    }
+
+    /// Sends `amount` to `recipient` without calling it
+    pub fn transfer_msg(&self, recipient: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+
+        let msg = match self {
+            Collateral::Native { denom } => CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![Coin { denom: denom.clone(), amount }]
+            }),
+            Collateral::CW20Token { contract_address } => CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_address.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer { recipient: recipient.to_string(), amount })?,
+                funds: vec![]
+            })
+        };
+
+        Ok(msg)
+
+    }
+
+    /// Executes `msg` on `contract` with `amount` attached. Native funds are
+    /// attached directly, CW20 tokens are sent through the token's `Send` so
+    /// `contract` receives `msg` wrapped in a `Receive`.
+    pub fn execute_msg(&self, contract: &Addr, msg: Binary, amount: Uint128) -> StdResult<WasmMsg> {
+
+        let msg = match self {
+            _ if amount.is_zero() => WasmMsg::Execute {
+                contract_addr: contract.to_string(),
+                msg,
+                funds: vec![]
+            },
+            Collateral::Native { denom } => WasmMsg::Execute {
+                contract_addr: contract.to_string(),
+                msg,
+                funds: vec![Coin { denom: denom.clone(), amount }]
+            },
+            Collateral::CW20Token { contract_address } => WasmMsg::Execute {
+                contract_addr: contract_address.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Send { contract: contract.to_string(), amount, msg })?,
+                funds: vec![]
+            }
+        };
+
+        Ok(msg)
+
+    }
+
+}
+
+/// The denom or token address, also used to key balances
+impl fmt::Display for Collateral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Collateral::Native { denom } => f.write_str(denom),
+            Collateral::CW20Token { contract_address } => f.write_str(contract_address.as_str())
+        }
+    }
+}
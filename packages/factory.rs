@@ -1,31 +1,66 @@
 #![allow(unused_imports)]
 
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Binary, Decimal256, Int128, Uint128};
+use cw20::Cw20ReceiveMsg;
 
-use crate::market::{Shares, Information, Quote};
+use crate::collateral::{Collateral, CollateralConfig};
+
+use crate::market::{Shares, Information, MarketStatus, Outcome, Quote, Side, Trade};
 
 #[cw_serde]
 pub struct InstantiateMsg {
-    pub usdc: String,
+    pub usdc: Collateral,
     pub fees_address: Addr,
     pub market_code_id: u64
 }
 
+/// `super_admin` is only read when migrating a factory from before roles existed,
+/// and is granted every role if no stored admin can be carried over.
+/// `market_code_id` replaces the configured market code id without the config
+/// timelock, so live markets can be moved to code that understands the upgraded
+/// factory's messages straight away
+#[cw_serde]
+pub struct MigrateMsg {
+    #[serde(default)]
+    pub super_admin: Option<Addr>,
+    #[serde(default)]
+    pub market_code_id: Option<u64>
+}
+
+/// `usdc` is the default collateral, used when a market or balance does not name one
 #[cw_serde]
 pub struct Details {
-    pub usdc: String,
+    pub usdc: Collateral,
     pub fees_address: Addr,
     pub market_code_id: u64
 }
 
 #[cw_serde]
 pub struct Statistics {
+    /// Summed across collaterals in 6 decimal units, see `GetCollateralVolumes`
+    /// for each collateral's volume in its base units
     pub volume: Uint128,
     pub total_pools: Uint128,
     pub unique_wallets: Uint128,
     pub active_events: Uint128,
-    pub completed_events: Uint128
+    pub completed_events: Uint128,
+    /// Protocol fee revenue summed across collaterals in 6 decimal units, see `GetFeeRevenue`
+    #[serde(default)]
+    pub fees_collected: Uint128
+}
+
+/// Activity during one UTC day, `day` counting days since the Unix epoch
+#[cw_serde]
+#[derive(Default)]
+pub struct DailyStatistics {
+    pub day: u64,
+    /// Summed across collaterals in 6 decimal units
+    pub volume: Uint128,
+    pub trades: Uint128,
+    pub new_wallets: Uint128,
+    pub markets_created: Uint128,
+    pub markets_resolved: Uint128
 }
 
 #[cw_serde]
@@ -35,7 +70,9 @@ pub enum ExecuteMsg {
         description: String,
         end_date: u64,
         categories: Vec<String>,
-        media: [String; 2]
+        media: [String; 2],
+        /// A whitelisted collateral, the default collateral if not set
+        collateral: Option<Collateral>
     },
     RecordStats {
         amount: Uint128,
@@ -43,12 +80,32 @@ pub enum ExecuteMsg {
         stat_type: String,
         data: Vec<Uint128>
     },
+    /// Grants every role except `SuperAdmin`. Kept for compatibility, prefer `GrantRole`.
     AddAdmin {
         account: Addr
     },
+    /// Revokes every role except `SuperAdmin`. Kept for compatibility, prefer `RevokeRole`.
     RemoveAdmin {
         account: Addr
     },
+    GrantRole {
+        account: Addr,
+        role: Role
+    },
+    /// The last `SuperAdmin` cannot be revoked
+    RevokeRole {
+        account: Addr,
+        role: Role
+    },
+    /// Nominates `account` to take over the sender's `SuperAdmin` role once
+    /// it calls `AcceptSuperAdmin`
+    TransferSuperAdmin {
+        account: Addr
+    },
+    AcceptSuperAdmin {},
+    /// Stops market creation, liquidity provision and trading
+    Pause {},
+    Unpause {},
     InitializeLiquidity {
         market: Addr,
         yes_price: Uint128,
@@ -62,23 +119,421 @@ pub enum ExecuteMsg {
         market: Addr,
         shares: Uint128
     },
+    /// Pays out the winning shares. Losing shares are written off and their
+    /// cost realized as a loss, claiming the losing outcome does only that.
     Claim {
         market: Addr,
-        variant: Uint128
+        variant: Outcome,
+        on_behalf_of: Option<Addr>,
+        #[serde(default)]
+        use_balance: bool
     },
+    /// Resolves `market` to its proposed outcome once the proposal's
+    /// challenge period has passed
     ResolveMarket {
         market: Addr,
-        variant: Uint128,
+        variant: Outcome,
         market_index: u128
     },
+    ProposeResolution {
+        market: Addr,
+        variant: Outcome
+    },
+    FinalizeMarket {
+        market: Addr
+    },
+    CancelMarket {
+        market: Addr,
+        market_index: u128
+    },
+    /// With `use_balance` buys are paid from, and sell proceeds credited to,
+    /// the account's internal balance instead of attached funds.
     PlaceOrder {
         market: Addr,
-        variant: Uint128,
-        buy_or_sell: Uint128,
-        amount: Uint128
+        variant: Outcome,
+        buy_or_sell: Side,
+        amount: Uint128,
+        min_out: Option<Uint128>,
+        on_behalf_of: Option<Addr>,
+        #[serde(default)]
+        use_balance: bool,
+        /// Recorded for the trading account on its first trade and kept after that.
+        /// Rejected when trading `on_behalf_of` another account.
+        #[serde(default)]
+        referrer: Option<Addr>
+    },
+    RegisterSigningKey {
+        pubkey: Binary
+    },
+    Deposit {},
+    /// Withdraws from the internal balance held in `collateral`, the current collateral by default
+    Withdraw {
+        amount: Uint128,
+        collateral: Option<Collateral>
+    },
+    SubmitSignedOrder {
+        signer: Addr,
+        order: SignedOrder,
+        signature: Binary
+    },
+    GrantTrading {
+        operator: Addr,
+        expires: u64,
+        max_notional: Uint128,
+        markets: Option<Vec<Addr>>
+    },
+    RevokeTrading {
+        operator: Addr
+    },
+    /// Buy amounts must add up to the attached USDC, or are debited from the
+    /// internal balance with `use_balance`. Without `atomic`, a failing order
+    /// is skipped and its buy amount refunded.
+    BatchPlaceOrder {
+        orders: Vec<BatchOrder>,
+        atomic: bool,
+        #[serde(default)]
+        use_balance: bool
+    },
+    /// Called by markets to credit USDC payouts to an internal balance
+    CreditBalance {
+        account: Addr
+    },
+    /// Migrates markets to `new_code_id` a page at a time, ordered by market
+    /// index. `new_code_id` must match the market code id set through the
+    /// timelocked `UpdateConfig`. Markets created before the search indexes
+    /// existed are indexed on the way.
+    MigrateMarkets {
+        new_code_id: u64,
+        start_after: Option<u128>,
+        limit: Option<u32>
+    },
+    /// Proposes new configuration values, applied by `ExecuteConfigUpdate` once
+    /// the timelock has passed. Fields left as `None` are kept.
+    UpdateConfig {
+        fees_address: Option<Addr>,
+        market_code_id: Option<u64>,
+        usdc: Option<Collateral>
+    },
+    ExecuteConfigUpdate {},
+    CancelConfigUpdate {},
+    /// Whitelists a collateral for new markets or updates its configuration.
+    /// `decimals` must not exceed 18.
+    SetCollateral {
+        collateral: Collateral,
+        decimals: u8,
+        min_liquidity: Uint128
+    },
+    /// Stops new markets from using a collateral, existing markets are unaffected
+    RemoveCollateral {
+        collateral: Collateral
+    },
+    /// Adds a category to the registry. Once the registry has any category,
+    /// new markets may only use registered ones.
+    AddCategory {
+        category: String
+    },
+    /// Removes a category from the registry, existing markets stay listed under it
+    RemoveCategory {
+        category: String
+    },
+    /// Redeems the winning fee shares held by the fees address, or left with
+    /// former fees addresses, in resolved markets. Markets that are not
+    /// resolved are skipped.
+    CollectFees {
+        markets: Vec<Addr>
+    },
+    /// Called by markets with a referrer's cut of a trade fee
+    CreditReferral {
+        referrer: Addr
+    },
+    /// Pays out the sender's referral earnings in `collateral`, the current collateral by default
+    ClaimReferralEarnings {
+        collateral: Option<Collateral>
+    },
+    /// Sets the referrers' cut of the fee going to the fees address, in basis points
+    SetReferralShare {
+        share_bps: u16
+    },
+    /// Sets the points rules. Epochs start counting the first time this is called.
+    SetPointsConfig {
+        per_volume: Uint128,
+        per_lp_day: Uint128,
+        per_win: Uint128,
+        epoch_cap: Option<Uint128>
+    },
+    /// Credits the sender's liquidity points accrued so far in `markets`
+    AccrueLiquidityPoints {
+        markets: Vec<Addr>
+    },
+    /// Adds the attached reward token to a finished epoch, split by points.
+    /// Requires `FeeManager`, and the token must be a whitelisted collateral.
+    FundEpochRewards {
+        epoch: u64
+    },
+    /// Pays out the sender's share of the rewards of `epochs`
+    ClaimEpochRewards {
+        epochs: Vec<u64>
+    },
+    /// Adds the attached reward token to `market`'s liquidity mining budget,
+    /// emitted at `reward_per_second` until it runs out
+    FundLiquidityRewards {
+        market: Addr,
+        reward_per_second: Uint128
+    },
+    /// Pays out the sender's liquidity mining rewards in `markets`
+    ClaimLiquidityRewards {
+        markets: Vec<Addr>
+    },
+    /// CW20 collateral sent to the factory, wrapping a `ReceiveMsg`
+    Receive(Cw20ReceiveMsg)
+}
+
+/// Actions paid for with CW20 collateral. The sent amount is the liquidity,
+/// buy amount or deposit.
+#[cw_serde]
+pub enum ReceiveMsg {
+    InitializeLiquidity {
+        market: Addr,
+        yes_price: Uint128
+    },
+    AddLiquidity {
+        market: Addr
+    },
+    /// Buys `variant` with the sent amount
+    PlaceOrder {
+        market: Addr,
+        variant: Outcome,
+        min_out: Option<Uint128>,
+        on_behalf_of: Option<Addr>,
+        #[serde(default)]
+        referrer: Option<Addr>
+    },
+    Deposit {},
+    /// Sent by markets to credit payouts to an internal balance
+    CreditBalance {
+        account: Addr
+    },
+    /// Sent by markets with a referrer's cut of a trade fee
+    CreditReferral {
+        referrer: Addr
+    },
+    /// Adds the sent tokens to a finished epoch's rewards
+    FundEpochRewards {
+        epoch: u64
+    },
+    /// Adds the sent tokens to `market`'s liquidity mining budget
+    FundLiquidityRewards {
+        market: Addr,
+        reward_per_second: Uint128
     }
 }
 
+/// An order signed off-chain by `signer` and relayed by any account.
+/// Buys are paid from, and sell proceeds credited to, the signer's deposited balance.
+#[cw_serde]
+pub struct SignedOrder {
+    pub market: Addr,
+    pub variant: Outcome,
+    pub buy_or_sell: Side,
+    pub amount: Uint128,
+    pub min_out: Uint128,
+    pub nonce: u64,
+    pub expiry: u64
+}
+
+/// The canonical payload of a signed order. The signature is made over the
+/// sha256 hash of this struct serialized to JSON, fields in declaration order.
+#[cw_serde]
+pub struct SignedOrderPayload {
+    pub chain_id: String,
+    pub factory: Addr,
+    pub signer: Addr,
+    pub order: SignedOrder
+}
+
+#[cw_serde]
+pub struct BatchOrder {
+    pub market: Addr,
+    pub variant: Outcome,
+    pub buy_or_sell: Side,
+    pub amount: Uint128,
+    pub min_out: Option<Uint128>
+}
+
+#[cw_serde]
+pub struct PendingBatchOrder {
+    pub account: Addr,
+    pub refund: Uint128,
+    pub collateral: Collateral,
+    pub use_balance: bool
+}
+
+/// Factory permissions. A `SuperAdmin` manages roles and holds every other role.
+#[cw_serde]
+#[derive(Copy)]
+pub enum Role {
+    SuperAdmin,
+    MarketCreator,
+    Resolver,
+    Pauser,
+    FeeManager
+}
+
+impl Role {
+    /// The roles granted by `AddAdmin`
+    pub const OPERATIONAL: [Role; 4] = [Role::MarketCreator, Role::Resolver, Role::Pauser, Role::FeeManager];
+}
+
+#[cw_serde]
+pub struct RoleAssignment {
+    pub account: Addr,
+    pub roles: Vec<Role>
+}
+
+#[cw_serde]
+pub struct PendingSuperAdmin {
+    pub from: Addr,
+    pub to: Addr
+}
+
+#[cw_serde]
+pub struct CollateralVolume {
+    pub collateral: Collateral,
+    pub volume: Uint128
+}
+
+/// Points rules. Collateral amounts are counted in whole units using the
+/// collateral's decimals.
+#[cw_serde]
+pub struct PointsConfig {
+    pub per_volume: Uint128,
+    pub per_lp_day: Uint128,
+    pub per_win: Uint128,
+    /// Most points an account can earn in a single epoch
+    pub epoch_cap: Option<Uint128>,
+    /// Start of epoch 0
+    pub genesis: u64
+}
+
+#[cw_serde]
+pub struct AccountPoints {
+    pub total: Uint128,
+    pub current_epoch: u64,
+    pub epoch_points: Uint128
+}
+
+#[cw_serde]
+pub struct EpochReward {
+    pub token: Collateral,
+    pub amount: Uint128
+}
+
+/// Points earned in an epoch. Totals are final once the epoch has ended.
+#[cw_serde]
+pub struct EpochInfo {
+    pub epoch: u64,
+    pub start: u64,
+    pub end: u64,
+    pub total_points: Uint128,
+    pub reward: Option<EpochReward>
+}
+
+/// An account's liquidity in a market as last reported by the market. `amount`
+/// is its value in collateral base units and earns points, `shares` earn
+/// liquidity mining rewards settled up to `reward_per_share_paid`.
+#[cw_serde]
+#[derive(Default)]
+pub struct LiquidityPosition {
+    pub amount: Uint128,
+    pub accrued_at: u64,
+    pub shares: Uint128,
+    pub reward_per_share_paid: Decimal256,
+    pub pending: Uint128
+}
+
+/// Trading totals of an account, all time or in one points epoch. Volume and
+/// PnL are summed over collaterals in 6 decimal units. PnL is realized by sells and claims.
+#[cw_serde]
+#[derive(Default)]
+pub struct AccountStats {
+    pub volume: Uint128,
+    pub trades: u64,
+    pub realized_pnl: Int128
+}
+
+#[cw_serde]
+pub enum LeaderboardMetric {
+    Volume,
+    RealizedPnl
+}
+
+#[cw_serde]
+pub struct LeaderboardEntry {
+    pub account: Addr,
+    pub stats: AccountStats
+}
+
+/// `next_start_after` is set when the page is full and continues the ranking
+/// when passed as `start_after`. It is an opaque position in the ranking, so
+/// trades between pages cannot skip or repeat accounts.
+#[cw_serde]
+pub struct LeaderboardPage {
+    pub entries: Vec<LeaderboardEntry>,
+    pub next_start_after: Option<Binary>
+}
+
+/// A market's liquidity mining program. `token` is emitted at
+/// `reward_per_second` until `end` and split between liquidity shares.
+#[cw_serde]
+pub struct LiquidityRewards {
+    pub token: Collateral,
+    pub reward_per_second: Uint128,
+    pub end: u64,
+    pub last_update: u64,
+    /// Rewards emitted per liquidity share since the program started
+    pub reward_per_share: Decimal256,
+    /// The market's liquidity shares as last reported
+    pub total_shares: Uint128
+}
+
+#[cw_serde]
+pub struct ReferralEarnings {
+    pub collateral: Collateral,
+    /// Everything earned so far, including what has been claimed
+    pub earned: Uint128,
+    pub claimable: Uint128
+}
+
+#[cw_serde]
+pub struct ReferralStats {
+    pub referees: u64,
+    /// Traded volume of the referees since they were referred, summed across collaterals in 6 decimal units
+    pub volume: Uint128,
+    pub earnings: Vec<ReferralEarnings>
+}
+
+/// A configuration change waiting for its timelock to pass
+#[cw_serde]
+pub struct PendingConfig {
+    pub fees_address: Option<Addr>,
+    pub market_code_id: Option<u64>,
+    pub usdc: Option<Collateral>,
+    pub proposed_by: Addr,
+    pub executable_at: u64
+}
+
+/// Permission for `operator` to place orders and claim on behalf of `granter`.
+/// `markets` restricts the grant to the listed markets, `None` allows all.
+#[cw_serde]
+pub struct TradingGrant {
+    pub granter: Addr,
+    pub operator: Addr,
+    pub expires: u64,
+    pub max_notional: Uint128,
+    pub used_notional: Uint128,
+    pub markets: Option<Vec<Addr>>
+}
+
 #[cw_serde]
 pub struct MarketInfo {
     pub information: Information,
@@ -87,11 +542,97 @@ pub struct MarketInfo {
     pub media: [String; 2]
 }
 
+/// An account's holdings in one market. Values are in USDC at current prices,
+/// `claimable` is what the winning shares redeem for once resolved.
+#[cw_serde]
+pub struct PortfolioEntry {
+    pub market: Addr,
+    pub title: String,
+    pub yes_shares: Uint128,
+    pub no_shares: Uint128,
+    pub liquidity_shares: Uint128,
+    pub position_value: Uint128,
+    pub liquidity_value: Uint128,
+    pub resolved: bool,
+    pub claimable: Uint128
+}
+
+/// Every market the account has traded counts toward the page limit, so a
+/// page may hold fewer entries than the limit. `next_start_after` is set
+/// while markets are left and continues the listing when passed as `start_after`.
+/// Markets whose position could not be queried are listed in `unavailable`.
+#[cw_serde]
+pub struct Portfolio {
+    pub entries: Vec<PortfolioEntry>,
+    pub unavailable: Vec<Addr>,
+    pub next_start_after: Option<Addr>
+}
+
+/// Markets paged through by `FetchMarkets`. Serialized as `"all"`, `"active"`
+/// or `"completed"`; the legacy numeric form `"0"` / `"1"` / `"2"` is still accepted.
+#[cw_serde]
+#[derive(Copy, Eq)]
+pub enum MarketType {
+    #[serde(alias = "0")]
+    All,
+    #[serde(alias = "1")]
+    Active,
+    #[serde(alias = "2")]
+    Completed
+}
+
+/// Filters of `SearchMarkets`, unset fields match every market
+#[cw_serde]
+#[derive(Default)]
+pub struct MarketFilter {
+    pub owner: Option<Addr>,
+    pub category: Option<String>,
+    pub status: Option<MarketStatus>,
+    /// Trading ends at or after this time
+    pub ends_after: Option<u64>,
+    /// Trading ends before this time
+    pub ends_before: Option<u64>,
+    /// Bounds on the collateral in the pool, YES plus NO liquidity
+    pub min_liquidity: Option<Uint128>,
+    pub max_liquidity: Option<Uint128>,
+    /// Resolved markets that resolved to this outcome
+    pub outcome: Option<Outcome>
+}
+
+#[cw_serde]
+#[derive(Copy, Default)]
+pub enum MarketSort {
+    /// Market index, the order markets were created in
+    #[default]
+    Created,
+    EndDate,
+    Volume
+}
+
+#[cw_serde]
+pub struct CategoryInfo {
+    pub name: String,
+    pub registered: bool,
+    /// Markets created under the category
+    pub markets: u64
+}
+
+#[cw_serde]
+pub struct MarketTrade {
+    pub market: Addr,
+    pub trade: Trade
+}
+
+/// Filtered listings scan a bounded number of markets per call, so a page
+/// may come back short or even empty. `next_start_after` is set while markets
+/// are left to scan and continues the listing when passed as `start_after`.
 #[cw_serde]
 pub struct MarketList {
     pub information: Vec<Information>,
     pub contracts: Vec<Addr>,
-    pub indexes: Vec<u128>
+    pub indexes: Vec<u128>,
+    #[serde(default)]
+    pub next_start_after: Option<u128>
 }
 
 #[cw_serde]
@@ -108,7 +649,7 @@ pub struct TempInformation {
     pub market_end: u64,
     pub categories: Vec<String>,
     pub liquidity_shares: Uint128,
-    pub usdc: String,
+    pub usdc: Collateral,
     pub owner: Addr,
     pub resolved: bool,
     pub factory: Addr,
@@ -123,16 +664,121 @@ pub enum QueryMsg {
     GetMarketInfo { contract_address: Addr, account: Addr },
     #[returns(Statistics)]
     GetStatistics {},
+    /// Statistics of each day from `from_day` to `to_day` inclusive, zero for
+    /// days without activity. Days count from the Unix epoch, at most 366 per query.
+    #[returns(Vec<DailyStatistics>)]
+    GetStatisticsHistory { from_day: u64, to_day: u64 },
+    #[returns(MarketList)]
+    FetchMarkets { page: u128, items_per_page: u128, account: Addr, market_type: MarketType },
+    /// Markets matching every field set in `filter`, sorted by `sort_by`.
+    /// `start_after` is the `next_start_after` of the previous page.
+    #[returns(MarketList)]
+    SearchMarkets {
+        filter: MarketFilter,
+        #[serde(default)]
+        sort_by: MarketSort,
+        #[serde(default)]
+        descending: bool,
+        start_after: Option<u128>,
+        limit: Option<u32>
+    },
+    /// Markets currently in `status`, newest first. `start_after` is a market
+    /// index, continue from `next_start_after` when a page comes back short.
+    /// Markets created before the status index existed are listed once
+    /// backfilled by `MigrateMarkets`.
+    #[returns(MarketList)]
+    FetchMarketsByStatus { status: MarketStatus, account: Addr, start_after: Option<u128>, limit: Option<u32> },
+    /// Markets listed under `category`, newest first, optionally only those
+    /// currently in `status`. `start_after` is a market index, continue from
+    /// `next_start_after` when a page comes back short.
     #[returns(MarketList)]
-    FetchMarkets { page: u128, items_per_page: u128, account: Addr, market_type: u128 },
+    FetchMarketsByCategory { category: String, status: Option<MarketStatus>, start_after: Option<u128>, limit: Option<u32> },
+    /// Categories used by markets or registered, ordered by name
+    #[returns(Vec<CategoryInfo>)]
+    ListCategories { start_after: Option<String>, limit: Option<u32> },
     #[returns(Details)]
     Details {},
+    #[returns(Option<PendingConfig>)]
+    PendingConfig {},
+    /// Whitelisted collaterals, `start_after` is a denom or token address
+    #[returns(Vec<CollateralConfig>)]
+    ListCollaterals { start_after: Option<String>, limit: Option<u32> },
+    /// Traded volume per collateral, `start_after` is a denom or token address
+    #[returns(Vec<CollateralVolume>)]
+    GetCollateralVolumes { start_after: Option<String>, limit: Option<u32> },
+    /// Cumulative protocol fee revenue of a market in its collateral, from
+    /// collected fee shares and sell fees
+    #[returns(Uint128)]
+    GetFeeRevenue { market: Addr },
     #[returns(Addr)]
     FeesAddress {},
+    #[returns(Option<Addr>)]
+    GetReferrer { account: Addr },
+    #[returns(ReferralStats)]
+    GetReferralStats { referrer: Addr },
+    /// Accounts referred by `referrer`, `start_after` is a referee address
+    #[returns(Vec<Addr>)]
+    ListReferees { referrer: Addr, start_after: Option<Addr>, limit: Option<u32> },
+    /// The referrers' cut of the fee going to the fees address, in basis points
+    #[returns(u16)]
+    ReferralShare {},
     #[returns(Quote)]
-    Quote { market: Addr, variant: Uint128, buy_or_sell: Uint128, amount: Uint128 },
+    Quote { market: Addr, variant: Outcome, buy_or_sell: Side, amount: Uint128 },
+    /// True if `account` is a super admin. Kept for compatibility, prefer `HasRole`.
     #[returns(bool)]
     IsAdmin { account: Addr },
+    #[returns(bool)]
+    HasRole { account: Addr, role: Role },
+    /// Accounts holding at least one role, ordered by address
+    #[returns(Vec<RoleAssignment>)]
+    ListRoles { start_after: Option<Addr>, limit: Option<u32> },
+    #[returns(Option<PendingSuperAdmin>)]
+    PendingSuperAdmin {},
+    #[returns(bool)]
+    IsPaused {},
+    /// Legacy per-order incentives, no longer accrued. See `GetPoints`.
+    #[returns(u64)]
+    GetIncentives { account: Addr },
+    #[returns(Option<PointsConfig>)]
+    PointsConfig {},
+    #[returns(AccountPoints)]
+    GetPoints { account: Addr },
+    #[returns(Uint128)]
+    GetEpochPoints { epoch: u64, account: Addr },
+    #[returns(EpochInfo)]
+    GetEpoch { epoch: u64 },
+    /// Rewards of `epoch` that `account` can still claim
+    #[returns(Uint128)]
+    GetEpochRewards { epoch: u64, account: Addr },
+    /// Trading totals of `account` over all time, or in `epoch` when set
+    #[returns(AccountStats)]
+    GetAccountStats { account: Addr, epoch: Option<u64> },
+    /// Accounts ranked by `metric` over all time, or in `epoch` when set,
+    /// best first. `start_after` is the `next_start_after` of the previous page
+    /// for the same metric and epoch.
+    #[returns(LeaderboardPage)]
+    Leaderboard { metric: LeaderboardMetric, epoch: Option<u64>, start_after: Option<Binary>, limit: Option<u32> },
+    #[returns(Option<LiquidityRewards>)]
+    GetLiquidityRewards { market: Addr },
+    /// Liquidity mining rewards `account` can claim in `market`
+    #[returns(Uint128)]
+    PendingLiquidityRewards { market: Addr, account: Addr },
+    /// Internal balance held in `collateral`, the current collateral by default
+    #[returns(Uint128)]
+    GetBalance { account: Addr, collateral: Option<Collateral> },
     #[returns(u64)]
-    GetIncentives { account: Addr }
+    GetNonce { account: Addr },
+    #[returns(Option<Binary>)]
+    GetSigningKey { account: Addr },
+    #[returns(Option<TradingGrant>)]
+    GetTradingGrant { granter: Addr, operator: Addr },
+    #[returns(Vec<TradingGrant>)]
+    ListTradingGrants { granter: Addr, start_after: Option<Addr>, limit: Option<u32> },
+    /// Trades of `account` across all markets it traded in, ordered by market
+    /// and newest first within a market. `start_after` is a (market, trade id) cursor.
+    #[returns(Vec<MarketTrade>)]
+    GetAccountTrades { account: Addr, start_after: Option<(Addr, u64)>, limit: Option<u32> },
+    /// Markets where `account` holds YES, NO or liquidity shares
+    #[returns(Portfolio)]
+    GetPortfolio { account: Addr, start_after: Option<Addr>, limit: Option<u32> }
 }
\ No newline at end of file
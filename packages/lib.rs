@@ -1,2 +1,4 @@
+pub mod collateral;
 pub mod factory;
-pub mod market;
\ No newline at end of file
+pub mod market;
+pub mod splitter;
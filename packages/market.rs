@@ -1,6 +1,19 @@
+use std::fmt;
+
 use cosmwasm_schema::cw_serde;
 use cosmwasm_schema::QueryResponses;
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Int128, Uint128};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Metadata, Schema, SchemaObject, SubschemaValidation};
+use schemars::JsonSchema;
+use serde::de::{self, Deserializer, Unexpected, Visitor};
+use serde::{Deserialize, Serialize};
+use cw20::Cw20ReceiveMsg;
+
+use crate::collateral::{default_decimals, default_min_liquidity, Collateral};
+
+/// Seconds a resolution proposal can be challenged before the market resolves to it
+pub const RESOLVE_DURATION: u64 = 180;
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -8,11 +21,18 @@ pub struct InstantiateMsg {
     pub description: String,
     pub end_date: u64,
     pub categories: Vec<String>,
-    pub usdc: String,
+    pub usdc: Collateral,
     pub owner: Addr,
-    pub factory: Addr
+    pub factory: Addr,
+    #[serde(default = "default_decimals")]
+    pub decimals: u8,
+    #[serde(default = "default_min_liquidity")]
+    pub min_liquidity: Uint128
 }
 
+#[cw_serde]
+pub struct MigrateMsg {}
+
 #[cw_serde]
 pub enum ExecuteMsg {
     InitializeLiquidity {
@@ -28,20 +48,249 @@ pub enum ExecuteMsg {
         shares: Uint128,
         receiver: Addr
     },
+    /// With `credit_balance` the payout is credited to the receiver's
+    /// internal balance in the factory instead of being sent to it.
+    /// Losing shares are burned and their cost realized as a loss, claiming
+    /// the losing outcome does only that.
     Claim {
-        variant: Uint128,
-        receiver: Addr
+        variant: Outcome,
+        receiver: Addr,
+        #[serde(default)]
+        credit_balance: bool
     },
+    /// Resolves to the proposed outcome once `RESOLVE_DURATION` has passed
+    /// since it was proposed
     ResolveMarket {
-        variant: Uint128,
+        variant: Outcome,
         receiver: Addr,
         market_index: u128
     },
     PlaceOrder {
-        variant: Uint128,
-        buy_or_sell: Uint128,
+        variant: Outcome,
+        buy_or_sell: Side,
         amount: Uint128,
+        receiver: Addr,
+        min_out: Option<Uint128>,
+        #[serde(default)]
+        credit_balance: bool,
+        /// Set by the factory when the trader was referred
+        #[serde(default)]
+        referral: Option<Referral>
+    },
+    /// Proposing a different outcome challenges the current proposal and
+    /// restarts the challenge period
+    ProposeResolution {
+        variant: Outcome,
+        receiver: Addr
+    },
+    FinalizeMarket {
         receiver: Addr
+    },
+    CancelMarket {
+        receiver: Addr,
+        market_index: u128
+    },
+    /// Redeems the winning shares held by the fees address after resolution
+    /// and pays them out to it. Losing fee shares are burned. Shares still held
+    /// by `former_fees_addresses` are moved to the fees address first.
+    CollectFees {
+        #[serde(default)]
+        former_fees_addresses: Vec<Addr>
+    },
+    /// CW20 collateral sent by the factory, wrapping one of the messages above
+    Receive(Cw20ReceiveMsg)
+}
+
+/// The referrer's cut of the fee going to the fees address, in basis points.
+/// Paid in outcome shares on buys and in collateral on sells.
+#[cw_serde]
+pub struct Referral {
+    pub referrer: Addr,
+    pub share_bps: u16
+}
+
+/// Outcome of a binary market. Serialized as `"yes"` / `"no"`; the legacy
+/// numeric form `"1"` / `"0"` is still accepted.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Yes,
+    No
+}
+
+/// Side of an order. Serialized as `"buy"` / `"sell"`; the legacy numeric
+/// form `"1"` / `"0"` is still accepted.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Buy,
+    Sell
+}
+
+impl From<Outcome> for Uint128 {
+    fn from(outcome: Outcome) -> Self {
+        match outcome {
+            Outcome::Yes => Uint128::from(1u128),
+            Outcome::No => Uint128::from(0u128)
+        }
+    }
+}
+
+impl From<Side> for Uint128 {
+    fn from(side: Side) -> Self {
+        match side {
+            Side::Buy => Uint128::from(1u128),
+            Side::Sell => Uint128::from(0u128)
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self { Outcome::Yes => "yes", Outcome::No => "no" })
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self { Side::Buy => "buy", Side::Sell => "sell" })
+    }
+}
+
+/// Accepts the variant name, or its legacy value as a `Uint128` string or a number
+struct BinaryVisitor<T> {
+    one: (&'static str, T),
+    zero: (&'static str, T),
+    expecting: &'static str
+}
+
+impl<'de, T> Visitor<'de> for BinaryVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.expecting)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<T, E> {
+        if value == self.one.0 || value == "1" {
+            Ok(self.one.1)
+        }
+        else if value == self.zero.0 || value == "0" {
+            Ok(self.zero.1)
+        }
+        else {
+            Err(E::invalid_value(Unexpected::Str(value), &self))
+        }
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<T, E> {
+        match value {
+            1 => Ok(self.one.1),
+            0 => Ok(self.zero.1),
+            _ => Err(E::invalid_value(Unexpected::Unsigned(value), &self))
+        }
+    }
+}
+
+/// Advertises every form `BinaryVisitor` accepts, the variant names first
+fn binary_schema(one: &str, zero: &str, description: &str) -> Schema {
+
+    let strings = |values: [&str; 2]| Schema::Object(SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        enum_values: Some(values.iter().map(|value| (*value).into()).collect()),
+        ..Default::default()
+    });
+
+    let integers = Schema::Object(SchemaObject {
+        instance_type: Some(InstanceType::Integer.into()),
+        enum_values: Some(vec![1.into(), 0.into()]),
+        ..Default::default()
+    });
+
+    Schema::Object(SchemaObject {
+        metadata: Some(Box::new(Metadata { description: Some(description.to_string()), ..Default::default() })),
+        subschemas: Some(Box::new(SubschemaValidation {
+            one_of: Some(vec![
+                strings([one, zero]),
+                strings(["1", "0"]),
+                integers
+            ]),
+            ..Default::default()
+        })),
+        ..Default::default()
+    })
+
+}
+
+impl JsonSchema for Outcome {
+    fn schema_name() -> String {
+        "Outcome".to_string()
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        binary_schema("yes", "no", "Outcome of a binary market, `\"1\"` / `1` is yes and `\"0\"` / `0` is no")
+    }
+}
+
+impl JsonSchema for Side {
+    fn schema_name() -> String {
+        "Side".to_string()
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        binary_schema("buy", "sell", "Side of an order, `\"1\"` / `1` is buy and `\"0\"` / `0` is sell")
+    }
+}
+
+impl<'de> Deserialize<'de> for Outcome {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(BinaryVisitor {
+            one: ("yes", Outcome::Yes),
+            zero: ("no", Outcome::No),
+            expecting: "\"yes\", \"no\", \"1\" or \"0\""
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Side {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(BinaryVisitor {
+            one: ("buy", Side::Buy),
+            zero: ("sell", Side::Sell),
+            expecting: "\"buy\", \"sell\", \"1\" or \"0\""
+        })
+    }
+}
+
+/// Lifecycle of a market. `TradingClosed` is reached implicitly once
+/// `market_end` has passed on an open market.
+#[cw_serde]
+#[derive(Copy, Default)]
+pub enum MarketStatus {
+    #[default]
+    Created,
+    Open,
+    TradingClosed,
+    ResolutionProposed,
+    Resolved,
+    Finalized,
+    Cancelled
+}
+
+impl MarketStatus {
+    /// Applies the time based transition from `Open` to `TradingClosed`
+    pub fn at(self, now: u64, market_end: u64) -> MarketStatus {
+        if self == MarketStatus::Open && now >= market_end {
+            MarketStatus::TradingClosed
+        }
+        else {
+            self
+        }
+    }
+
+    /// Whether the outcome is settled and liquidity may leave without the minimum floor
+    pub fn is_settled(&self) -> bool {
+        matches!(self, MarketStatus::Resolved | MarketStatus::Finalized | MarketStatus::Cancelled)
     }
 }
 
@@ -59,11 +308,41 @@ pub struct Information {
     pub market_end: u64,
     pub categories: Vec<String>,
     pub liquidity_shares: Uint128,
-    pub usdc: String,
+    pub usdc: Collateral,
+    /// Missing on markets stored before collaterals were configurable
+    #[serde(default = "default_decimals")]
+    pub decimals: u8,
+    #[serde(default = "default_min_liquidity")]
+    pub min_liquidity: Uint128,
     pub owner: Addr,
     pub resolved: bool,
     pub factory: Addr,
-    pub resolved_to: Uint128
+    pub resolved_to: Uint128,
+    /// Missing on markets stored before the lifecycle existed, set on migration
+    #[serde(default)]
+    pub status: MarketStatus,
+    pub proposed_to: Option<Outcome>,
+    /// When `proposed_to` last changed
+    pub proposed_at: Option<u64>
+}
+
+impl Information {
+    pub fn current_status(&self, now: u64) -> MarketStatus {
+        self.status.at(now, self.market_end)
+    }
+
+    /// Status of a market stored before the lifecycle existed
+    pub fn inferred_status(&self) -> MarketStatus {
+        if self.resolved {
+            MarketStatus::Resolved
+        }
+        else if self.yes_price.is_zero() {
+            MarketStatus::Created
+        }
+        else {
+            MarketStatus::Open
+        }
+    }
 }
 
 #[cw_serde]
@@ -72,6 +351,21 @@ pub struct Order {
     pub price: Uint128,
 }
 
+/// A single fill. `amount` is the USDC paid for a buy or received for a sell,
+/// `fees` is valued in USDC and `price` is the traded outcome's price after the fill.
+#[cw_serde]
+pub struct Trade {
+    pub id: u64,
+    pub account: Addr,
+    pub variant: Outcome,
+    pub buy_or_sell: Side,
+    pub amount: Uint128,
+    pub shares: Uint128,
+    pub fees: Uint128,
+    pub price: Uint128,
+    pub timestamp: u64
+}
+
 #[cw_serde]
 pub struct Shares {
     pub yes_shares: Uint128,
@@ -95,6 +389,57 @@ impl Default for Shares {
     }
 }
 
+/// Cost basis of an account's outcome shares and the PnL realized from
+/// sells and claims, all in USDC.
+#[cw_serde]
+#[derive(Default)]
+pub struct Position {
+    pub yes_cost: Uint128,
+    pub no_cost: Uint128,
+    pub realized_pnl: Int128
+}
+
+/// Prices are scaled by 10^8. `*_value` marks shares at the current price,
+/// `*_liquidation_value` is what selling them now pays after impact and fees,
+/// `None` when the pool has too little liquidity to quote the sale.
+#[cw_serde]
+pub struct PositionInfo {
+    pub yes_shares: Uint128,
+    pub no_shares: Uint128,
+    pub yes_cost: Uint128,
+    pub no_cost: Uint128,
+    pub yes_average_price: Uint128,
+    pub no_average_price: Uint128,
+    pub yes_value: Uint128,
+    pub no_value: Uint128,
+    pub yes_liquidation_value: Option<Uint128>,
+    pub no_liquidation_value: Option<Uint128>,
+    pub unrealized_pnl: Int128,
+    pub realized_pnl: Int128
+}
+
+/// Result of adding `amount` USDC of liquidity, with the pool state after it
+#[cw_serde]
+pub struct AddLiquiditySimulation {
+    pub shares_minted: Uint128,
+    pub yes_liquidity: Uint128,
+    pub no_liquidity: Uint128,
+    pub liquidity_shares: Uint128
+}
+
+/// Result of burning liquidity shares, with the pool state after it.
+/// `capped_to_balance` is set when the payout is limited by the contract's
+/// USDC balance, `below_minimum_liquidity` when the 10 USDC floor would reject it.
+#[cw_serde]
+pub struct RemoveLiquiditySimulation {
+    pub amount_out: Uint128,
+    pub yes_liquidity: Uint128,
+    pub no_liquidity: Uint128,
+    pub liquidity_shares: Uint128,
+    pub capped_to_balance: bool,
+    pub below_minimum_liquidity: bool
+}
+
 #[cw_serde]
 pub struct Data {
     pub information: Information,
@@ -118,8 +463,8 @@ pub enum QueryMsg {
     },
     #[returns(Quote)]
     Quote {
-        variant: Uint128,
-        buy_or_sell: Uint128,
+        variant: Outcome,
+        buy_or_sell: Side,
         amount: Uint128
     },
     #[returns((u64, Vec<Order>))]
@@ -128,5 +473,23 @@ pub enum QueryMsg {
         items_per_page: u128
     },
     #[returns(Uint128)]
-    GetTotalOrders {}
+    GetTotalOrders {},
+    #[returns(AddLiquiditySimulation)]
+    SimulateAddLiquidity {
+        amount: Uint128
+    },
+    #[returns(RemoveLiquiditySimulation)]
+    SimulateRemoveLiquidity {
+        shares: Uint128
+    },
+    #[returns(PositionInfo)]
+    GetPosition {
+        account: Addr
+    },
+    #[returns(Vec<Trade>)]
+    GetAccountTrades {
+        account: Addr,
+        start_after: Option<u64>,
+        limit: Option<u32>
+    }
 }
\ No newline at end of file
@@ -0,0 +1,111 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Uint128};
+
+use crate::collateral::Collateral;
+
+/// A share of the protocol fees, e.g. the treasury or the insurance fund.
+/// Each recipient receives `weight` out of the summed weights.
+#[cw_serde]
+pub struct Recipient {
+    pub name: String,
+    pub address: Addr,
+    pub weight: u64
+}
+
+/// Only `collaterals` can be distributed
+#[cw_serde]
+pub struct Config {
+    pub owner: Addr,
+    pub recipients: Vec<Recipient>,
+    pub collaterals: Vec<Collateral>
+}
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub owner: Addr,
+    pub recipients: Vec<Recipient>,
+    pub collaterals: Vec<Collateral>
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Splits the collateral received since the last distribution between the
+    /// recipients by weight. Anyone can call it for a whitelisted collateral.
+    Distribute {
+        collateral: Collateral
+    },
+    /// Withdraws the sender's distributed share of `collateral`, all of it by default
+    Withdraw {
+        collateral: Collateral,
+        amount: Option<Uint128>
+    },
+    /// Replaces the recipients for future distributions. Fees received so far
+    /// in the whitelisted collaterals are first split by the current weights,
+    /// and shares already distributed stay withdrawable.
+    UpdateRecipients {
+        recipients: Vec<Recipient>
+    },
+    /// Replaces the whitelist of distributable collaterals. Shares already
+    /// distributed in a removed collateral stay withdrawable.
+    UpdateCollaterals {
+        collaterals: Vec<Collateral>
+    },
+    UpdateOwner {
+        owner: Addr
+    }
+}
+
+#[cw_serde]
+pub struct Distribution {
+    pub id: u64,
+    pub collateral: Collateral,
+    pub amount: Uint128,
+    pub shares: Vec<DistributionShare>,
+    pub timestamp: u64
+}
+
+#[cw_serde]
+pub struct DistributionShare {
+    pub address: Addr,
+    pub amount: Uint128
+}
+
+#[cw_serde]
+pub struct Withdrawal {
+    pub id: u64,
+    pub recipient: Addr,
+    pub collateral: Collateral,
+    pub amount: Uint128,
+    pub timestamp: u64
+}
+
+#[cw_serde]
+pub struct ClaimableBalance {
+    pub collateral: Collateral,
+    pub amount: Uint128
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(Config)]
+    Config {},
+    /// Collateral held by the splitter that has not been distributed yet
+    #[returns(Uint128)]
+    Undistributed { collateral: Collateral },
+    #[returns(Uint128)]
+    Claimable { recipient: Addr, collateral: Collateral },
+    /// Withdrawable balances of `recipient` in every collateral it has received,
+    /// `start_after` is a denom or token address
+    #[returns(Vec<ClaimableBalance>)]
+    ListClaimable { recipient: Addr, start_after: Option<String>, limit: Option<u32> },
+    /// Distributions in ascending order, `start_after` is a distribution id
+    #[returns(Vec<Distribution>)]
+    Distributions { start_after: Option<u64>, limit: Option<u32> },
+    /// Withdrawals of `recipient` in ascending order, `start_after` is a withdrawal id
+    #[returns(Vec<Withdrawal>)]
+    Withdrawals { recipient: Addr, start_after: Option<u64>, limit: Option<u32> }
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
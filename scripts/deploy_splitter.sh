@@ -0,0 +1,7 @@
+xiond tx wasm store ../artifacts/splitter.wasm \
+    --chain-id xion-testnet-1 \
+    --gas-adjustment 1.3 \
+    --gas-prices 0.001uxion \
+    --gas auto \
+    --node https://rpc.xion-testnet-1.burnt.com:443 \
+    --from xion13pt0cc57lf943wqjd3ges78mcmulwden5ssy80
@@ -0,0 +1,25 @@
+# set the initial state of the instance, weights are relative to their sum
+INIT='{
+    "owner":"xion13pt0cc57lf943wqjd3ges78mcmulwden5ssy80",
+    "recipients":[
+        {
+            "name":"treasury",
+            "address":"xion13pt0cc57lf943wqjd3ges78mcmulwden5ssy80",
+            "weight":100
+        }
+    ]
+}'
+
+# instantiate the contract
+
+CODE_ID=0 # replace with the splitter code id
+
+xiond tx wasm instantiate $CODE_ID "$INIT" \
+    --from xion13pt0cc57lf943wqjd3ges78mcmulwden5ssy80 \
+    --label "splitter"\
+    --gas-prices 0.025uxion \
+    --gas auto \
+    --gas-adjustment 1.3 \
+    --chain-id xion-testnet-1 \
+    --node https://rpc.xion-testnet-1.burnt.com:443 \
+    --no-admin
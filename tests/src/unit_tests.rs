@@ -1,11 +1,19 @@
-use cosmwasm_std::{Addr, Empty, Uint128, BankQuery, BalanceResponse, QueryRequest, coins};
+use cosmwasm_std::{Addr, Binary, Deps, Empty, Env, Int128, StdError, StdResult, Uint128, BankQuery, BalanceResponse, QueryRequest, coins, from_json, to_json_binary, to_json_vec};
 use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use k256::ecdsa::{signature::hazmat::PrehashSigner, Signature, SigningKey};
 use packages::factory::{
-    ExecuteMsg as FactoryExecuteMsg, InstantiateMsg as FactoryInstantiate
+    ExecuteMsg as FactoryExecuteMsg, InstantiateMsg as FactoryInstantiate,
+    QueryMsg as FactoryQueryMsg, MigrateMsg as FactoryMigrateMsg, ReceiveMsg as FactoryReceiveMsg, Details, Statistics, CollateralVolume, PendingConfig, PendingSuperAdmin, ReferralStats, AccountPoints, EpochInfo, EpochReward, LiquidityRewards, AccountStats, LeaderboardMetric, LeaderboardPage, DailyStatistics, CategoryInfo, MarketType, MarketFilter, MarketSort, Role, RoleAssignment, SignedOrder, SignedOrderPayload, TradingGrant, BatchOrder, MarketTrade, Portfolio, PortfolioEntry, MarketList
 };
+use sha2::{Digest, Sha256};
+use packages::collateral::{Collateral, CollateralConfig};
 use packages::market::{
-    Quote, Data,
-    QueryMsg as MarketQueryMsg
+    Quote, Data, Trade, PositionInfo, AddLiquiditySimulation, RemoveLiquiditySimulation, MarketStatus, Outcome, Side,
+    ExecuteMsg as MarketExecuteMsg, QueryMsg as MarketQueryMsg, RESOLVE_DURATION
+};
+use packages::splitter::{
+    ClaimableBalance, Distribution, Recipient, Withdrawal,
+    ExecuteMsg as SplitterExecuteMsg, InstantiateMsg as SplitterInstantiate, QueryMsg as SplitterQueryMsg
 };
 
 fn mock_app() -> App {
@@ -20,7 +28,7 @@ fn factory_contract() -> Box<dyn Contract<Empty>> {
         factory::contract::execute,
         factory::contract::instantiate,
         factory::contract::query,
-    ).with_reply(factory::execute::reply);
+    ).with_reply(factory::execute::reply).with_migrate(factory::contract::migrate);
     Box::new(contract)
 }
 
@@ -30,6 +38,25 @@ fn market_contract() -> Box<dyn Contract<Empty>> {
         market::contract::execute,
         market::contract::instantiate,
         market::contract::query,
+    ).with_migrate(market::contract::migrate);
+    Box::new(contract)
+}
+
+/// Market code whose queries all fail, to stand in for a market that cannot answer
+fn unavailable_market_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new_with_empty(
+        market::contract::execute,
+        market::contract::instantiate,
+        |_: Deps, _: Env, _: MarketQueryMsg| -> StdResult<Binary> { Err(StdError::generic_err("market unavailable")) },
+    ).with_migrate(market::contract::migrate);
+    Box::new(contract)
+}
+
+fn cw20_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new_with_empty(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
     );
     Box::new(contract)
 }
@@ -56,7 +83,7 @@ fn initialize_contracts(app: &mut App) -> ContractInfo {
             factory_code_id,
             Addr::unchecked("user"),
             &FactoryInstantiate {
-                usdc: usdc.clone(),
+                usdc: Collateral::native(usdc.clone()),
                 fees_address: Addr::unchecked("fees"),
                 market_code_id
             },
@@ -92,15 +119,45 @@ fn create_market(
                 description: "Bet on this market today!".to_string(),
                 end_date: 12456788910111213,
                 categories: vec!["finance".to_string(), "crypto".to_string()],
-                media: ["https://site.com/assets/media-0.png".to_string(), "https://site.com/assets/media-1.png".to_string()]
+                media: ["https://site.com/assets/media-0.png".to_string(), "https://site.com/assets/media-1.png".to_string()],
+                collateral: None
             },
             &[],
         )
         .unwrap();
-        
-    let market_address = create_market_res.events[1].attributes[0].value.clone();
 
-    return market_address;
+    create_market_res.events[1].attributes[0].value.clone()
+
+}
+
+/// Proposes `variant` and resolves to it once the challenge period is over
+fn resolve_market(
+    app: &mut App,
+    factory_contract_addr: &Addr,
+    market: &str,
+    variant: Outcome,
+    market_index: u128
+) {
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_contract_addr.clone(),
+        &FactoryExecuteMsg::ProposeResolution { market: Addr::unchecked(market), variant },
+        &[],
+    )
+    .unwrap();
+
+    app.update_block(|block| block.time = block.time.plus_seconds(RESOLVE_DURATION));
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_contract_addr.clone(),
+        &FactoryExecuteMsg::ResolveMarket { market: Addr::unchecked(market), variant, market_index },
+        &[],
+    )
+    .unwrap();
 
 }
 
@@ -353,8 +410,8 @@ fn buy_yes_test() {
         .query_wasm_smart(
             market_address.clone(),
             &MarketQueryMsg::Quote {
-                variant: Uint128::from(1u128),
-                buy_or_sell: Uint128::from(1u128),
+                variant: Outcome::Yes,
+                buy_or_sell: Side::Buy,
                 amount: Uint128::from(15_000_000u128)
             },
         )
@@ -368,9 +425,13 @@ fn buy_yes_test() {
         Addr::unchecked(factory_address.clone()),
         &FactoryExecuteMsg::PlaceOrder {
             market: Addr::unchecked(market_address.clone()),
-            variant: Uint128::from(1u128),
-            buy_or_sell: Uint128::from(1u128),
-            amount: Uint128::from(15_000_000u128)
+            variant: Outcome::Yes,
+            buy_or_sell: Side::Buy,
+            amount: Uint128::from(15_000_000u128),
+            min_out: None,
+            on_behalf_of: None,
+            use_balance: false,
+            referrer: None
         },
         &coins(15_000_000, &usdc_denom),
     )
@@ -431,8 +492,8 @@ fn sell_yes_test() {
         .query_wasm_smart(
             market_address.clone(),
             &MarketQueryMsg::Quote {
-                variant: Uint128::from(1u128),
-                buy_or_sell: Uint128::from(1u128),
+                variant: Outcome::Yes,
+                buy_or_sell: Side::Buy,
                 amount: Uint128::from(15_000_000u128)
             },
         )
@@ -446,9 +507,13 @@ fn sell_yes_test() {
         Addr::unchecked(factory_address.clone()),
         &FactoryExecuteMsg::PlaceOrder {
             market: Addr::unchecked(market_address.clone()),
-            variant: Uint128::from(1u128),
-            buy_or_sell: Uint128::from(1u128),
-            amount: Uint128::from(15_000_000u128)
+            variant: Outcome::Yes,
+            buy_or_sell: Side::Buy,
+            amount: Uint128::from(15_000_000u128),
+            min_out: None,
+            on_behalf_of: None,
+            use_balance: false,
+            referrer: None
         },
         &coins(15_000_000, &usdc_denom),
     )
@@ -459,27 +524,35 @@ fn sell_yes_test() {
         .query_wasm_smart(
             market_address.clone(),
             &MarketQueryMsg::Quote {
-                variant: Uint128::from(1u128),
-                buy_or_sell: Uint128::from(0u128),
-                amount: shares_out.clone()
+                variant: Outcome::Yes,
+                buy_or_sell: Side::Sell,
+                amount: shares_out
             },
         )
         .unwrap();
 
     let usdc_out = usdc_quote.amount_out;
 
+    let sell = FactoryExecuteMsg::PlaceOrder {
+        market: Addr::unchecked(market_address.clone()),
+        variant: Outcome::Yes,
+        buy_or_sell: Side::Sell,
+        amount: shares_out,
+        min_out: None,
+        on_behalf_of: None,
+        use_balance: false,
+        referrer: None
+    };
+
+    // Funds attached to a sell would be stuck in the factory
+    let err = app
+    .execute_contract(Addr::unchecked("user"), Addr::unchecked(factory_address.clone()), &sell, &coins(1_000_000, &usdc_denom))
+    .unwrap_err();
+
+    assert!(err.root_cause().to_string().contains("must not be attached to sells"));
+
     app
-    .execute_contract(
-        Addr::unchecked("user"),
-        Addr::unchecked(factory_address.clone()),
-        &FactoryExecuteMsg::PlaceOrder {
-            market: Addr::unchecked(market_address.clone()),
-            variant: Uint128::from(1u128),
-            buy_or_sell: Uint128::from(0u128),
-            amount: shares_out.clone()
-        },
-        &[],
-    )
+    .execute_contract(Addr::unchecked("user"), Addr::unchecked(factory_address.clone()), &sell, &[])
     .unwrap();
 
     let expected_remaining_balance = Uint128::from(10_000_000_000u128 - 1_000_000_000u128 - 15_000_000u128 + usdc_out.u128());
@@ -549,8 +622,8 @@ fn buy_no_test() {
         .query_wasm_smart(
             market_address.clone(),
             &MarketQueryMsg::Quote {
-                variant: Uint128::from(0u128),
-                buy_or_sell: Uint128::from(1u128),
+                variant: Outcome::No,
+                buy_or_sell: Side::Buy,
                 amount: Uint128::from(15_000_000u128)
             },
         )
@@ -564,9 +637,13 @@ fn buy_no_test() {
         Addr::unchecked(factory_address.clone()),
         &FactoryExecuteMsg::PlaceOrder {
             market: Addr::unchecked(market_address.clone()),
-            variant: Uint128::from(0u128),
-            buy_or_sell: Uint128::from(1u128),
-            amount: Uint128::from(15_000_000u128)
+            variant: Outcome::No,
+            buy_or_sell: Side::Buy,
+            amount: Uint128::from(15_000_000u128),
+            min_out: None,
+            on_behalf_of: None,
+            use_balance: false,
+            referrer: None
         },
         &coins(15_000_000, &usdc_denom),
     )
@@ -627,8 +704,8 @@ fn sell_no_test() {
         .query_wasm_smart(
             market_address.clone(),
             &MarketQueryMsg::Quote {
-                variant: Uint128::from(0u128),
-                buy_or_sell: Uint128::from(1u128),
+                variant: Outcome::No,
+                buy_or_sell: Side::Buy,
                 amount: Uint128::from(15_000_000u128)
             },
         )
@@ -642,9 +719,13 @@ fn sell_no_test() {
         Addr::unchecked(factory_address.clone()),
         &FactoryExecuteMsg::PlaceOrder {
             market: Addr::unchecked(market_address.clone()),
-            variant: Uint128::from(0u128),
-            buy_or_sell: Uint128::from(1u128),
-            amount: Uint128::from(15_000_000u128)
+            variant: Outcome::No,
+            buy_or_sell: Side::Buy,
+            amount: Uint128::from(15_000_000u128),
+            min_out: None,
+            on_behalf_of: None,
+            use_balance: false,
+            referrer: None
         },
         &coins(15_000_000, &usdc_denom),
     )
@@ -655,9 +736,9 @@ fn sell_no_test() {
         .query_wasm_smart(
             market_address.clone(),
             &MarketQueryMsg::Quote {
-                variant: Uint128::from(0u128),
-                buy_or_sell: Uint128::from(0u128),
-                amount: shares_out.clone()
+                variant: Outcome::No,
+                buy_or_sell: Side::Sell,
+                amount: shares_out
             },
         )
         .unwrap();
@@ -670,9 +751,13 @@ fn sell_no_test() {
         Addr::unchecked(factory_address.clone()),
         &FactoryExecuteMsg::PlaceOrder {
             market: Addr::unchecked(market_address.clone()),
-            variant: Uint128::from(0u128),
-            buy_or_sell: Uint128::from(0u128),
-            amount: shares_out.clone()
+            variant: Outcome::No,
+            buy_or_sell: Side::Sell,
+            amount: shares_out,
+            min_out: None,
+            on_behalf_of: None,
+            use_balance: false,
+            referrer: None
         },
         &[],
     )
@@ -704,4 +789,4531 @@ fn sell_no_test() {
 
     assert_eq!(result.amount.amount, expected_remaining_balance);
 
-}
\ No newline at end of file
+}
+
+fn sign_order(app: &App, signing_key: &SigningKey, factory: &Addr, signer: &Addr, order: &SignedOrder) -> Binary {
+
+    let payload = SignedOrderPayload {
+        chain_id: app.block_info().chain_id,
+        factory: factory.clone(),
+        signer: signer.clone(),
+        order: order.clone()
+    };
+
+    let message_hash = Sha256::digest(to_json_vec(&payload).unwrap());
+    let signature: Signature = signing_key.sign_prehash(&message_hash).unwrap();
+
+    Binary::from(signature.to_bytes().to_vec())
+
+}
+
+#[test]
+fn signed_order_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let market_address = create_market(
+        &mut app,
+        factory_address.clone()
+    );
+
+    let usdc_denom = String::from(USDC_DENOM);
+    for account in ["user", "trader"] {
+        app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+            to_address: account.to_string(),
+            amount: coins(10_000_000_000, &usdc_denom),
+        }))
+        .unwrap();
+    }
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::InitializeLiquidity {
+            market: Addr::unchecked(market_address.clone()),
+            yes_price: Uint128::from(50_000_000u128),
+            liquidity: Uint128::from(1_000_000_000u128)
+        },
+        &coins(1_000_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    let trader = Addr::unchecked("trader");
+    let signing_key = SigningKey::from_bytes(&[7u8; 32].into()).unwrap();
+    let pubkey = signing_key.verifying_key().to_encoded_point(true).as_bytes().to_vec();
+
+    // Right length, but the x coordinate is past the field modulus
+    let mut malformed = vec![0x02u8];
+    malformed.extend([0xffu8; 32]);
+
+    let err = app
+    .execute_contract(
+        trader.clone(),
+        factory_address.clone(),
+        &FactoryExecuteMsg::RegisterSigningKey {
+            pubkey: Binary::from(malformed)
+        },
+        &[],
+    )
+    .unwrap_err();
+
+    assert!(err.root_cause().to_string().contains("Invalid secp256k1 public key"));
+
+    app
+    .execute_contract(
+        trader.clone(),
+        factory_address.clone(),
+        &FactoryExecuteMsg::RegisterSigningKey {
+            pubkey: Binary::from(pubkey)
+        },
+        &[],
+    )
+    .unwrap();
+
+    app
+    .execute_contract(
+        trader.clone(),
+        factory_address.clone(),
+        &FactoryExecuteMsg::Deposit {},
+        &coins(20_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    let shares_quote: Quote = app
+        .wrap()
+        .query_wasm_smart(
+            market_address.clone(),
+            &MarketQueryMsg::Quote {
+                variant: Outcome::Yes,
+                buy_or_sell: Side::Buy,
+                amount: Uint128::from(15_000_000u128)
+            },
+        )
+        .unwrap();
+
+    let order = SignedOrder {
+        market: Addr::unchecked(market_address.clone()),
+        variant: Outcome::Yes,
+        buy_or_sell: Side::Buy,
+        amount: Uint128::from(15_000_000u128),
+        min_out: shares_quote.amount_out,
+        nonce: 0,
+        expiry: app.block_info().time.seconds() + 60
+    };
+
+    let signature = sign_order(&app, &signing_key, &factory_address, &trader, &order);
+
+    let wrong_key = SigningKey::from_bytes(&[9u8; 32].into()).unwrap();
+    let wrong_signature = sign_order(&app, &wrong_key, &factory_address, &trader, &order);
+
+    app
+    .execute_contract(
+        Addr::unchecked("relayer"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::SubmitSignedOrder {
+            signer: trader.clone(),
+            order: order.clone(),
+            signature: wrong_signature
+        },
+        &[],
+    )
+    .unwrap_err();
+
+    app
+    .execute_contract(
+        Addr::unchecked("relayer"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::SubmitSignedOrder {
+            signer: trader.clone(),
+            order: order.clone(),
+            signature: signature.clone()
+        },
+        &[],
+    )
+    .unwrap();
+
+    // Replaying the same signed order must fail
+    app
+    .execute_contract(
+        Addr::unchecked("relayer"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::SubmitSignedOrder {
+            signer: trader.clone(),
+            order,
+            signature
+        },
+        &[],
+    )
+    .unwrap_err();
+
+    let result: Data = app
+    .wrap()
+    .query_wasm_smart(
+        Addr::unchecked(market_address.clone()),
+        &MarketQueryMsg::GetInfo {
+            account: trader.clone()
+        },
+    )
+    .unwrap();
+
+    let balance: Uint128 = app
+    .wrap()
+    .query_wasm_smart(
+        factory_address.clone(),
+        &FactoryQueryMsg::GetBalance {
+            account: trader.clone(),
+            collateral: None
+        },
+    )
+    .unwrap();
+
+    let nonce: u64 = app
+    .wrap()
+    .query_wasm_smart(
+        factory_address,
+        &FactoryQueryMsg::GetNonce {
+            account: trader
+        },
+    )
+    .unwrap();
+
+    assert_eq!(result.shares.yes_shares, shares_quote.amount_out);
+    assert_eq!(balance, Uint128::from(5_000_000u128));
+    assert_eq!(nonce, 1u64);
+
+}
+
+#[test]
+fn delegated_trading_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let market_address = create_market(
+        &mut app,
+        factory_address.clone()
+    );
+
+    let usdc_denom = String::from(USDC_DENOM);
+    for account in ["user", "operator"] {
+        app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+            to_address: account.to_string(),
+            amount: coins(10_000_000_000, &usdc_denom),
+        }))
+        .unwrap();
+    }
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::InitializeLiquidity {
+            market: Addr::unchecked(market_address.clone()),
+            yes_price: Uint128::from(50_000_000u128),
+            liquidity: Uint128::from(1_000_000_000u128)
+        },
+        &coins(1_000_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    let granter = Addr::unchecked("granter");
+    let operator = Addr::unchecked("operator");
+
+    let place_order = |amount: u128| FactoryExecuteMsg::PlaceOrder {
+        market: Addr::unchecked(market_address.clone()),
+        variant: Outcome::Yes,
+        buy_or_sell: Side::Buy,
+        amount: Uint128::from(amount),
+        min_out: None,
+        on_behalf_of: Some(granter.clone()),
+        use_balance: false,
+        referrer: None
+    };
+
+    // Without a grant the operator cannot trade for the granter
+    app
+    .execute_contract(operator.clone(), factory_address.clone(), &place_order(10_000_000), &coins(10_000_000, &usdc_denom))
+    .unwrap_err();
+
+    app
+    .execute_contract(
+        granter.clone(),
+        factory_address.clone(),
+        &FactoryExecuteMsg::GrantTrading {
+            operator: operator.clone(),
+            expires: app.block_info().time.seconds() + 3_600,
+            max_notional: Uint128::from(25_000_000u128),
+            markets: Some(vec![Addr::unchecked(market_address.clone())])
+        },
+        &[],
+    )
+    .unwrap();
+
+    // Operators cannot pick a referrer for the granter
+    let referred = FactoryExecuteMsg::PlaceOrder {
+        market: Addr::unchecked(market_address.clone()),
+        variant: Outcome::Yes,
+        buy_or_sell: Side::Buy,
+        amount: Uint128::from(15_000_000u128),
+        min_out: None,
+        on_behalf_of: Some(granter.clone()),
+        use_balance: false,
+        referrer: Some(operator.clone())
+    };
+
+    let err = app
+    .execute_contract(operator.clone(), factory_address.clone(), &referred, &coins(15_000_000, &usdc_denom))
+    .unwrap_err();
+
+    assert!(err.root_cause().to_string().contains("set its referrer"));
+
+    app
+    .execute_contract(operator.clone(), factory_address.clone(), &place_order(15_000_000), &coins(15_000_000, &usdc_denom))
+    .unwrap();
+
+    // Exceeds the remaining 10 USDC of notional
+    app
+    .execute_contract(operator.clone(), factory_address.clone(), &place_order(15_000_000), &coins(15_000_000, &usdc_denom))
+    .unwrap_err();
+
+    let grant: Option<TradingGrant> = app
+    .wrap()
+    .query_wasm_smart(
+        factory_address.clone(),
+        &FactoryQueryMsg::GetTradingGrant {
+            granter: granter.clone(),
+            operator: operator.clone()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(grant.unwrap().used_notional, Uint128::from(15_000_000u128));
+
+    let result: Data = app
+    .wrap()
+    .query_wasm_smart(
+        Addr::unchecked(market_address.clone()),
+        &MarketQueryMsg::GetInfo {
+            account: granter.clone()
+        },
+    )
+    .unwrap();
+
+    assert!(result.shares.yes_shares > Uint128::from(0u128));
+
+    app
+    .execute_contract(
+        granter.clone(),
+        factory_address.clone(),
+        &FactoryExecuteMsg::RevokeTrading {
+            operator: operator.clone()
+        },
+        &[],
+    )
+    .unwrap();
+
+    app
+    .execute_contract(operator.clone(), factory_address.clone(), &place_order(5_000_000), &coins(5_000_000, &usdc_denom))
+    .unwrap_err();
+
+    let grants: Vec<TradingGrant> = app
+    .wrap()
+    .query_wasm_smart(
+        factory_address,
+        &FactoryQueryMsg::ListTradingGrants {
+            granter,
+            start_after: None,
+            limit: None
+        },
+    )
+    .unwrap();
+
+    assert!(grants.is_empty());
+
+}
+
+#[test]
+fn internal_balance_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let market_address = create_market(
+        &mut app,
+        factory_address.clone()
+    );
+
+    let usdc_denom = String::from(USDC_DENOM);
+    app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+        to_address: "user".to_string(),
+        amount: coins(10_000_000_000, &usdc_denom),
+    }))
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::InitializeLiquidity {
+            market: Addr::unchecked(market_address.clone()),
+            yes_price: Uint128::from(50_000_000u128),
+            liquidity: Uint128::from(1_000_000_000u128)
+        },
+        &coins(1_000_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::Deposit {},
+        &coins(100_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    let get_balance = |app: &App| -> Uint128 {
+        app
+        .wrap()
+        .query_wasm_smart(
+            factory_address.clone(),
+            &FactoryQueryMsg::GetBalance {
+                account: Addr::unchecked("user"),
+                collateral: None
+            },
+        )
+        .unwrap()
+    };
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::PlaceOrder {
+            market: Addr::unchecked(market_address.clone()),
+            variant: Outcome::Yes,
+            buy_or_sell: Side::Buy,
+            amount: Uint128::from(15_000_000u128),
+            min_out: None,
+            on_behalf_of: None,
+            use_balance: true,
+            referrer: None
+        },
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(get_balance(&app), Uint128::from(85_000_000u128));
+
+    let info: Data = app
+    .wrap()
+    .query_wasm_smart(
+        Addr::unchecked(market_address.clone()),
+        &MarketQueryMsg::GetInfo {
+            account: Addr::unchecked("user")
+        },
+    )
+    .unwrap();
+
+    let usdc_quote: Quote = app
+        .wrap()
+        .query_wasm_smart(
+            market_address.clone(),
+            &MarketQueryMsg::Quote {
+                variant: Outcome::Yes,
+                buy_or_sell: Side::Sell,
+                amount: info.shares.yes_shares
+            },
+        )
+        .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::PlaceOrder {
+            market: Addr::unchecked(market_address.clone()),
+            variant: Outcome::Yes,
+            buy_or_sell: Side::Sell,
+            amount: info.shares.yes_shares,
+            min_out: None,
+            on_behalf_of: None,
+            use_balance: true,
+            referrer: None
+        },
+        &[],
+    )
+    .unwrap();
+
+    let balance = get_balance(&app);
+    assert_eq!(balance, Uint128::from(85_000_000u128) + usdc_quote.amount_out);
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::Withdraw {
+            amount: balance,
+            collateral: None
+        },
+        &[],
+    )
+    .unwrap();
+
+    let result: BalanceResponse = app
+    .wrap()
+    .query(&QueryRequest::Bank(
+        BankQuery::Balance {
+            address: "user".to_string(),
+            denom: USDC_DENOM.to_string(),
+        },
+    ))
+    .unwrap();
+
+    assert_eq!(get_balance(&app), Uint128::from(0u128));
+    assert_eq!(result.amount.amount, Uint128::from(10_000_000_000u128 - 1_000_000_000u128 - 15_000_000u128) + usdc_quote.amount_out);
+
+}
+
+#[test]
+fn batch_place_order_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let usdc_denom = String::from(USDC_DENOM);
+    app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+        to_address: "user".to_string(),
+        amount: coins(10_000_000_000, &usdc_denom),
+    }))
+    .unwrap();
+
+    let mut markets = vec![];
+
+    for _ in 0..2 {
+
+        let market_address = create_market(
+            &mut app,
+            factory_address.clone()
+        );
+
+        app
+        .execute_contract(
+            Addr::unchecked("user"),
+            factory_address.clone(),
+            &FactoryExecuteMsg::InitializeLiquidity {
+                market: Addr::unchecked(market_address.clone()),
+                yes_price: Uint128::from(50_000_000u128),
+                liquidity: Uint128::from(1_000_000_000u128)
+            },
+            &coins(1_000_000_000, &usdc_denom),
+        )
+        .unwrap();
+
+        markets.push(Addr::unchecked(market_address));
+
+    }
+
+    let orders = vec![
+        BatchOrder {
+            market: markets[0].clone(),
+            variant: Outcome::Yes,
+            buy_or_sell: Side::Buy,
+            amount: Uint128::from(10_000_000u128),
+            min_out: None
+        },
+        BatchOrder {
+            market: markets[1].clone(),
+            variant: Outcome::No,
+            buy_or_sell: Side::Buy,
+            amount: Uint128::from(20_000_000u128),
+            // Unreachable slippage bound, this order always fails
+            min_out: Some(Uint128::from(1_000_000_000u128))
+        }
+    ];
+
+    // Attached funds must match the sum of buys
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::BatchPlaceOrder {
+            orders: orders.clone(),
+            atomic: false,
+            use_balance: false
+        },
+        &coins(10_000_000, &usdc_denom),
+    )
+    .unwrap_err();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::BatchPlaceOrder {
+            orders: orders.clone(),
+            atomic: true,
+            use_balance: false
+        },
+        &coins(30_000_000, &usdc_denom),
+    )
+    .unwrap_err();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::BatchPlaceOrder {
+            orders,
+            atomic: false,
+            use_balance: false
+        },
+        &coins(30_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    let first: Data = app
+    .wrap()
+    .query_wasm_smart(
+        markets[0].clone(),
+        &MarketQueryMsg::GetInfo {
+            account: Addr::unchecked("user")
+        },
+    )
+    .unwrap();
+
+    let second: Data = app
+    .wrap()
+    .query_wasm_smart(
+        markets[1].clone(),
+        &MarketQueryMsg::GetInfo {
+            account: Addr::unchecked("user")
+        },
+    )
+    .unwrap();
+
+    let result: BalanceResponse = app
+    .wrap()
+    .query(&QueryRequest::Bank(
+        BankQuery::Balance {
+            address: "user".to_string(),
+            denom: USDC_DENOM.to_string(),
+        },
+    ))
+    .unwrap();
+
+    assert!(first.shares.yes_shares > Uint128::from(0u128));
+    assert_eq!(second.shares.no_shares, Uint128::from(0u128));
+    // Only the successful order is paid for, the failed one is refunded
+    assert_eq!(result.amount.amount, Uint128::from(10_000_000_000u128 - 2_000_000_000u128 - 10_000_000u128));
+
+}
+
+#[test]
+fn account_trades_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let usdc_denom = String::from(USDC_DENOM);
+    app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+        to_address: "user".to_string(),
+        amount: coins(10_000_000_000, &usdc_denom),
+    }))
+    .unwrap();
+
+    let mut markets = vec![];
+
+    for _ in 0..2 {
+
+        let market_address = create_market(
+            &mut app,
+            factory_address.clone()
+        );
+
+        app
+        .execute_contract(
+            Addr::unchecked("user"),
+            factory_address.clone(),
+            &FactoryExecuteMsg::InitializeLiquidity {
+                market: Addr::unchecked(market_address.clone()),
+                yes_price: Uint128::from(50_000_000u128),
+                liquidity: Uint128::from(1_000_000_000u128)
+            },
+            &coins(1_000_000_000, &usdc_denom),
+        )
+        .unwrap();
+
+        markets.push(Addr::unchecked(market_address));
+
+    }
+
+    for (market, amount) in [(&markets[0], 10_000_000u128), (&markets[0], 20_000_000u128), (&markets[1], 15_000_000u128)] {
+        app
+        .execute_contract(
+            Addr::unchecked("user"),
+            factory_address.clone(),
+            &FactoryExecuteMsg::PlaceOrder {
+                market: market.clone(),
+                variant: Outcome::Yes,
+                buy_or_sell: Side::Buy,
+                amount: Uint128::from(amount),
+                min_out: None,
+                on_behalf_of: None,
+                use_balance: false,
+                referrer: None
+            },
+            &coins(amount, &usdc_denom),
+        )
+        .unwrap();
+    }
+
+    let trades: Vec<Trade> = app
+    .wrap()
+    .query_wasm_smart(
+        markets[0].clone(),
+        &MarketQueryMsg::GetAccountTrades {
+            account: Addr::unchecked("user"),
+            start_after: None,
+            limit: None
+        },
+    )
+    .unwrap();
+
+    assert_eq!(trades.len(), 2);
+    assert_eq!(trades[0].amount, Uint128::from(20_000_000u128));
+    assert_eq!(trades[1].amount, Uint128::from(10_000_000u128));
+
+    let first_page: Vec<MarketTrade> = app
+    .wrap()
+    .query_wasm_smart(
+        factory_address.clone(),
+        &FactoryQueryMsg::GetAccountTrades {
+            account: Addr::unchecked("user"),
+            start_after: None,
+            limit: Some(2)
+        },
+    )
+    .unwrap();
+
+    assert_eq!(first_page.len(), 2);
+
+    let last = first_page.last().unwrap();
+
+    let second_page: Vec<MarketTrade> = app
+    .wrap()
+    .query_wasm_smart(
+        factory_address,
+        &FactoryQueryMsg::GetAccountTrades {
+            account: Addr::unchecked("user"),
+            start_after: Some((last.market.clone(), last.trade.id)),
+            limit: Some(2)
+        },
+    )
+    .unwrap();
+
+    assert_eq!(second_page.len(), 1);
+
+    let mut amounts: Vec<Uint128> = first_page.iter().chain(second_page.iter()).map(|t| t.trade.amount).collect();
+    amounts.sort();
+
+    assert_eq!(amounts, vec![Uint128::from(10_000_000u128), Uint128::from(15_000_000u128), Uint128::from(20_000_000u128)]);
+
+}
+
+#[test]
+fn position_pnl_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let market_address = create_market(
+        &mut app,
+        factory_address.clone()
+    );
+
+    let usdc_denom = String::from(USDC_DENOM);
+    app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+        to_address: "user".to_string(),
+        amount: coins(10_000_000_000, &usdc_denom),
+    }))
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::InitializeLiquidity {
+            market: Addr::unchecked(market_address.clone()),
+            yes_price: Uint128::from(50_000_000u128),
+            liquidity: Uint128::from(1_000_000_000u128)
+        },
+        &coins(1_000_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::PlaceOrder {
+            market: Addr::unchecked(market_address.clone()),
+            variant: Outcome::Yes,
+            buy_or_sell: Side::Buy,
+            amount: Uint128::from(15_000_000u128),
+            min_out: None,
+            on_behalf_of: None,
+            use_balance: false,
+            referrer: None
+        },
+        &coins(15_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    let position: PositionInfo = app
+    .wrap()
+    .query_wasm_smart(
+        market_address.clone(),
+        &MarketQueryMsg::GetPosition {
+            account: Addr::unchecked("user")
+        },
+    )
+    .unwrap();
+
+    assert_eq!(position.yes_cost, Uint128::from(15_000_000u128));
+    assert_eq!(position.realized_pnl, Int128::zero());
+    // Fees and impact leave a fresh position slightly under water
+    assert!(position.yes_liquidation_value.unwrap() < position.yes_cost);
+    assert!(position.yes_liquidation_value.unwrap() <= position.yes_value);
+
+    let sell_amount = position.yes_shares / Uint128::from(2u128);
+
+    let usdc_quote: Quote = app
+        .wrap()
+        .query_wasm_smart(
+            market_address.clone(),
+            &MarketQueryMsg::Quote {
+                variant: Outcome::Yes,
+                buy_or_sell: Side::Sell,
+                amount: sell_amount
+            },
+        )
+        .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::PlaceOrder {
+            market: Addr::unchecked(market_address.clone()),
+            variant: Outcome::Yes,
+            buy_or_sell: Side::Sell,
+            amount: sell_amount,
+            min_out: None,
+            on_behalf_of: None,
+            use_balance: false,
+            referrer: None
+        },
+        &[],
+    )
+    .unwrap();
+
+    let after: PositionInfo = app
+    .wrap()
+    .query_wasm_smart(
+        market_address.clone(),
+        &MarketQueryMsg::GetPosition {
+            account: Addr::unchecked("user")
+        },
+    )
+    .unwrap();
+
+    let released = Uint128::from(15_000_000u128).multiply_ratio(sell_amount, position.yes_shares);
+
+    assert_eq!(after.yes_cost, Uint128::from(15_000_000u128) - released);
+    assert_eq!(
+        after.realized_pnl,
+        Int128::try_from(usdc_quote.amount_out).unwrap() - Int128::try_from(released).unwrap()
+    );
+
+    // A position larger than what is left in the pool is still reported, but selling it cannot be quoted
+    for _ in 0..3 {
+        app
+        .execute_contract(
+            Addr::unchecked("user"),
+            factory_address.clone(),
+            &FactoryExecuteMsg::PlaceOrder {
+                market: Addr::unchecked(market_address.clone()),
+                variant: Outcome::Yes,
+                buy_or_sell: Side::Buy,
+                amount: Uint128::from(20_000_000u128),
+                min_out: None,
+                on_behalf_of: None,
+                use_balance: false,
+                referrer: None
+            },
+            &coins(20_000_000, &usdc_denom),
+        )
+        .unwrap();
+    }
+
+    let grown: PositionInfo = app
+    .wrap()
+    .query_wasm_smart(market_address.clone(), &MarketQueryMsg::GetPosition { account: Addr::unchecked("user") })
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::RemoveLiquidity {
+            market: Addr::unchecked(market_address.clone()),
+            shares: Uint128::from(494_000_000u128)
+        },
+        &[],
+    )
+    .unwrap();
+
+    let drained: PositionInfo = app
+    .wrap()
+    .query_wasm_smart(
+        market_address.clone(),
+        &MarketQueryMsg::GetPosition {
+            account: Addr::unchecked("user")
+        },
+    )
+    .unwrap();
+
+    assert_eq!(drained.yes_shares, grown.yes_shares);
+    assert_eq!(drained.yes_liquidation_value, None);
+    assert_eq!(drained.no_liquidation_value, Some(Uint128::zero()));
+
+}
+
+#[test]
+fn portfolio_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let usdc_denom = String::from(USDC_DENOM);
+    for account in ["user", "trader"] {
+        app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+            to_address: account.to_string(),
+            amount: coins(10_000_000_000, &usdc_denom),
+        }))
+        .unwrap();
+    }
+
+    let mut markets = vec![];
+
+    for _ in 0..2 {
+
+        let market_address = create_market(
+            &mut app,
+            factory_address.clone()
+        );
+
+        app
+        .execute_contract(
+            Addr::unchecked("user"),
+            factory_address.clone(),
+            &FactoryExecuteMsg::InitializeLiquidity {
+                market: Addr::unchecked(market_address.clone()),
+                yes_price: Uint128::from(50_000_000u128),
+                liquidity: Uint128::from(1_000_000_000u128)
+            },
+            &coins(1_000_000_000, &usdc_denom),
+        )
+        .unwrap();
+
+        markets.push(Addr::unchecked(market_address));
+
+    }
+
+    let place_order = |market: &Addr, buy_or_sell: Side, amount: Uint128| FactoryExecuteMsg::PlaceOrder {
+        market: market.clone(),
+        variant: Outcome::Yes,
+        buy_or_sell,
+        amount,
+        min_out: None,
+        on_behalf_of: None,
+        use_balance: false,
+        referrer: None
+    };
+
+    for market in &markets {
+        app
+        .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &place_order(market, Side::Buy, Uint128::from(15_000_000u128)), &coins(15_000_000, &usdc_denom))
+        .unwrap();
+    }
+
+    // Fully exit the first market
+    let data: Data = app
+    .wrap()
+    .query_wasm_smart(
+        markets[0].clone(),
+        &MarketQueryMsg::GetInfo {
+            account: Addr::unchecked("trader")
+        },
+    )
+    .unwrap();
+
+    app
+    .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &place_order(&markets[0], Side::Sell, data.shares.yes_shares), &[])
+    .unwrap();
+
+    let portfolio: Portfolio = app
+    .wrap()
+    .query_wasm_smart(
+        factory_address.clone(),
+        &FactoryQueryMsg::GetPortfolio {
+            account: Addr::unchecked("trader"),
+            start_after: None,
+            limit: None
+        },
+    )
+    .unwrap();
+
+    assert_eq!(portfolio.next_start_after, None);
+    let portfolio = portfolio.entries;
+
+    assert_eq!(portfolio.len(), 1);
+    assert_eq!(portfolio[0].market, markets[1]);
+    assert!(portfolio[0].position_value > Uint128::from(0u128));
+    assert!(!portfolio[0].resolved);
+
+    // The exited market still counts toward the page limit
+    let mut start_after = None;
+    let mut pages = 0;
+    let mut entries: Vec<PortfolioEntry> = vec![];
+
+    loop {
+        let page: Portfolio = app
+        .wrap()
+        .query_wasm_smart(
+            factory_address.clone(),
+            &FactoryQueryMsg::GetPortfolio {
+                account: Addr::unchecked("trader"),
+                start_after,
+                limit: Some(1)
+            },
+        )
+        .unwrap();
+
+        assert!(page.entries.len() <= 1);
+        pages += 1;
+        entries.extend(page.entries);
+
+        match page.next_start_after {
+            Some(next) => start_after = Some(next),
+            None => break
+        }
+    }
+
+    assert_eq!(pages, markets.len());
+    assert_eq!(entries, portfolio);
+
+    let lp_portfolio: Portfolio = app
+    .wrap()
+    .query_wasm_smart(
+        factory_address.clone(),
+        &FactoryQueryMsg::GetPortfolio {
+            account: Addr::unchecked("user"),
+            start_after: None,
+            limit: None
+        },
+    )
+    .unwrap();
+
+    assert_eq!(lp_portfolio.entries.len(), 2);
+    assert!(lp_portfolio.entries.iter().all(|entry| entry.liquidity_value > Uint128::from(0u128)));
+
+    // A market that cannot be queried is flagged instead of failing the whole portfolio
+    let unavailable_code_id = app.store_code(unavailable_market_contract());
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::UpdateConfig { fees_address: None, market_code_id: Some(unavailable_code_id), usdc: None },
+        &[],
+    )
+    .unwrap();
+
+    app.update_block(|block| block.time = block.time.plus_seconds(factory::execute::CONFIG_TIMELOCK));
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &FactoryExecuteMsg::ExecuteConfigUpdate {}, &[])
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::MigrateMarkets { new_code_id: unavailable_code_id, start_after: Some(1), limit: Some(1) },
+        &[],
+    )
+    .unwrap();
+
+    let lp_portfolio: Portfolio = app
+    .wrap()
+    .query_wasm_smart(
+        factory_address,
+        &FactoryQueryMsg::GetPortfolio {
+            account: Addr::unchecked("user"),
+            start_after: None,
+            limit: None
+        },
+    )
+    .unwrap();
+
+    assert_eq!(lp_portfolio.entries.len(), 1);
+    assert_eq!(lp_portfolio.entries[0].market, markets[0]);
+    assert_eq!(lp_portfolio.unavailable, vec![markets[1].clone()]);
+
+}
+
+#[test]
+fn simulate_liquidity_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let market_address = create_market(
+        &mut app,
+        factory_address.clone()
+    );
+
+    let usdc_denom = String::from(USDC_DENOM);
+    app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+        to_address: "user".to_string(),
+        amount: coins(10_000_000_000, &usdc_denom),
+    }))
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::InitializeLiquidity {
+            market: Addr::unchecked(market_address.clone()),
+            yes_price: Uint128::from(50_000_000u128),
+            liquidity: Uint128::from(1_000_000_000u128)
+        },
+        &coins(1_000_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    let add_simulation: AddLiquiditySimulation = app
+    .wrap()
+    .query_wasm_smart(
+        market_address.clone(),
+        &MarketQueryMsg::SimulateAddLiquidity {
+            amount: Uint128::from(1_000_000_000u128)
+        },
+    )
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::AddLiquidity {
+            market: Addr::unchecked(market_address.clone()),
+            amount: Uint128::from(1_000_000_000u128)
+        },
+        &coins(1_000_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    let result: Data = app
+    .wrap()
+    .query_wasm_smart(
+        market_address.clone(),
+        &MarketQueryMsg::GetInfo {
+            account: Addr::unchecked("user")
+        },
+    )
+    .unwrap();
+
+    assert_eq!(add_simulation.shares_minted, Uint128::from(500_000_000u128));
+    assert_eq!(add_simulation.liquidity_shares, result.information.liquidity_shares);
+    assert_eq!(add_simulation.yes_liquidity, result.information.yes_liquidity);
+
+    let partial: RemoveLiquiditySimulation = app
+    .wrap()
+    .query_wasm_smart(
+        market_address.clone(),
+        &MarketQueryMsg::SimulateRemoveLiquidity {
+            shares: Uint128::from(500_000_000u128)
+        },
+    )
+    .unwrap();
+
+    assert_eq!(partial.amount_out, Uint128::from(1_000_000_000u128));
+    assert!(!partial.capped_to_balance);
+    assert!(!partial.below_minimum_liquidity);
+
+    let full: RemoveLiquiditySimulation = app
+    .wrap()
+    .query_wasm_smart(
+        market_address.clone(),
+        &MarketQueryMsg::SimulateRemoveLiquidity {
+            shares: result.shares.liquidity_shares
+        },
+    )
+    .unwrap();
+
+    assert!(full.below_minimum_liquidity);
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address,
+        &FactoryExecuteMsg::RemoveLiquidity {
+            market: Addr::unchecked(market_address),
+            shares: result.shares.liquidity_shares
+        },
+        &[],
+    )
+    .unwrap_err();
+
+}
+
+#[test]
+fn market_lifecycle_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let cancelled_market = create_market(
+        &mut app,
+        factory_address.clone()
+    );
+
+    let end_date = app.block_info().time.seconds() + 1_000;
+
+    let create_market_res = app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::CreateMarket {
+            title: "Will ETH flip BTC?".to_string(),
+            description: "Short lived market".to_string(),
+            end_date,
+            categories: vec!["crypto".to_string()],
+            media: ["".to_string(), "".to_string()],
+            collateral: None
+        },
+        &[],
+    )
+    .unwrap();
+
+    let market_address = create_market_res.events[1].attributes[0].value.clone();
+
+    let status = |app: &App, market: &String| -> MarketStatus {
+        let data: Data = app
+        .wrap()
+        .query_wasm_smart(
+            market.clone(),
+            &MarketQueryMsg::GetInfo {
+                account: Addr::unchecked("user")
+            },
+        )
+        .unwrap();
+        data.information.status
+    };
+
+    assert_eq!(status(&app, &cancelled_market), MarketStatus::Created);
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::CancelMarket {
+            market: Addr::unchecked(cancelled_market.clone()),
+            market_index: 1
+        },
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(status(&app, &cancelled_market), MarketStatus::Cancelled);
+
+    let usdc_denom = String::from(USDC_DENOM);
+    app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+        to_address: "user".to_string(),
+        amount: coins(10_000_000_000, &usdc_denom),
+    }))
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::InitializeLiquidity {
+            market: Addr::unchecked(market_address.clone()),
+            yes_price: Uint128::from(50_000_000u128),
+            liquidity: Uint128::from(1_000_000_000u128)
+        },
+        &coins(1_000_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    assert_eq!(status(&app, &market_address), MarketStatus::Open);
+
+    let buy = FactoryExecuteMsg::PlaceOrder {
+        market: Addr::unchecked(market_address.clone()),
+        variant: Outcome::Yes,
+        buy_or_sell: Side::Buy,
+        amount: Uint128::from(10_000_000u128),
+        min_out: None,
+        on_behalf_of: None,
+        use_balance: false,
+        referrer: None
+    };
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &buy, &coins(10_000_000, &usdc_denom))
+    .unwrap();
+
+    // A traded market can no longer be cancelled
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::CancelMarket {
+            market: Addr::unchecked(market_address.clone()),
+            market_index: 1
+        },
+        &[],
+    )
+    .unwrap_err();
+
+    app.update_block(|block| block.time = block.time.plus_seconds(1_000));
+
+    assert_eq!(status(&app, &market_address), MarketStatus::TradingClosed);
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &buy, &coins(10_000_000, &usdc_denom))
+    .unwrap_err();
+
+    let closed: MarketList = app
+    .wrap()
+    .query_wasm_smart(
+        factory_address.clone(),
+        &FactoryQueryMsg::FetchMarketsByStatus {
+            status: MarketStatus::TradingClosed,
+            account: Addr::unchecked("user"),
+            start_after: None,
+            limit: None
+        },
+    )
+    .unwrap();
+
+    assert_eq!(closed.contracts, vec![Addr::unchecked(market_address.clone())]);
+    assert_eq!(closed.indexes, vec![2u128]);
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::ProposeResolution {
+            market: Addr::unchecked(market_address.clone()),
+            variant: Outcome::Yes
+        },
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(status(&app, &market_address), MarketStatus::ResolutionProposed);
+
+    let propose = |variant: Outcome| FactoryExecuteMsg::ProposeResolution {
+        market: Addr::unchecked(market_address.clone()),
+        variant
+    };
+
+    // The cancelled market handed its active index to this one
+    let resolve = |variant: Outcome| FactoryExecuteMsg::ResolveMarket {
+        market: Addr::unchecked(market_address.clone()),
+        variant,
+        market_index: 1
+    };
+
+    // A different outcome challenges the proposal and restarts the period
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &propose(Outcome::No), &[])
+    .unwrap();
+
+    app.update_block(|block| block.time = block.time.plus_seconds(RESOLVE_DURATION - 1));
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &propose(Outcome::Yes), &[])
+    .unwrap();
+
+    let proposed: MarketList = app
+    .wrap()
+    .query_wasm_smart(
+        factory_address.clone(),
+        &FactoryQueryMsg::FetchMarketsByStatus {
+            status: MarketStatus::ResolutionProposed,
+            account: Addr::unchecked("user"),
+            start_after: None,
+            limit: None
+        },
+    )
+    .unwrap();
+
+    assert_eq!(proposed.contracts, vec![Addr::unchecked(market_address.clone())]);
+
+    app.update_block(|block| block.time = block.time.plus_seconds(RESOLVE_DURATION - 1));
+
+    let err = app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &resolve(Outcome::Yes), &[])
+    .unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<market::ContractError>(),
+        Some(market::ContractError::ChallengePeriodActive { .. })
+    ));
+
+    app.update_block(|block| block.time = block.time.plus_seconds(1));
+
+    // Only the proposed outcome can be resolved to
+    let err = app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &resolve(Outcome::No), &[])
+    .unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<market::ContractError>(),
+        Some(market::ContractError::ProposalMismatch { proposed: Outcome::Yes })
+    ));
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &resolve(Outcome::Yes), &[])
+    .unwrap();
+
+    assert_eq!(status(&app, &market_address), MarketStatus::Resolved);
+
+    let finalize = FactoryExecuteMsg::FinalizeMarket {
+        market: Addr::unchecked(market_address.clone())
+    };
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &finalize, &[])
+    .unwrap_err();
+
+    for account in ["user", "fees"] {
+        app
+        .execute_contract(
+            Addr::unchecked(account),
+            factory_address.clone(),
+            &FactoryExecuteMsg::Claim {
+                market: Addr::unchecked(market_address.clone()),
+                variant: Outcome::Yes,
+                on_behalf_of: None,
+                use_balance: false
+            },
+            &[],
+        )
+        .unwrap();
+    }
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &finalize, &[])
+    .unwrap();
+
+    assert_eq!(status(&app, &market_address), MarketStatus::Finalized);
+
+    let finalized: MarketList = app
+    .wrap()
+    .query_wasm_smart(
+        factory_address.clone(),
+        &FactoryQueryMsg::FetchMarketsByStatus {
+            status: MarketStatus::Finalized,
+            account: Addr::unchecked("user"),
+            start_after: None,
+            limit: None
+        },
+    )
+    .unwrap();
+
+    assert_eq!(finalized.contracts, vec![Addr::unchecked(market_address)]);
+
+    // Markets left the buckets of their earlier statuses
+    for status in [MarketStatus::Open, MarketStatus::TradingClosed, MarketStatus::ResolutionProposed, MarketStatus::Resolved] {
+        let list: MarketList = app
+        .wrap()
+        .query_wasm_smart(
+            factory_address.clone(),
+            &FactoryQueryMsg::FetchMarketsByStatus {
+                status,
+                account: Addr::unchecked("user"),
+                start_after: None,
+                limit: None
+            },
+        )
+        .unwrap();
+
+        assert!(list.contracts.is_empty());
+    }
+
+}
+
+#[test]
+fn typed_errors_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let market_address = create_market(
+        &mut app,
+        factory_address.clone()
+    );
+
+    let usdc_denom = String::from(USDC_DENOM);
+    app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+        to_address: "user".to_string(),
+        amount: coins(10_000_000_000, &usdc_denom),
+    }))
+    .unwrap();
+
+    let err = app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::InitializeLiquidity {
+            market: Addr::unchecked(market_address.clone()),
+            yes_price: Uint128::from(50_000_000u128),
+            liquidity: Uint128::from(1_000_000_000u128)
+        },
+        &coins(999_000_000, &usdc_denom),
+    )
+    .unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<factory::ContractError>(),
+        Some(factory::ContractError::InvalidFunds { expected, got })
+            if *expected == Uint128::from(1_000_000_000u128) && *got == Uint128::from(999_000_000u128)
+    ));
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::InitializeLiquidity {
+            market: Addr::unchecked(market_address.clone()),
+            yes_price: Uint128::from(50_000_000u128),
+            liquidity: Uint128::from(1_000_000_000u128)
+        },
+        &coins(1_000_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    // Selling shares that are not owned used to panic on underflow
+    let err = app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::PlaceOrder {
+            market: Addr::unchecked(market_address.clone()),
+            variant: Outcome::Yes,
+            buy_or_sell: Side::Sell,
+            amount: Uint128::from(5_000_000u128),
+            min_out: None,
+            on_behalf_of: None,
+            use_balance: false,
+            referrer: None
+        },
+        &[],
+    )
+    .unwrap_err();
+
+    let market_error = err.chain().find_map(|cause| cause.downcast_ref::<market::ContractError>());
+
+    assert!(matches!(
+        market_error,
+        Some(market::ContractError::InsufficientShares { have, want })
+            if have.is_zero() && *want == Uint128::from(5_000_000u128)
+    ));
+
+    let err = app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::PlaceOrder {
+            market: Addr::unchecked(market_address.clone()),
+            variant: Outcome::Yes,
+            buy_or_sell: Side::Buy,
+            amount: Uint128::from(500_000_000u128),
+            min_out: None,
+            on_behalf_of: None,
+            use_balance: false,
+            referrer: None
+        },
+        &coins(500_000_000, &usdc_denom),
+    )
+    .unwrap_err();
+
+    let market_error = err.chain().find_map(|cause| cause.downcast_ref::<market::ContractError>());
+
+    assert!(matches!(market_error, Some(market::ContractError::ImpactTooHigh { .. })));
+
+    // Queries fail with the same typed errors
+    let err = app
+    .wrap()
+    .query_wasm_smart::<MarketList>(
+        factory_address,
+        &FactoryQueryMsg::FetchMarkets {
+            page: 0,
+            items_per_page: 10,
+            account: Addr::unchecked("user"),
+            market_type: MarketType::All
+        },
+    )
+    .unwrap_err();
+
+    assert!(err.to_string().contains(&factory::ContractError::ZeroPage {}.to_string()));
+
+    let err = app
+    .wrap()
+    .query_wasm_smart::<RemoveLiquiditySimulation>(
+        market_address,
+        &MarketQueryMsg::SimulateRemoveLiquidity { shares: Uint128::MAX },
+    )
+    .unwrap_err();
+
+    assert!(err.to_string().contains("Insufficient shares"));
+
+}
+
+#[test]
+fn legacy_numeric_variants_test() {
+
+    let legacy: FactoryExecuteMsg = from_json(br#"{"place_order":{"market":"market","variant":"1","buy_or_sell":"0","amount":"5","min_out":null,"on_behalf_of":null}}"#).unwrap();
+    let typed: FactoryExecuteMsg = from_json(br#"{"place_order":{"market":"market","variant":"yes","buy_or_sell":"sell","amount":"5","min_out":null,"on_behalf_of":null}}"#).unwrap();
+
+    assert_eq!(legacy, typed);
+    assert_eq!(legacy, FactoryExecuteMsg::PlaceOrder {
+        market: Addr::unchecked("market"),
+        variant: Outcome::Yes,
+        buy_or_sell: Side::Sell,
+        amount: Uint128::from(5u128),
+        min_out: None,
+        on_behalf_of: None,
+        use_balance: false,
+        referrer: None
+    });
+
+    let claim: MarketExecuteMsg = from_json(br#"{"claim":{"variant":0,"receiver":"user"}}"#).unwrap();
+    assert_eq!(claim, MarketExecuteMsg::Claim {
+        variant: Outcome::No,
+        receiver: Addr::unchecked("user"),
+        credit_balance: false
+    });
+
+    assert_eq!(to_json_vec(&Outcome::No).unwrap(), br#""no""#.to_vec());
+    assert_eq!(to_json_vec(&Side::Buy).unwrap(), br#""buy""#.to_vec());
+
+    assert!(from_json::<Outcome>(br#""2""#).is_err());
+    assert!(from_json::<Side>(br#""yes""#).is_err());
+
+    // The schema advertises every accepted form
+    let schema = String::from_utf8(to_json_vec(&schemars::schema_for!(Side)).unwrap()).unwrap();
+    for form in [r#"["buy","sell"]"#, r#"["1","0"]"#, r#"[1,0]"#] {
+        assert!(schema.contains(form), "{} missing from {}", form, schema);
+    }
+
+}
+
+#[test]
+fn migrate_markets_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let markets = [
+        create_market(&mut app, factory_address.clone()),
+        create_market(&mut app, factory_address.clone())
+    ];
+
+    let new_code_id = app.store_code(market_contract());
+
+    let code_id = |app: &App, market: &String| app.wrap().query_wasm_contract_info(market.clone()).unwrap().code_id;
+
+    // The code id has not been through the timelock yet
+    let err = app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::MigrateMarkets { new_code_id, start_after: None, limit: None },
+        &[],
+    )
+    .unwrap_err();
+
+    assert!(err.root_cause().to_string().contains("configured code id"));
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::UpdateConfig { fees_address: None, market_code_id: Some(new_code_id), usdc: None },
+        &[],
+    )
+    .unwrap();
+
+    app.update_block(|block| block.time = block.time.plus_seconds(factory::execute::CONFIG_TIMELOCK));
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &FactoryExecuteMsg::ExecuteConfigUpdate {}, &[])
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("trader"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::MigrateMarkets { new_code_id, start_after: None, limit: None },
+        &[],
+    )
+    .unwrap_err();
+
+    let res = app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::MigrateMarkets { new_code_id, start_after: None, limit: Some(1) },
+        &[],
+    )
+    .unwrap();
+
+    assert!(res.events.iter().any(|event| event.attributes.iter().any(|attr| attr.key == "last_index" && attr.value == "1")));
+
+    assert_eq!(code_id(&app, &markets[0]), new_code_id);
+    assert_ne!(code_id(&app, &markets[1]), new_code_id);
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::MigrateMarkets { new_code_id, start_after: Some(1), limit: Some(1) },
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(code_id(&app, &markets[1]), new_code_id);
+
+    let details: Details = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::Details {})
+    .unwrap();
+
+    assert_eq!(details.market_code_id, new_code_id);
+
+    // New markets are created from the new code id and are still usable
+    let market_address = create_market(&mut app, factory_address.clone());
+    assert_eq!(code_id(&app, &market_address), new_code_id);
+
+    let data: Data = app
+    .wrap()
+    .query_wasm_smart(markets[1].clone(), &MarketQueryMsg::GetInfo { account: Addr::unchecked("user") })
+    .unwrap();
+
+    assert_eq!(data.information.status, MarketStatus::Created);
+
+    let new_factory_code_id = app.store_code(factory_contract());
+
+    app
+    .migrate_contract(Addr::unchecked("user"), factory_address.clone(), &FactoryMigrateMsg { super_admin: None, market_code_id: None }, new_factory_code_id)
+    .unwrap();
+
+    assert_eq!(app.wrap().query_wasm_contract_info(factory_address).unwrap().code_id, new_factory_code_id);
+
+}
+
+#[test]
+fn upgrade_factory_with_live_markets_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let market_address = create_market(&mut app, factory_address.clone());
+
+    let usdc_denom = String::from(USDC_DENOM);
+    app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+        to_address: "user".to_string(),
+        amount: coins(10_000_000_000, &usdc_denom),
+    }))
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::InitializeLiquidity {
+            market: Addr::unchecked(market_address.clone()),
+            yes_price: Uint128::from(50_000_000u128),
+            liquidity: Uint128::from(1_000_000_000u128)
+        },
+        &coins(1_000_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    let new_factory_code_id = app.store_code(factory_contract());
+    let new_market_code_id = app.store_code(market_contract());
+
+    let code_id = |app: &App, contract: &str| app.wrap().query_wasm_contract_info(contract.to_string()).unwrap().code_id;
+
+    let err = app
+    .migrate_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryMigrateMsg { super_admin: None, market_code_id: Some(0) },
+        new_factory_code_id
+    )
+    .unwrap_err();
+
+    assert!(err.root_cause().to_string().contains("code id must be greater than 0"));
+
+    app
+    .migrate_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryMigrateMsg { super_admin: None, market_code_id: Some(new_market_code_id) },
+        new_factory_code_id
+    )
+    .unwrap();
+
+    let details: Details = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::Details {})
+    .unwrap();
+
+    assert_eq!(details.market_code_id, new_market_code_id);
+
+    // The live market moves to the new code in the same block, without the config timelock
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::MigrateMarkets { new_code_id: new_market_code_id, start_after: None, limit: None },
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(code_id(&app, &market_address), new_market_code_id);
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::PlaceOrder {
+            market: Addr::unchecked(market_address.clone()),
+            variant: Outcome::Yes,
+            buy_or_sell: Side::Buy,
+            amount: Uint128::from(15_000_000u128),
+            min_out: None,
+            on_behalf_of: None,
+            use_balance: false,
+            referrer: None
+        },
+        &coins(15_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    let data: Data = app
+    .wrap()
+    .query_wasm_smart(market_address.clone(), &MarketQueryMsg::GetInfo { account: Addr::unchecked("user") })
+    .unwrap();
+
+    assert!(!data.shares.yes_shares.is_zero());
+
+}
+
+#[test]
+fn update_config_timelock_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let usdc_denom = String::from(USDC_DENOM);
+    app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+        to_address: "user".to_string(),
+        amount: coins(10_000_000_000, &usdc_denom),
+    }))
+    .unwrap();
+
+    // Created before the collateral changes, so it keeps using USDC
+    let old_market = create_market(&mut app, factory_address.clone());
+
+    let update = FactoryExecuteMsg::UpdateConfig {
+        fees_address: Some(Addr::unchecked("new_fees")),
+        market_code_id: None,
+        usdc: Some(Collateral::native("ibc/usdc"))
+    };
+
+    // Only whitelisted collateral can become the default
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &update, &[])
+    .unwrap_err();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::SetCollateral {
+            collateral: Collateral::native("ibc/usdc"),
+            decimals: 6,
+            min_liquidity: Uint128::from(10_000_000u128)
+        },
+        &[],
+    )
+    .unwrap();
+
+    app
+    .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &update, &[])
+    .unwrap_err();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::UpdateConfig { fees_address: None, market_code_id: None, usdc: None },
+        &[],
+    )
+    .unwrap_err();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::UpdateConfig { fees_address: None, market_code_id: Some(0), usdc: None },
+        &[],
+    )
+    .unwrap_err();
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &update, &[])
+    .unwrap();
+
+    let pending: Option<PendingConfig> = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::PendingConfig {})
+    .unwrap();
+
+    let pending = pending.unwrap();
+    assert_eq!(pending.executable_at, app.block_info().time.seconds() + factory::execute::CONFIG_TIMELOCK);
+
+    let err = app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &FactoryExecuteMsg::ExecuteConfigUpdate {}, &[])
+    .unwrap_err();
+
+    assert!(err.root_cause().to_string().contains("timelocked"));
+
+    // Cancelling drops the proposal, proposing again restarts the delay
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &FactoryExecuteMsg::CancelConfigUpdate {}, &[])
+    .unwrap();
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &FactoryExecuteMsg::ExecuteConfigUpdate {}, &[])
+    .unwrap_err();
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &update, &[])
+    .unwrap();
+
+    app.update_block(|block| block.time = block.time.plus_seconds(factory::execute::CONFIG_TIMELOCK));
+
+    let res = app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &FactoryExecuteMsg::ExecuteConfigUpdate {}, &[])
+    .unwrap();
+
+    let changes = res.events
+        .iter()
+        .filter(|event| event.attributes.iter().any(|attr| attr.key == "type" && attr.value == "config_update"))
+        .count();
+
+    assert_eq!(changes, 2);
+
+    let details: Details = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::Details {})
+    .unwrap();
+
+    assert_eq!(details.fees_address, Addr::unchecked("new_fees"));
+    assert_eq!(details.usdc, Collateral::native("ibc/usdc"));
+
+    let pending: Option<PendingConfig> = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::PendingConfig {})
+    .unwrap();
+
+    assert_eq!(pending, None);
+
+    // Existing markets still take the collateral they were created with
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::InitializeLiquidity {
+            market: Addr::unchecked(old_market.clone()),
+            yes_price: Uint128::from(50_000_000u128),
+            liquidity: Uint128::from(1_000_000_000u128)
+        },
+        &coins(1_000_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    let new_market = create_market(&mut app, factory_address.clone());
+
+    let data: Data = app
+    .wrap()
+    .query_wasm_smart(new_market, &MarketQueryMsg::GetInfo { account: Addr::unchecked("user") })
+    .unwrap();
+
+    assert_eq!(data.information.usdc, Collateral::native("ibc/usdc"));
+
+}
+
+#[test]
+fn unauthorized_calls_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let market_address = create_market(
+        &mut app,
+        factory_address.clone()
+    );
+
+    let market = Addr::unchecked(market_address.clone());
+    let mallory = Addr::unchecked("mallory");
+
+    // Privileged factory messages need a role the caller does not hold
+    let admin_msgs = vec![
+        FactoryExecuteMsg::AddAdmin { account: mallory.clone() },
+        FactoryExecuteMsg::GrantRole { account: mallory.clone(), role: Role::SuperAdmin },
+        FactoryExecuteMsg::RevokeRole { account: Addr::unchecked("user"), role: Role::SuperAdmin },
+        FactoryExecuteMsg::Pause {},
+        FactoryExecuteMsg::UpdateConfig { fees_address: Some(mallory.clone()), market_code_id: None, usdc: None },
+        FactoryExecuteMsg::SetCollateral { collateral: Collateral::native("umal"), decimals: 6, min_liquidity: Uint128::from(1u128) },
+        FactoryExecuteMsg::RemoveCollateral { collateral: Collateral::native(USDC_DENOM) },
+        FactoryExecuteMsg::AddCategory { category: "spam".to_string() },
+        FactoryExecuteMsg::SetReferralShare { share_bps: 10_000 },
+        FactoryExecuteMsg::SetPointsConfig {
+            per_volume: Uint128::from(1u128),
+            per_lp_day: Uint128::from(1u128),
+            per_win: Uint128::from(1u128),
+            epoch_cap: None
+        },
+        FactoryExecuteMsg::MigrateMarkets { new_code_id: 1, start_after: None, limit: None }
+    ];
+
+    for msg in admin_msgs {
+        let err = app
+        .execute_contract(mallory.clone(), factory_address.clone(), &msg, &[])
+        .unwrap_err();
+
+        assert!(
+            matches!(
+                err.downcast_ref::<factory::ContractError>(),
+                Some(factory::ContractError::MissingRole { .. }) | Some(factory::ContractError::Unauthorized {})
+            ),
+            "{:?} was not rejected as unauthorized: {}", msg, err
+        );
+    }
+
+    // Stats and balances are only credited by known markets
+    let market_msgs = vec![
+        FactoryExecuteMsg::RecordStats {
+            amount: Uint128::from(1_000_000u128),
+            account: mallory.clone(),
+            stat_type: String::from("volume"),
+            data: vec![]
+        },
+        FactoryExecuteMsg::CreditBalance { account: mallory.clone() }
+    ];
+
+    for msg in market_msgs {
+        app
+        .execute_contract(mallory.clone(), factory_address.clone(), &msg, &[])
+        .unwrap_err();
+    }
+
+    // Lifecycle transitions are limited to the market owner and role holders
+    let lifecycle_msgs = vec![
+        FactoryExecuteMsg::ProposeResolution { market: market.clone(), variant: Outcome::Yes },
+        FactoryExecuteMsg::CancelMarket { market: market.clone(), market_index: 1 }
+    ];
+
+    for msg in lifecycle_msgs {
+        let err = app
+        .execute_contract(mallory.clone(), factory_address.clone(), &msg, &[])
+        .unwrap_err();
+
+        let market_error = err.chain().find_map(|cause| cause.downcast_ref::<market::ContractError>());
+
+        assert!(matches!(market_error, Some(market::ContractError::NotOwnerOrAdmin { .. })));
+    }
+
+    // Markets only take orders and transitions from the factory
+    let direct_msgs = vec![
+        MarketExecuteMsg::ProposeResolution { variant: Outcome::Yes, receiver: Addr::unchecked("user") },
+        MarketExecuteMsg::ResolveMarket { variant: Outcome::Yes, receiver: Addr::unchecked("user"), market_index: 1 },
+        MarketExecuteMsg::CancelMarket { receiver: Addr::unchecked("user"), market_index: 1 },
+        MarketExecuteMsg::Claim { variant: Outcome::Yes, receiver: mallory.clone(), credit_balance: false },
+        MarketExecuteMsg::CollectFees { former_fees_addresses: vec![] }
+    ];
+
+    for msg in direct_msgs {
+        let err = app
+        .execute_contract(Addr::unchecked("user"), market.clone(), &msg, &[])
+        .unwrap_err();
+
+        assert!(
+            matches!(err.downcast_ref::<market::ContractError>(), Some(market::ContractError::Unauthorized {})),
+            "{:?} was not rejected as unauthorized: {}", msg, err
+        );
+    }
+
+    // Listings never return more than the maximum page size
+    for index in 0..35 {
+        app
+        .execute_contract(
+            Addr::unchecked("user"),
+            factory_address.clone(),
+            &FactoryExecuteMsg::AddCategory { category: format!("category-{:02}", index) },
+            &[],
+        )
+        .unwrap();
+    }
+
+    let categories = |app: &App, limit: Option<u32>| -> Vec<CategoryInfo> {
+        app
+        .wrap()
+        .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::ListCategories { start_after: None, limit })
+        .unwrap()
+    };
+
+    assert_eq!(categories(&app, None).len(), 10);
+    assert_eq!(categories(&app, Some(1_000)).len(), 30);
+
+}
+
+#[test]
+fn roles_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let has_role = |app: &App, account: &str, role: Role| -> bool {
+        app
+        .wrap()
+        .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::HasRole { account: Addr::unchecked(account), role })
+        .unwrap()
+    };
+
+    assert!(has_role(&app, "user", Role::Resolver));
+    assert!(!has_role(&app, "creator", Role::MarketCreator));
+
+    let create = FactoryExecuteMsg::CreateMarket {
+        title: "Will BTC reach $100,000?".to_string(),
+        description: "Bet on this market today!".to_string(),
+        end_date: 12456788910111213,
+        categories: vec!["finance".to_string()],
+        media: ["https://site.com/assets/media-0.png".to_string(), "https://site.com/assets/media-1.png".to_string()],
+        collateral: None
+    };
+
+    app
+    .execute_contract(Addr::unchecked("creator"), factory_address.clone(), &create, &[])
+    .unwrap_err();
+
+    // Only super admins manage roles
+    app
+    .execute_contract(
+        Addr::unchecked("creator"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::GrantRole { account: Addr::unchecked("creator"), role: Role::MarketCreator },
+        &[],
+    )
+    .unwrap_err();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::GrantRole { account: Addr::unchecked("creator"), role: Role::MarketCreator },
+        &[],
+    )
+    .unwrap();
+
+    app
+    .execute_contract(Addr::unchecked("creator"), factory_address.clone(), &create, &[])
+    .unwrap();
+
+    let is_admin: bool = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::IsAdmin { account: Addr::unchecked("creator") })
+    .unwrap();
+
+    // Operational roles alone do not make an admin
+    assert!(!is_admin);
+    assert!(!has_role(&app, "creator", Role::Resolver));
+
+    let is_admin: bool = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::IsAdmin { account: Addr::unchecked("user") })
+    .unwrap();
+
+    assert!(is_admin);
+
+    let roles: Vec<RoleAssignment> = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::ListRoles { start_after: None, limit: None })
+    .unwrap();
+
+    assert_eq!(roles, vec![
+        RoleAssignment { account: Addr::unchecked("creator"), roles: vec![Role::MarketCreator] },
+        RoleAssignment { account: Addr::unchecked("user"), roles: vec![Role::SuperAdmin] }
+    ]);
+
+    let err = app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::RevokeRole { account: Addr::unchecked("user"), role: Role::SuperAdmin },
+        &[],
+    )
+    .unwrap_err();
+
+    assert!(err.root_cause().to_string().contains("last super admin"));
+
+    // Pausing stops market creation until unpaused
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::GrantRole { account: Addr::unchecked("guardian"), role: Role::Pauser },
+        &[],
+    )
+    .unwrap();
+
+    app
+    .execute_contract(Addr::unchecked("creator"), factory_address.clone(), &FactoryExecuteMsg::Pause {}, &[])
+    .unwrap_err();
+
+    app
+    .execute_contract(Addr::unchecked("guardian"), factory_address.clone(), &FactoryExecuteMsg::Pause {}, &[])
+    .unwrap();
+
+    let err = app
+    .execute_contract(Addr::unchecked("creator"), factory_address.clone(), &create, &[])
+    .unwrap_err();
+
+    assert!(err.root_cause().to_string().contains("paused"));
+
+    app
+    .execute_contract(Addr::unchecked("guardian"), factory_address.clone(), &FactoryExecuteMsg::Unpause {}, &[])
+    .unwrap();
+
+    app
+    .execute_contract(Addr::unchecked("creator"), factory_address.clone(), &create, &[])
+    .unwrap();
+
+    // Super admin transfer only completes once the nominee accepts
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::TransferSuperAdmin { account: Addr::unchecked("new_admin") },
+        &[],
+    )
+    .unwrap();
+
+    let pending: Option<PendingSuperAdmin> = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::PendingSuperAdmin {})
+    .unwrap();
+
+    assert_eq!(pending, Some(PendingSuperAdmin { from: Addr::unchecked("user"), to: Addr::unchecked("new_admin") }));
+    assert!(has_role(&app, "user", Role::SuperAdmin));
+
+    app
+    .execute_contract(Addr::unchecked("creator"), factory_address.clone(), &FactoryExecuteMsg::AcceptSuperAdmin {}, &[])
+    .unwrap_err();
+
+    app
+    .execute_contract(Addr::unchecked("new_admin"), factory_address.clone(), &FactoryExecuteMsg::AcceptSuperAdmin {}, &[])
+    .unwrap();
+
+    assert!(has_role(&app, "new_admin", Role::SuperAdmin));
+    assert!(!has_role(&app, "user", Role::SuperAdmin));
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::GrantRole { account: Addr::unchecked("user"), role: Role::SuperAdmin },
+        &[],
+    )
+    .unwrap_err();
+
+    // A transfer proposed by a super admin who is later revoked cannot be accepted
+    app
+    .execute_contract(
+        Addr::unchecked("new_admin"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::GrantRole { account: Addr::unchecked("co_admin"), role: Role::SuperAdmin },
+        &[],
+    )
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("new_admin"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::TransferSuperAdmin { account: Addr::unchecked("nominee") },
+        &[],
+    )
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("co_admin"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::RevokeRole { account: Addr::unchecked("new_admin"), role: Role::SuperAdmin },
+        &[],
+    )
+    .unwrap();
+
+    let pending: Option<PendingSuperAdmin> = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::PendingSuperAdmin {})
+    .unwrap();
+
+    assert_eq!(pending, None);
+
+    app
+    .execute_contract(Addr::unchecked("nominee"), factory_address.clone(), &FactoryExecuteMsg::AcceptSuperAdmin {}, &[])
+    .unwrap_err();
+
+    assert!(!has_role(&app, "nominee", Role::SuperAdmin));
+
+}
+
+#[test]
+fn cw20_collateral_test() {
+
+    let mut app = mock_app();
+
+    // Plain denoms are read as native collateral, as stored before CW20 support
+    assert_eq!(from_json::<Collateral>(br#""usdc""#).unwrap(), Collateral::native("usdc"));
+
+    let cw20_code_id = app.store_code(cw20_contract());
+
+    let token = app
+    .instantiate_contract(
+        cw20_code_id,
+        Addr::unchecked("user"),
+        &cw20_base::msg::InstantiateMsg {
+            name: "Circle USD".to_string(),
+            symbol: "USDC".to_string(),
+            decimals: 6,
+            initial_balances: vec![
+                cw20::Cw20Coin { address: "user".to_string(), amount: Uint128::from(10_000_000_000u128) },
+                cw20::Cw20Coin { address: "trader".to_string(), amount: Uint128::from(100_000_000u128) }
+            ],
+            mint: None,
+            marketing: None
+        },
+        &[],
+        "cw20",
+        None,
+    )
+    .unwrap();
+
+    let collateral: Collateral = from_json(format!(r#"{{"c_w20_token":{{"contract_address":"{}"}}}}"#, token)).unwrap();
+    assert_eq!(collateral, Collateral::CW20Token { contract_address: token.clone() });
+
+    let factory_code_id = app.store_code(factory_contract());
+    let market_code_id = app.store_code(market_contract());
+
+    let factory_address = app
+    .instantiate_contract(
+        factory_code_id,
+        Addr::unchecked("user"),
+        &FactoryInstantiate {
+            usdc: collateral,
+            fees_address: Addr::unchecked("fees"),
+            market_code_id
+        },
+        &[],
+        "Instantiate Factory",
+        Some("user".to_string()),
+    )
+    .unwrap();
+
+    let market_address = Addr::unchecked(create_market(&mut app, factory_address.clone()));
+
+    let token_balance = |app: &App, account: &Addr| -> Uint128 {
+        let response: cw20::BalanceResponse = app
+        .wrap()
+        .query_wasm_smart(token.clone(), &cw20::Cw20QueryMsg::Balance { address: account.to_string() })
+        .unwrap();
+        response.balance
+    };
+
+    let send = |app: &mut App, sender: &str, amount: u128, msg: &FactoryReceiveMsg| {
+        app.execute_contract(
+            Addr::unchecked(sender),
+            token.clone(),
+            &cw20::Cw20ExecuteMsg::Send {
+                contract: factory_address.to_string(),
+                amount: Uint128::from(amount),
+                msg: to_json_binary(msg).unwrap()
+            },
+            &[],
+        )
+    };
+
+    send(&mut app, "user", 1_000_000_000, &FactoryReceiveMsg::InitializeLiquidity {
+        market: market_address.clone(),
+        yes_price: Uint128::from(50_000_000u128)
+    })
+    .unwrap();
+
+    assert_eq!(token_balance(&app, &market_address), Uint128::from(1_000_000_000u128));
+
+    // Tokens sent directly, not by the collateral token, are rejected
+    app
+    .execute_contract(
+        Addr::unchecked("trader"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            sender: "trader".to_string(),
+            amount: Uint128::from(10_000_000u128),
+            msg: to_json_binary(&FactoryReceiveMsg::Deposit {}).unwrap()
+        }),
+        &[],
+    )
+    .unwrap_err();
+
+    send(&mut app, "trader", 10_000_000, &FactoryReceiveMsg::PlaceOrder {
+        market: market_address.clone(),
+        variant: Outcome::Yes,
+        min_out: None,
+        on_behalf_of: None,
+        referrer: None
+    })
+    .unwrap();
+
+    assert_eq!(token_balance(&app, &Addr::unchecked("trader")), Uint128::from(90_000_000u128));
+
+    let data: Data = app
+    .wrap()
+    .query_wasm_smart(market_address.clone(), &MarketQueryMsg::GetInfo { account: Addr::unchecked("trader") })
+    .unwrap();
+
+    let shares = data.shares.yes_shares;
+    assert!(shares > Uint128::from(0u128));
+
+    let quote: Quote = app
+    .wrap()
+    .query_wasm_smart(market_address.clone(), &MarketQueryMsg::Quote { variant: Outcome::Yes, buy_or_sell: Side::Sell, amount: shares / Uint128::from(2u128) })
+    .unwrap();
+
+    // Sell proceeds are paid out with a CW20 transfer
+    app
+    .execute_contract(
+        Addr::unchecked("trader"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::PlaceOrder {
+            market: market_address.clone(),
+            variant: Outcome::Yes,
+            buy_or_sell: Side::Sell,
+            amount: shares / Uint128::from(2u128),
+            min_out: None,
+            on_behalf_of: None,
+            use_balance: false,
+            referrer: None
+        },
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(token_balance(&app, &Addr::unchecked("trader")), Uint128::from(90_000_000u128) + quote.amount_out);
+
+    // or credited to the internal balance through the factory's Receive
+    let quote: Quote = app
+    .wrap()
+    .query_wasm_smart(market_address.clone(), &MarketQueryMsg::Quote { variant: Outcome::Yes, buy_or_sell: Side::Sell, amount: shares - shares / Uint128::from(2u128) })
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("trader"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::PlaceOrder {
+            market: market_address.clone(),
+            variant: Outcome::Yes,
+            buy_or_sell: Side::Sell,
+            amount: shares - shares / Uint128::from(2u128),
+            min_out: None,
+            on_behalf_of: None,
+            use_balance: true,
+            referrer: None
+        },
+        &[],
+    )
+    .unwrap();
+
+    send(&mut app, "trader", 5_000_000, &FactoryReceiveMsg::Deposit {}).unwrap();
+
+    let balance: Uint128 = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::GetBalance { account: Addr::unchecked("trader"), collateral: None })
+    .unwrap();
+
+    assert_eq!(balance, quote.amount_out + Uint128::from(5_000_000u128));
+
+    let before = token_balance(&app, &Addr::unchecked("trader"));
+
+    app
+    .execute_contract(
+        Addr::unchecked("trader"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::Withdraw { amount: balance, collateral: None },
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(token_balance(&app, &Addr::unchecked("trader")), before + balance);
+
+}
+
+#[test]
+fn collateral_whitelist_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let atom = Collateral::native("uatom");
+
+    app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+        to_address: "user".to_string(),
+        amount: coins(10_000_000_000, "uatom"),
+    }))
+    .unwrap();
+
+    let create = |collateral: Option<Collateral>| FactoryExecuteMsg::CreateMarket {
+        title: "Will ATOM reach $100?".to_string(),
+        description: "Bet on this market today!".to_string(),
+        end_date: 12456788910111213,
+        categories: vec!["crypto".to_string()],
+        media: ["".to_string(), "".to_string()],
+        collateral
+    };
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &create(Some(atom.clone())), &[])
+    .unwrap_err();
+
+    let set_atom = FactoryExecuteMsg::SetCollateral {
+        collateral: atom.clone(),
+        decimals: 8,
+        min_liquidity: Uint128::from(100_000_000u128)
+    };
+
+    app
+    .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &set_atom, &[])
+    .unwrap_err();
+
+    let err = app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::SetCollateral { collateral: atom.clone(), decimals: 39, min_liquidity: Uint128::from(100_000_000u128) },
+        &[],
+    )
+    .unwrap_err();
+
+    assert!(err.root_cause().to_string().contains("must not exceed 18"));
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &set_atom, &[])
+    .unwrap();
+
+    let collaterals: Vec<CollateralConfig> = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::ListCollaterals { start_after: None, limit: None })
+    .unwrap();
+
+    assert_eq!(collaterals.len(), 2);
+    assert!(collaterals.iter().any(|config| config.collateral == Collateral::native(USDC_DENOM) && config.min_liquidity == Uint128::from(10_000_000u128)));
+
+    let res = app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &create(Some(atom.clone())), &[])
+    .unwrap();
+
+    let market_address = res.events[1].attributes[0].value.clone();
+
+    let data: Data = app
+    .wrap()
+    .query_wasm_smart(market_address.clone(), &MarketQueryMsg::GetInfo { account: Addr::unchecked("user") })
+    .unwrap();
+
+    assert_eq!(data.information.usdc, atom);
+    assert_eq!(data.information.min_liquidity, Uint128::from(100_000_000u128));
+
+    // The minimum follows the collateral's configuration, not a fixed 10 USDC
+    let err = app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::InitializeLiquidity {
+            market: Addr::unchecked(market_address.clone()),
+            yes_price: Uint128::from(50_000_000u128),
+            liquidity: Uint128::from(50_000_000u128)
+        },
+        &coins(50_000_000, "uatom"),
+    )
+    .unwrap_err();
+
+    assert!(err.root_cause().to_string().contains("at least 100000000"));
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::InitializeLiquidity {
+            market: Addr::unchecked(market_address.clone()),
+            yes_price: Uint128::from(50_000_000u128),
+            liquidity: Uint128::from(1_000_000_000u128)
+        },
+        &coins(1_000_000_000, "uatom"),
+    )
+    .unwrap();
+
+    let buy = FactoryExecuteMsg::PlaceOrder {
+        market: Addr::unchecked(market_address.clone()),
+        variant: Outcome::Yes,
+        buy_or_sell: Side::Buy,
+        amount: Uint128::from(10_000_000u128),
+        min_out: None,
+        on_behalf_of: None,
+        use_balance: false,
+        referrer: None
+    };
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &buy, &coins(10_000_000, USDC_DENOM))
+    .unwrap_err();
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &buy, &coins(10_000_000, "uatom"))
+    .unwrap();
+
+    let volumes: Vec<CollateralVolume> = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::GetCollateralVolumes { start_after: None, limit: None })
+    .unwrap();
+
+    assert_eq!(volumes, vec![CollateralVolume { collateral: atom.clone(), volume: Uint128::from(10_000_000u128) }]);
+
+    // Totals across collaterals are kept in 6 decimals
+    let statistics: Statistics = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::GetStatistics {})
+    .unwrap();
+
+    assert_eq!(statistics.volume, Uint128::from(100_000u128));
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::RemoveCollateral { collateral: Collateral::native(USDC_DENOM) },
+        &[],
+    )
+    .unwrap_err();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::RemoveCollateral { collateral: atom.clone() },
+        &[],
+    )
+    .unwrap();
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &create(Some(atom.clone())), &[])
+    .unwrap_err();
+
+    // Existing markets keep trading in the removed collateral
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &buy, &coins(10_000_000, "uatom"))
+    .unwrap();
+
+}
+
+#[test]
+fn collect_fees_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let end_date = app.block_info().time.seconds() + 1_000;
+
+    let create_market_res = app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::CreateMarket {
+            title: "Will ETH flip BTC?".to_string(),
+            description: "Short lived market".to_string(),
+            end_date,
+            categories: vec!["crypto".to_string()],
+            media: ["".to_string(), "".to_string()],
+            collateral: None
+        },
+        &[],
+    )
+    .unwrap();
+
+    let market_address = create_market_res.events[1].attributes[0].value.clone();
+
+    let usdc_denom = String::from(USDC_DENOM);
+    app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+        to_address: "user".to_string(),
+        amount: coins(10_000_000_000, &usdc_denom),
+    }))
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::InitializeLiquidity {
+            market: Addr::unchecked(market_address.clone()),
+            yes_price: Uint128::from(50_000_000u128),
+            liquidity: Uint128::from(1_000_000_000u128)
+        },
+        &coins(1_000_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    for variant in [Outcome::Yes, Outcome::No] {
+        app
+        .execute_contract(
+            Addr::unchecked("user"),
+            factory_address.clone(),
+            &FactoryExecuteMsg::PlaceOrder {
+                market: Addr::unchecked(market_address.clone()),
+                variant,
+                buy_or_sell: Side::Buy,
+                amount: Uint128::from(20_000_000u128),
+                min_out: None,
+                on_behalf_of: None,
+                use_balance: false,
+                referrer: None
+            },
+            &coins(20_000_000, &usdc_denom),
+        )
+        .unwrap();
+    }
+
+    let sell_quote: Quote = app
+    .wrap()
+    .query_wasm_smart(
+        market_address.clone(),
+        &MarketQueryMsg::Quote {
+            variant: Outcome::Yes,
+            buy_or_sell: Side::Sell,
+            amount: Uint128::from(5_000_000u128)
+        },
+    )
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::PlaceOrder {
+            market: Addr::unchecked(market_address.clone()),
+            variant: Outcome::Yes,
+            buy_or_sell: Side::Sell,
+            amount: Uint128::from(5_000_000u128),
+            min_out: None,
+            on_behalf_of: None,
+            use_balance: false,
+            referrer: None
+        },
+        &[],
+    )
+    .unwrap();
+
+    let fee_revenue = |app: &App| -> Uint128 {
+        app
+        .wrap()
+        .query_wasm_smart(
+            factory_address.clone(),
+            &FactoryQueryMsg::GetFeeRevenue {
+                market: Addr::unchecked(market_address.clone())
+            },
+        )
+        .unwrap()
+    };
+
+    let fees_balance = |app: &App| -> Uint128 {
+        app.wrap().query_balance("fees", USDC_DENOM).unwrap().amount
+    };
+
+    // Sell fees are paid out directly and count as revenue right away
+    let sell_fee = sell_quote.fees / Uint128::from(2u128);
+    assert_eq!(fee_revenue(&app), sell_fee);
+    assert_eq!(fees_balance(&app), sell_fee);
+
+    let collect = FactoryExecuteMsg::CollectFees {
+        markets: vec![Addr::unchecked(market_address.clone())]
+    };
+
+    // Only fee managers can sweep
+    app
+    .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &collect, &[])
+    .unwrap_err();
+
+    // Markets that are not resolved yet are skipped
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &collect, &[])
+    .unwrap();
+
+    assert_eq!(fee_revenue(&app), sell_fee);
+
+    // Rotating the fees address leaves the fee shares with the old one until they are collected
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::UpdateConfig { fees_address: Some(Addr::unchecked("treasury")), market_code_id: None, usdc: None },
+        &[],
+    )
+    .unwrap();
+
+    app.update_block(|block| block.time = block.time.plus_seconds(factory::execute::CONFIG_TIMELOCK));
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &FactoryExecuteMsg::ExecuteConfigUpdate {}, &[])
+    .unwrap();
+
+    resolve_market(&mut app, &factory_address, &market_address, Outcome::Yes, 1);
+
+    let fee_shares = |app: &App| -> Data {
+        app
+        .wrap()
+        .query_wasm_smart(
+            market_address.clone(),
+            &MarketQueryMsg::GetInfo {
+                account: Addr::unchecked("fees")
+            },
+        )
+        .unwrap()
+    };
+
+    let before = fee_shares(&app);
+    assert!(!before.shares.yes_shares.is_zero());
+    assert!(!before.shares.no_shares.is_zero());
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &collect, &[])
+    .unwrap();
+
+    // Winning fee shares pay out at the resolved price, losing ones are burned
+    let after = fee_shares(&app);
+    assert!(after.shares.yes_shares.is_zero());
+    assert!(after.shares.no_shares.is_zero());
+    assert_eq!(after.information.no_shares, before.information.no_shares - before.shares.no_shares);
+
+    // The old address's shares are paid to the current fees address
+    let collected = before.shares.yes_shares;
+    assert_eq!(fees_balance(&app), sell_fee);
+    assert_eq!(app.wrap().query_balance("treasury", USDC_DENOM).unwrap().amount, collected);
+    assert_eq!(fee_revenue(&app), sell_fee + collected);
+
+    let statistics: Statistics = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::GetStatistics {})
+    .unwrap();
+
+    assert_eq!(statistics.fees_collected, sell_fee + collected);
+
+    // Sweeping again finds nothing left to collect
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &collect, &[])
+    .unwrap();
+
+    assert_eq!(fee_revenue(&app), sell_fee + collected);
+
+    // The fee wallet no longer has to claim before the market can be finalized
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::Claim {
+            market: Addr::unchecked(market_address.clone()),
+            variant: Outcome::Yes,
+            on_behalf_of: None,
+            use_balance: false
+        },
+        &[],
+    )
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address,
+        &FactoryExecuteMsg::FinalizeMarket {
+            market: Addr::unchecked(market_address)
+        },
+        &[],
+    )
+    .unwrap();
+
+}
+
+fn splitter_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new_with_empty(
+        splitter::contract::execute,
+        splitter::contract::instantiate,
+        splitter::contract::query,
+    ).with_migrate(splitter::contract::migrate);
+    Box::new(contract)
+}
+
+#[test]
+fn fee_splitter_test() {
+
+    let mut app = mock_app();
+
+    let splitter_code_id = app.store_code(splitter_contract());
+
+    let recipients = vec![
+        Recipient { name: "treasury".to_string(), address: Addr::unchecked("treasury"), weight: 50 },
+        Recipient { name: "insurance".to_string(), address: Addr::unchecked("insurance"), weight: 30 },
+        Recipient { name: "stakers".to_string(), address: Addr::unchecked("stakers"), weight: 20 }
+    ];
+
+    // Weights must be positive and recipients unique
+    app
+    .instantiate_contract(
+        splitter_code_id,
+        Addr::unchecked("user"),
+        &SplitterInstantiate {
+            owner: Addr::unchecked("user"),
+            recipients: vec![recipients[0].clone(), recipients[0].clone()],
+            collaterals: vec![Collateral::native(USDC_DENOM)]
+        },
+        &[],
+        "Instantiate Splitter",
+        None,
+    )
+    .unwrap_err();
+
+    let splitter_address = app
+    .instantiate_contract(
+        splitter_code_id,
+        Addr::unchecked("user"),
+        &SplitterInstantiate {
+            owner: Addr::unchecked("user"),
+            recipients: recipients.clone(),
+            collaterals: vec![Collateral::native(USDC_DENOM)]
+        },
+        &[],
+        "Instantiate Splitter",
+        None,
+    )
+    .unwrap();
+
+    let factory_code_id = app.store_code(factory_contract());
+    let market_code_id = app.store_code(market_contract());
+
+    let factory_address = app
+    .instantiate_contract(
+        factory_code_id,
+        Addr::unchecked("user"),
+        &FactoryInstantiate {
+            usdc: Collateral::native(USDC_DENOM),
+            fees_address: splitter_address.clone(),
+            market_code_id
+        },
+        &[],
+        "Instantiate Factory",
+        None,
+    )
+    .unwrap();
+
+    let market_address = create_market(&mut app, factory_address.clone());
+
+    let usdc_denom = String::from(USDC_DENOM);
+    app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+        to_address: "user".to_string(),
+        amount: coins(10_000_000_000, &usdc_denom),
+    }))
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::InitializeLiquidity {
+            market: Addr::unchecked(market_address.clone()),
+            yes_price: Uint128::from(50_000_000u128),
+            liquidity: Uint128::from(1_000_000_000u128)
+        },
+        &coins(1_000_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::PlaceOrder {
+            market: Addr::unchecked(market_address.clone()),
+            variant: Outcome::Yes,
+            buy_or_sell: Side::Buy,
+            amount: Uint128::from(20_000_000u128),
+            min_out: None,
+            on_behalf_of: None,
+            use_balance: false,
+            referrer: None
+        },
+        &coins(20_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::PlaceOrder {
+            market: Addr::unchecked(market_address.clone()),
+            variant: Outcome::Yes,
+            buy_or_sell: Side::Sell,
+            amount: Uint128::from(10_000_000u128),
+            min_out: None,
+            on_behalf_of: None,
+            use_balance: false,
+            referrer: None
+        },
+        &[],
+    )
+    .unwrap();
+
+    let collateral = Collateral::native(USDC_DENOM);
+
+    // Sell fees land in the splitter as plain transfers
+    let received = app.wrap().query_balance(splitter_address.to_string(), USDC_DENOM).unwrap().amount;
+    assert!(!received.is_zero());
+
+    let undistributed: Uint128 = app
+    .wrap()
+    .query_wasm_smart(splitter_address.clone(), &SplitterQueryMsg::Undistributed { collateral: collateral.clone() })
+    .unwrap();
+
+    assert_eq!(undistributed, received);
+
+    let distribute = SplitterExecuteMsg::Distribute { collateral: collateral.clone() };
+
+    // Attached funds would be swept into the next distribution
+    app
+    .execute_contract(Addr::unchecked("user"), splitter_address.clone(), &distribute, &coins(1, &usdc_denom))
+    .unwrap_err();
+
+    // Only whitelisted collaterals can be distributed
+    app
+    .execute_contract(
+        Addr::unchecked("anyone"),
+        splitter_address.clone(),
+        &SplitterExecuteMsg::Distribute { collateral: Collateral::native("uother") },
+        &[],
+    )
+    .unwrap_err();
+
+    app
+    .execute_contract(
+        Addr::unchecked("treasury"),
+        splitter_address.clone(),
+        &SplitterExecuteMsg::UpdateCollaterals { collaterals: vec![Collateral::native("uother")] },
+        &[],
+    )
+    .unwrap_err();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        splitter_address.clone(),
+        &SplitterExecuteMsg::UpdateCollaterals { collaterals: vec![collateral.clone(), collateral.clone()] },
+        &[],
+    )
+    .unwrap_err();
+
+    app
+    .execute_contract(Addr::unchecked("anyone"), splitter_address.clone(), &distribute, &[])
+    .unwrap();
+
+    // Nothing new has arrived since
+    app
+    .execute_contract(Addr::unchecked("anyone"), splitter_address.clone(), &distribute, &[])
+    .unwrap_err();
+
+    let claimable = |app: &App, recipient: &str| -> Uint128 {
+        app
+        .wrap()
+        .query_wasm_smart(
+            splitter_address.clone(),
+            &SplitterQueryMsg::Claimable { recipient: Addr::unchecked(recipient), collateral: collateral.clone() },
+        )
+        .unwrap()
+    };
+
+    assert_eq!(claimable(&app, "treasury"), received.multiply_ratio(50u128, 100u128));
+    assert_eq!(claimable(&app, "insurance"), received.multiply_ratio(30u128, 100u128));
+    assert_eq!(claimable(&app, "stakers"), received.multiply_ratio(20u128, 100u128));
+
+    let distributions: Vec<Distribution> = app
+    .wrap()
+    .query_wasm_smart(splitter_address.clone(), &SplitterQueryMsg::Distributions { start_after: None, limit: None })
+    .unwrap();
+
+    assert_eq!(distributions.len(), 1);
+    assert_eq!(distributions[0].shares.len(), 3);
+
+    let list_claimable = |app: &App, recipient: &str, start_after: Option<String>, limit: Option<u32>| -> Vec<ClaimableBalance> {
+        app
+        .wrap()
+        .query_wasm_smart(
+            splitter_address.clone(),
+            &SplitterQueryMsg::ListClaimable { recipient: Addr::unchecked(recipient), start_after, limit },
+        )
+        .unwrap()
+    };
+
+    assert_eq!(list_claimable(&app, "treasury", None, Some(1)).len(), 1);
+    assert!(list_claimable(&app, "treasury", Some(collateral.to_string()), None).is_empty());
+
+    // Only the owner can change the split
+    let update = SplitterExecuteMsg::UpdateRecipients {
+        recipients: vec![Recipient { name: "treasury".to_string(), address: Addr::unchecked("treasury"), weight: 1 }]
+    };
+
+    app
+    .execute_contract(Addr::unchecked("treasury"), splitter_address.clone(), &update, &[])
+    .unwrap_err();
+
+    // Fees received before the update are split by the old weights
+    app
+    .send_tokens(Addr::unchecked("user"), splitter_address.clone(), &coins(1_000_000, &usdc_denom))
+    .unwrap();
+
+    let pending = received - distributions[0].amount + Uint128::from(1_000_000u128);
+    let stakers_share = claimable(&app, "stakers");
+
+    app
+    .execute_contract(Addr::unchecked("user"), splitter_address.clone(), &update, &[])
+    .unwrap();
+
+    let distributions: Vec<Distribution> = app
+    .wrap()
+    .query_wasm_smart(splitter_address.clone(), &SplitterQueryMsg::Distributions { start_after: None, limit: None })
+    .unwrap();
+
+    assert_eq!(distributions.len(), 2);
+    assert_eq!(distributions[1].shares.len(), 3);
+
+    // Removed recipients keep what was already distributed to them
+    assert_eq!(claimable(&app, "stakers"), stakers_share + pending.multiply_ratio(20u128, 100u128));
+    let stakers_share = claimable(&app, "stakers");
+
+    app
+    .execute_contract(
+        Addr::unchecked("stakers"),
+        splitter_address.clone(),
+        &SplitterExecuteMsg::Withdraw { collateral: collateral.clone(), amount: Some(stakers_share + Uint128::from(1u128)) },
+        &[],
+    )
+    .unwrap_err();
+
+    app
+    .send_tokens(Addr::unchecked("user"), Addr::unchecked("stakers"), &coins(1, &usdc_denom))
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("stakers"),
+        splitter_address.clone(),
+        &SplitterExecuteMsg::Withdraw { collateral: collateral.clone(), amount: None },
+        &coins(1, &usdc_denom),
+    )
+    .unwrap_err();
+
+    app
+    .execute_contract(
+        Addr::unchecked("stakers"),
+        splitter_address.clone(),
+        &SplitterExecuteMsg::Withdraw { collateral: collateral.clone(), amount: None },
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(app.wrap().query_balance("stakers", USDC_DENOM).unwrap().amount, stakers_share + Uint128::from(1u128));
+    assert!(claimable(&app, "stakers").is_zero());
+
+    let withdrawals: Vec<Withdrawal> = app
+    .wrap()
+    .query_wasm_smart(
+        splitter_address.clone(),
+        &SplitterQueryMsg::Withdrawals { recipient: Addr::unchecked("stakers"), start_after: None, limit: None },
+    )
+    .unwrap();
+
+    assert_eq!(withdrawals.len(), 1);
+    assert_eq!(withdrawals[0].amount, stakers_share);
+
+    // Withdrawn funds are not distributed again, only the rounding leftover is
+    let undistributed: Uint128 = app
+    .wrap()
+    .query_wasm_smart(splitter_address.clone(), &SplitterQueryMsg::Undistributed { collateral: collateral.clone() })
+    .unwrap();
+
+    assert_eq!(undistributed, pending - distributions[1].amount);
+
+    // New fees go to the new recipients
+
+    let treasury_share = claimable(&app, "treasury");
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address,
+        &FactoryExecuteMsg::PlaceOrder {
+            market: Addr::unchecked(market_address),
+            variant: Outcome::Yes,
+            buy_or_sell: Side::Sell,
+            amount: Uint128::from(10_000_000u128),
+            min_out: None,
+            on_behalf_of: None,
+            use_balance: false,
+            referrer: None
+        },
+        &[],
+    )
+    .unwrap();
+
+    app
+    .execute_contract(Addr::unchecked("anyone"), splitter_address.clone(), &distribute, &[])
+    .unwrap();
+
+    assert!(claimable(&app, "treasury") > treasury_share);
+    assert_eq!(
+        claimable(&app, "insurance"),
+        received.multiply_ratio(30u128, 100u128) + pending.multiply_ratio(30u128, 100u128)
+    );
+
+}
+
+#[test]
+fn referral_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let market_address = create_market(&mut app, factory_address.clone());
+
+    let usdc_denom = String::from(USDC_DENOM);
+    for account in ["user", "trader", "veteran"] {
+        app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+            to_address: account.to_string(),
+            amount: coins(10_000_000_000, &usdc_denom),
+        }))
+        .unwrap();
+    }
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::InitializeLiquidity {
+            market: Addr::unchecked(market_address.clone()),
+            yes_price: Uint128::from(50_000_000u128),
+            liquidity: Uint128::from(1_000_000_000u128)
+        },
+        &coins(1_000_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    let set_share = |share_bps: u16| FactoryExecuteMsg::SetReferralShare { share_bps };
+
+    // Only fee managers set the share, and it cannot exceed the whole fee
+    app
+    .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &set_share(2_000), &[])
+    .unwrap_err();
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &set_share(10_001), &[])
+    .unwrap_err();
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &set_share(2_000), &[])
+    .unwrap();
+
+    let order = |buy_or_sell: Side, amount: u128, referrer: Option<&str>| FactoryExecuteMsg::PlaceOrder {
+        market: Addr::unchecked(market_address.clone()),
+        variant: Outcome::Yes,
+        buy_or_sell,
+        amount: Uint128::from(amount),
+        min_out: None,
+        on_behalf_of: None,
+        use_balance: false,
+        referrer: referrer.map(Addr::unchecked)
+    };
+
+    app
+    .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &order(Side::Buy, 10_000_000, Some("trader")), &coins(10_000_000, &usdc_denom))
+    .unwrap_err();
+
+    // Accounts that already traded cannot be referred
+    app
+    .execute_contract(Addr::unchecked("veteran"), factory_address.clone(), &order(Side::Buy, 10_000_000, None), &coins(10_000_000, &usdc_denom))
+    .unwrap();
+
+    app
+    .execute_contract(Addr::unchecked("veteran"), factory_address.clone(), &order(Side::Buy, 10_000_000, Some("alice")), &coins(10_000_000, &usdc_denom))
+    .unwrap();
+
+    let buy_quote: Quote = app
+    .wrap()
+    .query_wasm_smart(
+        market_address.clone(),
+        &MarketQueryMsg::Quote { variant: Outcome::Yes, buy_or_sell: Side::Buy, amount: Uint128::from(20_000_000u128) },
+    )
+    .unwrap();
+
+    let fee_shares_before: Data = app
+    .wrap()
+    .query_wasm_smart(market_address.clone(), &MarketQueryMsg::GetInfo { account: Addr::unchecked("fees") })
+    .unwrap();
+
+    app
+    .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &order(Side::Buy, 20_000_000, Some("alice")), &coins(20_000_000, &usdc_denom))
+    .unwrap();
+
+    // On buys the cut is carved out of the fee shares and leaves the pool in
+    // collateral, credited to the referrer at the factory
+    let protocol_fee = buy_quote.fees / Uint128::from(2u128);
+    let buy_cut = protocol_fee.multiply_ratio(2_000u128, 10_000u128);
+
+    let fee_shares_after: Data = app
+    .wrap()
+    .query_wasm_smart(market_address.clone(), &MarketQueryMsg::GetInfo { account: Addr::unchecked("fees") })
+    .unwrap();
+
+    assert_eq!(fee_shares_after.shares.yes_shares - fee_shares_before.shares.yes_shares, protocol_fee - buy_cut);
+    assert_eq!(fee_shares_after.information.yes_liquidity - fee_shares_before.information.yes_liquidity, Uint128::from(20_000_000u128) - buy_cut);
+
+    let alice: Data = app
+    .wrap()
+    .query_wasm_smart(market_address.clone(), &MarketQueryMsg::GetInfo { account: Addr::unchecked("alice") })
+    .unwrap();
+
+    assert!(alice.shares.yes_shares.is_zero());
+
+    let second_buy_quote: Quote = app
+    .wrap()
+    .query_wasm_smart(
+        market_address.clone(),
+        &MarketQueryMsg::Quote { variant: Outcome::Yes, buy_or_sell: Side::Buy, amount: Uint128::from(10_000_000u128) },
+    )
+    .unwrap();
+
+    let second_buy_cut = (second_buy_quote.fees / Uint128::from(2u128)).multiply_ratio(2_000u128, 10_000u128);
+
+    // The referrer sticks, later referrers are ignored
+    app
+    .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &order(Side::Buy, 10_000_000, Some("bob")), &coins(10_000_000, &usdc_denom))
+    .unwrap();
+
+    let referrer = |app: &App, account: &str| -> Option<Addr> {
+        app
+        .wrap()
+        .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::GetReferrer { account: Addr::unchecked(account) })
+        .unwrap()
+    };
+
+    assert_eq!(referrer(&app, "trader"), Some(Addr::unchecked("alice")));
+    assert_eq!(referrer(&app, "veteran"), None);
+
+    let sell_quote: Quote = app
+    .wrap()
+    .query_wasm_smart(
+        market_address.clone(),
+        &MarketQueryMsg::Quote { variant: Outcome::Yes, buy_or_sell: Side::Sell, amount: Uint128::from(10_000_000u128) },
+    )
+    .unwrap();
+
+    app
+    .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &order(Side::Sell, 10_000_000, None), &[])
+    .unwrap();
+
+    // On sells the cut comes out of the transfer to the fees address
+    let sell_fee = sell_quote.fees / Uint128::from(2u128);
+    let sell_cut = sell_fee.multiply_ratio(2_000u128, 10_000u128);
+
+    assert_eq!(app.wrap().query_balance("fees", USDC_DENOM).unwrap().amount, sell_fee - sell_cut);
+
+    let stats: ReferralStats = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::GetReferralStats { referrer: Addr::unchecked("alice") })
+    .unwrap();
+
+    assert_eq!(stats.referees, 1);
+    assert_eq!(stats.earnings.len(), 1);
+    assert_eq!(stats.earnings[0].earned, buy_cut + second_buy_cut + sell_cut);
+    assert_eq!(stats.earnings[0].claimable, stats.earnings[0].earned);
+    assert!(stats.volume > Uint128::from(30_000_000u128));
+
+    let referees: Vec<Addr> = app
+    .wrap()
+    .query_wasm_smart(
+        factory_address.clone(),
+        &FactoryQueryMsg::ListReferees { referrer: Addr::unchecked("alice"), start_after: None, limit: None },
+    )
+    .unwrap();
+
+    assert_eq!(referees, vec![Addr::unchecked("trader")]);
+
+    let claim = FactoryExecuteMsg::ClaimReferralEarnings { collateral: None };
+
+    app
+    .execute_contract(Addr::unchecked("bob"), factory_address.clone(), &claim, &[])
+    .unwrap_err();
+
+    app
+    .execute_contract(Addr::unchecked("alice"), factory_address.clone(), &claim, &[])
+    .unwrap();
+
+    assert_eq!(app.wrap().query_balance("alice", USDC_DENOM).unwrap().amount, stats.earnings[0].earned);
+
+    app
+    .execute_contract(Addr::unchecked("alice"), factory_address, &claim, &[])
+    .unwrap_err();
+
+}
+
+#[test]
+fn points_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let usdc_denom = String::from(USDC_DENOM);
+    for account in ["user", "trader"] {
+        app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+            to_address: account.to_string(),
+            amount: coins(10_000_000_000, &usdc_denom),
+        }))
+        .unwrap();
+    }
+
+    app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+        to_address: "user".to_string(),
+        amount: coins(20_120_000, "ureward"),
+    }))
+    .unwrap();
+
+    let points_config = |epoch_cap: Option<u128>| FactoryExecuteMsg::SetPointsConfig {
+        per_volume: Uint128::from(1u128),
+        per_lp_day: Uint128::from(10u128),
+        per_win: Uint128::from(100u128),
+        epoch_cap: epoch_cap.map(Uint128::from)
+    };
+
+    app
+    .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &points_config(None), &[])
+    .unwrap_err();
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &points_config(None), &[])
+    .unwrap();
+
+    let day = 86_400u64;
+    let end_date = app.block_info().time.seconds() + 2 * day;
+
+    let create_market_res = app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::CreateMarket {
+            title: "Will ETH flip BTC?".to_string(),
+            description: "Short lived market".to_string(),
+            end_date,
+            categories: vec!["crypto".to_string()],
+            media: ["".to_string(), "".to_string()],
+            collateral: None
+        },
+        &[],
+    )
+    .unwrap();
+
+    let market_address = create_market_res.events[1].attributes[0].value.clone();
+
+    let initialize = |market: &String| FactoryExecuteMsg::InitializeLiquidity {
+        market: Addr::unchecked(market.clone()),
+        yes_price: Uint128::from(50_000_000u128),
+        liquidity: Uint128::from(1_000_000_000u128)
+    };
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &initialize(&market_address), &coins(1_000_000_000, &usdc_denom))
+    .unwrap();
+
+    let buy = |market: &String| FactoryExecuteMsg::PlaceOrder {
+        market: Addr::unchecked(market.clone()),
+        variant: Outcome::Yes,
+        buy_or_sell: Side::Buy,
+        amount: Uint128::from(20_000_000u128),
+        min_out: None,
+        on_behalf_of: None,
+        use_balance: false,
+        referrer: None
+    };
+
+    app
+    .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &buy(&market_address), &coins(20_000_000, &usdc_denom))
+    .unwrap();
+
+    let points = |app: &App, account: &str| -> AccountPoints {
+        app
+        .wrap()
+        .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::GetPoints { account: Addr::unchecked(account) })
+        .unwrap()
+    };
+
+    // One point per USDC traded
+    assert_eq!(points(&app, "trader").total, Uint128::from(20u128));
+
+    let accrue = FactoryExecuteMsg::AccrueLiquidityPoints {
+        markets: vec![Addr::unchecked(market_address.clone())]
+    };
+
+    // Ten points per USDC of liquidity per day
+    app.update_block(|block| block.time = block.time.plus_seconds(day));
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &accrue, &[])
+    .unwrap();
+
+    assert_eq!(points(&app, "user").total, Uint128::from(10_000u128));
+
+    app.update_block(|block| block.time = block.time.plus_seconds(day));
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &accrue, &[])
+    .unwrap();
+
+    assert_eq!(points(&app, "user").total, Uint128::from(20_000u128));
+
+    resolve_market(&mut app, &factory_address, &market_address, Outcome::Yes, 1);
+
+    app
+    .execute_contract(
+        Addr::unchecked("trader"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::Claim {
+            market: Addr::unchecked(market_address.clone()),
+            variant: Outcome::Yes,
+            on_behalf_of: None,
+            use_balance: false
+        },
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(points(&app, "trader").total, Uint128::from(120u128));
+
+    // Winning fee shares are protocol revenue and earn no points
+    app
+    .execute_contract(
+        Addr::unchecked("fees"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::Claim {
+            market: Addr::unchecked(market_address.clone()),
+            variant: Outcome::Yes,
+            on_behalf_of: None,
+            use_balance: false
+        },
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(points(&app, "fees").total, Uint128::from(0u128));
+
+    let fund = FactoryExecuteMsg::FundEpochRewards { epoch: 0 };
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &fund, &coins(20_120_000, "ureward"))
+    .unwrap_err();
+
+    app.update_block(|block| block.time = block.time.plus_seconds(6 * day));
+
+    // Liquidity stops earning once trading has ended
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &accrue, &[])
+    .unwrap();
+
+    let user_points = points(&app, "user");
+    assert_eq!(user_points.total, Uint128::from(20_000u128));
+    assert_eq!(user_points.current_epoch, 1);
+    assert!(user_points.epoch_points.is_zero());
+
+    // Rewards must be paid in a whitelisted collateral by a fee manager
+    let err = app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &fund, &coins(20_120_000, "ureward"))
+    .unwrap_err();
+
+    assert!(err.root_cause().to_string().contains("not whitelisted"));
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::SetCollateral {
+            collateral: Collateral::native("ureward"),
+            decimals: 6,
+            min_liquidity: Uint128::from(10_000_000u128)
+        },
+        &[],
+    )
+    .unwrap();
+
+    let err = app
+    .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &fund, &coins(1_000_000, &usdc_denom))
+    .unwrap_err();
+
+    assert!(err.root_cause().to_string().contains("Missing role"));
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &fund, &coins(20_120_000, "ureward"))
+    .unwrap();
+
+    let epoch: EpochInfo = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::GetEpoch { epoch: 0 })
+    .unwrap();
+
+    assert_eq!(epoch.total_points, Uint128::from(20_120u128));
+    assert_eq!(epoch.reward, Some(EpochReward { token: Collateral::native("ureward"), amount: Uint128::from(20_120_000u128) }));
+
+    let claim = FactoryExecuteMsg::ClaimEpochRewards { epochs: vec![0] };
+
+    app
+    .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &claim, &[])
+    .unwrap();
+
+    assert_eq!(app.wrap().query_balance("trader", "ureward").unwrap().amount, Uint128::from(120_000u128));
+
+    app
+    .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &claim, &[])
+    .unwrap_err();
+
+    let user_rewards: Uint128 = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::GetEpochRewards { epoch: 0, account: Addr::unchecked("user") })
+    .unwrap();
+
+    assert_eq!(user_rewards, Uint128::from(20_000_000u128));
+
+    // Points earned in an epoch are capped
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &points_config(Some(50)), &[])
+    .unwrap();
+
+    let open_market = create_market(&mut app, factory_address.clone());
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &initialize(&open_market), &coins(1_000_000_000, &usdc_denom))
+    .unwrap();
+
+    for _ in 0..3 {
+        app
+        .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &buy(&open_market), &coins(20_000_000, &usdc_denom))
+        .unwrap();
+    }
+
+    let trader_points = points(&app, "trader");
+    assert_eq!(trader_points.epoch_points, Uint128::from(50u128));
+    assert_eq!(trader_points.total, Uint128::from(170u128));
+
+}
+
+#[test]
+fn liquidity_rewards_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let usdc_denom = String::from(USDC_DENOM);
+    for account in ["user", "trader"] {
+        app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+            to_address: account.to_string(),
+            amount: coins(10_000_000_000, &usdc_denom),
+        }))
+        .unwrap();
+    }
+
+    for account in ["user", "trader"] {
+        app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+            to_address: account.to_string(),
+            amount: coins(1_000_000, "ureward"),
+        }))
+        .unwrap();
+    }
+
+    let market_address = create_market(&mut app, factory_address.clone());
+    let market = Addr::unchecked(market_address.clone());
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::InitializeLiquidity {
+            market: market.clone(),
+            yes_price: Uint128::from(50_000_000u128),
+            liquidity: Uint128::from(1_000_000_000u128)
+        },
+        &coins(1_000_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    let fund = |reward_per_second: u128| FactoryExecuteMsg::FundLiquidityRewards {
+        market: market.clone(),
+        reward_per_second: Uint128::from(reward_per_second)
+    };
+
+    // Only market creators attach reward budgets
+    app
+    .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &fund(100), &coins(1_000_000, "ureward"))
+    .unwrap_err();
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &fund(0), &coins(1_000_000, "ureward"))
+    .unwrap_err();
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &fund(100), &coins(1_000_000, "ureward"))
+    .unwrap();
+
+    let start = app.block_info().time.seconds();
+
+    let rewards: Option<LiquidityRewards> = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::GetLiquidityRewards { market: market.clone() })
+    .unwrap();
+
+    let rewards = rewards.unwrap();
+    assert_eq!(rewards.token, Collateral::native("ureward"));
+    assert_eq!(rewards.end, start + 10_000);
+
+    let pending = |app: &App, account: &str| -> Uint128 {
+        app
+        .wrap()
+        .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::PendingLiquidityRewards { market: market.clone(), account: Addr::unchecked(account) })
+        .unwrap()
+    };
+
+    app.update_block(|block| block.time = block.time.plus_seconds(1_000));
+
+    assert_eq!(pending(&app, "user"), Uint128::from(100_000u128));
+
+    // An equal deposit earns half of the emissions from then on
+    app
+    .execute_contract(
+        Addr::unchecked("trader"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::AddLiquidity { market: market.clone(), amount: Uint128::from(1_000_000_000u128) },
+        &coins(1_000_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    app.update_block(|block| block.time = block.time.plus_seconds(1_000));
+
+    assert_eq!(pending(&app, "user"), Uint128::from(150_000u128));
+    assert_eq!(pending(&app, "trader"), Uint128::from(50_000u128));
+
+    let data: Data = app
+    .wrap()
+    .query_wasm_smart(market_address.clone(), &MarketQueryMsg::GetInfo { account: Addr::unchecked("trader") })
+    .unwrap();
+
+    app
+    .execute_contract(
+        Addr::unchecked("trader"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::RemoveLiquidity { market: market.clone(), shares: data.shares.liquidity_shares },
+        &[],
+    )
+    .unwrap();
+
+    app.update_block(|block| block.time = block.time.plus_seconds(1_000));
+
+    assert_eq!(pending(&app, "user"), Uint128::from(250_000u128));
+    assert_eq!(pending(&app, "trader"), Uint128::from(50_000u128));
+
+    let claim = FactoryExecuteMsg::ClaimLiquidityRewards { markets: vec![market.clone()] };
+
+    app
+    .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &claim, &[])
+    .unwrap();
+
+    assert_eq!(app.wrap().query_balance("trader", "ureward").unwrap().amount, Uint128::from(1_050_000u128));
+
+    app
+    .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &claim, &[])
+    .unwrap_err();
+
+    // Top-ups must use the same token
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &fund(100), &coins(1_000_000, &usdc_denom))
+    .unwrap_err();
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &claim, &[])
+    .unwrap();
+
+    assert_eq!(app.wrap().query_balance("user", "ureward").unwrap().amount, Uint128::from(250_000u128));
+
+    // Emissions stop once the budget runs out
+    app.update_block(|block| block.time = block.time.plus_seconds(20_000));
+
+    assert_eq!(pending(&app, "user"), Uint128::from(700_000u128));
+
+}
+
+#[test]
+fn leaderboard_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let usdc_denom = String::from(USDC_DENOM);
+    for account in ["user", "alice", "bob", "carol"] {
+        app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+            to_address: account.to_string(),
+            amount: coins(10_000_000_000, &usdc_denom),
+        }))
+        .unwrap();
+    }
+
+    // Epochs start counting once points are configured
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::SetPointsConfig {
+            per_volume: Uint128::from(1u128),
+            per_lp_day: Uint128::from(0u128),
+            per_win: Uint128::from(0u128),
+            epoch_cap: None
+        },
+        &[],
+    )
+    .unwrap();
+
+    let initialize = |market: &String| FactoryExecuteMsg::InitializeLiquidity {
+        market: Addr::unchecked(market.clone()),
+        yes_price: Uint128::from(50_000_000u128),
+        liquidity: Uint128::from(1_000_000_000u128)
+    };
+
+    let order = |market: &String, variant: Outcome, buy_or_sell: Side, amount: u128| FactoryExecuteMsg::PlaceOrder {
+        market: Addr::unchecked(market.clone()),
+        variant,
+        buy_or_sell,
+        amount: Uint128::from(amount),
+        min_out: None,
+        on_behalf_of: None,
+        use_balance: false,
+        referrer: None
+    };
+
+    let day = 86_400u64;
+    let end_date = app.block_info().time.seconds() + 2 * day;
+
+    let create_market_res = app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::CreateMarket {
+            title: "Will ETH flip BTC?".to_string(),
+            description: "Short lived market".to_string(),
+            end_date,
+            categories: vec!["crypto".to_string()],
+            media: ["".to_string(), "".to_string()],
+            collateral: None
+        },
+        &[],
+    )
+    .unwrap();
+
+    let market_address = create_market_res.events[1].attributes[0].value.clone();
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &initialize(&market_address), &coins(1_000_000_000, &usdc_denom))
+    .unwrap();
+
+    app
+    .execute_contract(Addr::unchecked("alice"), factory_address.clone(), &order(&market_address, Outcome::Yes, Side::Buy, 25_000_000), &coins(25_000_000, &usdc_denom))
+    .unwrap();
+
+    for _ in 0..2 {
+        app
+        .execute_contract(Addr::unchecked("bob"), factory_address.clone(), &order(&market_address, Outcome::Yes, Side::Buy, 10_000_000), &coins(10_000_000, &usdc_denom))
+        .unwrap();
+    }
+
+    app
+    .execute_contract(Addr::unchecked("carol"), factory_address.clone(), &order(&market_address, Outcome::No, Side::Buy, 5_000_000), &coins(5_000_000, &usdc_denom))
+    .unwrap();
+
+    // Selling straight back realizes a loss on fees and impact
+    let data: Data = app
+    .wrap()
+    .query_wasm_smart(market_address.clone(), &MarketQueryMsg::GetInfo { account: Addr::unchecked("alice") })
+    .unwrap();
+
+    app
+    .execute_contract(Addr::unchecked("alice"), factory_address.clone(), &order(&market_address, Outcome::Yes, Side::Sell, data.shares.yes_shares.u128()), &[])
+    .unwrap();
+
+    app.update_block(|block| block.time = block.time.plus_seconds(3 * day));
+
+    resolve_market(&mut app, &factory_address, &market_address, Outcome::Yes, 1);
+
+    app
+    .execute_contract(
+        Addr::unchecked("bob"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::Claim {
+            market: Addr::unchecked(market_address.clone()),
+            variant: Outcome::Yes,
+            on_behalf_of: None,
+            use_balance: false
+        },
+        &[],
+    )
+    .unwrap();
+
+    // Claiming the losing outcome writes the shares off at their full cost
+    let claim_no = FactoryExecuteMsg::Claim {
+        market: Addr::unchecked(market_address.clone()),
+        variant: Outcome::No,
+        on_behalf_of: None,
+        use_balance: false
+    };
+
+    app
+    .execute_contract(Addr::unchecked("carol"), factory_address.clone(), &claim_no, &[])
+    .unwrap();
+
+    app
+    .execute_contract(Addr::unchecked("carol"), factory_address.clone(), &claim_no, &[])
+    .unwrap_err();
+
+    let position: PositionInfo = app
+    .wrap()
+    .query_wasm_smart(market_address.clone(), &MarketQueryMsg::GetPosition { account: Addr::unchecked("carol") })
+    .unwrap();
+
+    assert_eq!(position.no_shares, Uint128::zero());
+    assert_eq!(position.no_cost, Uint128::zero());
+    assert_eq!(position.realized_pnl, Int128::new(-5_000_000));
+
+    let stats = |app: &App, account: &str, epoch: Option<u64>| -> AccountStats {
+        app
+        .wrap()
+        .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::GetAccountStats { account: Addr::unchecked(account), epoch })
+        .unwrap()
+    };
+
+    let alice = stats(&app, "alice", None);
+    let bob = stats(&app, "bob", None);
+    let carol = stats(&app, "carol", None);
+
+    assert_eq!(alice.trades, 2);
+    assert!(alice.volume > Uint128::from(25_000_000u128));
+    assert!(alice.realized_pnl < Int128::zero());
+    assert_eq!(bob, AccountStats { volume: Uint128::from(20_000_000u128), trades: 2, realized_pnl: bob.realized_pnl });
+    assert!(bob.realized_pnl > Int128::zero());
+    assert_eq!(carol, AccountStats { volume: Uint128::from(5_000_000u128), trades: 1, realized_pnl: Int128::new(-5_000_000) });
+    assert_eq!(stats(&app, "alice", Some(0)), alice);
+
+    let page = |app: &App, metric: LeaderboardMetric, epoch: Option<u64>, start_after: Option<Binary>, limit: Option<u32>| -> LeaderboardPage {
+        app
+        .wrap()
+        .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::Leaderboard { metric, epoch, start_after, limit })
+        .unwrap()
+    };
+
+    let leaderboard = |app: &App, metric: LeaderboardMetric, epoch: Option<u64>, start_after: Option<Binary>, limit: Option<u32>| -> Vec<String> {
+        page(app, metric, epoch, start_after, limit).entries.into_iter().map(|entry| entry.account.to_string()).collect()
+    };
+
+    assert_eq!(leaderboard(&app, LeaderboardMetric::Volume, None, None, None), vec!["alice", "bob", "carol"]);
+    assert_eq!(page(&app, LeaderboardMetric::Volume, None, None, None).next_start_after, None);
+
+    let first = page(&app, LeaderboardMetric::Volume, None, None, Some(1));
+    assert_eq!(first.entries[0].account, Addr::unchecked("alice"));
+    assert_eq!(leaderboard(&app, LeaderboardMetric::Volume, None, first.next_start_after, None), vec!["bob", "carol"]);
+
+    assert_eq!(leaderboard(&app, LeaderboardMetric::RealizedPnl, None, None, None), vec!["bob", "alice", "carol"]);
+
+    let first = page(&app, LeaderboardMetric::RealizedPnl, None, None, Some(2));
+    assert_eq!(leaderboard(&app, LeaderboardMetric::RealizedPnl, None, first.next_start_after, None), vec!["carol"]);
+
+    // A new epoch starts a new competition
+    app.update_block(|block| block.time = block.time.plus_seconds(7 * day));
+
+    let open_market = create_market(&mut app, factory_address.clone());
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &initialize(&open_market), &coins(1_000_000_000, &usdc_denom))
+    .unwrap();
+
+    for _ in 0..2 {
+        app
+        .execute_contract(Addr::unchecked("carol"), factory_address.clone(), &order(&open_market, Outcome::No, Side::Buy, 25_000_000), &coins(25_000_000, &usdc_denom))
+        .unwrap();
+    }
+
+    assert_eq!(leaderboard(&app, LeaderboardMetric::Volume, Some(1), None, None), vec!["carol"]);
+    assert_eq!(leaderboard(&app, LeaderboardMetric::Volume, None, None, None), vec!["carol", "alice", "bob"]);
+    assert_eq!(stats(&app, "carol", Some(1)).volume, Uint128::from(50_000_000u128));
+    assert_eq!(stats(&app, "carol", None).volume, Uint128::from(55_000_000u128));
+
+    // The cursor keeps its place when the account it ends on climbs the ranking
+    let first = page(&app, LeaderboardMetric::Volume, None, None, Some(2));
+    assert_eq!(first.entries.iter().map(|entry| entry.account.to_string()).collect::<Vec<String>>(), vec!["carol", "alice"]);
+
+    app
+    .execute_contract(Addr::unchecked("alice"), factory_address.clone(), &order(&open_market, Outcome::Yes, Side::Buy, 10_000_000), &coins(10_000_000, &usdc_denom))
+    .unwrap();
+
+    assert_eq!(leaderboard(&app, LeaderboardMetric::Volume, None, None, Some(1)), vec!["alice"]);
+    assert_eq!(leaderboard(&app, LeaderboardMetric::Volume, None, first.next_start_after, None), vec!["bob"]);
+
+}
+
+#[test]
+fn statistics_history_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let usdc_denom = String::from(USDC_DENOM);
+    for account in ["user", "alice", "bob"] {
+        app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+            to_address: account.to_string(),
+            amount: coins(10_000_000_000, &usdc_denom),
+        }))
+        .unwrap();
+    }
+
+    let day = 86_400u64;
+    let today = app.block_info().time.seconds() / day;
+
+    let create_market_res = app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::CreateMarket {
+            title: "Will ETH flip BTC?".to_string(),
+            description: "Short lived market".to_string(),
+            end_date: app.block_info().time.seconds() + day,
+            categories: vec!["crypto".to_string()],
+            media: ["".to_string(), "".to_string()],
+            collateral: None
+        },
+        &[],
+    )
+    .unwrap();
+
+    let market_address = create_market_res.events[1].attributes[0].value.clone();
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::InitializeLiquidity {
+            market: Addr::unchecked(market_address.clone()),
+            yes_price: Uint128::from(50_000_000u128),
+            liquidity: Uint128::from(1_000_000_000u128)
+        },
+        &coins(1_000_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    let buy = FactoryExecuteMsg::PlaceOrder {
+        market: Addr::unchecked(market_address.clone()),
+        variant: Outcome::Yes,
+        buy_or_sell: Side::Buy,
+        amount: Uint128::from(10_000_000u128),
+        min_out: None,
+        on_behalf_of: None,
+        use_balance: false,
+        referrer: None
+    };
+
+    for account in ["alice", "alice", "bob"] {
+        app
+        .execute_contract(Addr::unchecked(account), factory_address.clone(), &buy, &coins(10_000_000, &usdc_denom))
+        .unwrap();
+    }
+
+    app.update_block(|block| block.time = block.time.plus_seconds(2 * day));
+
+    resolve_market(&mut app, &factory_address, &market_address, Outcome::Yes, 1);
+
+    let history: Vec<DailyStatistics> = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::GetStatisticsHistory { from_day: today, to_day: today + 2 })
+    .unwrap();
+
+    assert_eq!(history, vec![
+        DailyStatistics {
+            day: today,
+            volume: Uint128::from(30_000_000u128),
+            trades: Uint128::from(3u128),
+            new_wallets: Uint128::from(2u128),
+            markets_created: Uint128::from(1u128),
+            markets_resolved: Uint128::from(0u128)
+        },
+        DailyStatistics { day: today + 1, ..Default::default() },
+        DailyStatistics { day: today + 2, markets_resolved: Uint128::from(1u128), ..Default::default() }
+    ]);
+
+    let history: Vec<DailyStatistics> = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::GetStatisticsHistory { from_day: today + 1, to_day: today })
+    .unwrap();
+
+    assert!(history.is_empty());
+
+    app
+    .wrap()
+    .query_wasm_smart::<Vec<DailyStatistics>>(factory_address.clone(), &FactoryQueryMsg::GetStatisticsHistory { from_day: today, to_day: today + 366 })
+    .unwrap_err();
+
+}
+
+#[test]
+fn category_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let usdc_denom = String::from(USDC_DENOM);
+    app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+        to_address: "user".to_string(),
+        amount: coins(10_000_000_000, &usdc_denom),
+    }))
+    .unwrap();
+
+    let first_market = create_market(&mut app, factory_address.clone());
+    let second_market = create_market(&mut app, factory_address.clone());
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::InitializeLiquidity {
+            market: Addr::unchecked(first_market.clone()),
+            yes_price: Uint128::from(50_000_000u128),
+            liquidity: Uint128::from(1_000_000_000u128)
+        },
+        &coins(1_000_000_000, &usdc_denom),
+    )
+    .unwrap();
+
+    let by_category = |app: &App, category: &str, status: Option<MarketStatus>, start_after: Option<u128>, limit: Option<u32>| -> Vec<String> {
+        let market_list: MarketList = app
+        .wrap()
+        .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::FetchMarketsByCategory {
+            category: category.to_string(),
+            status,
+            start_after,
+            limit
+        })
+        .unwrap();
+        market_list.contracts.into_iter().map(|market| market.to_string()).collect()
+    };
+
+    assert_eq!(by_category(&app, "crypto", None, None, None), vec![second_market.clone(), first_market.clone()]);
+    assert_eq!(by_category(&app, "finance", None, None, Some(1)), vec![second_market.clone()]);
+    assert_eq!(by_category(&app, "finance", None, Some(2), None), vec![first_market.clone()]);
+    assert_eq!(by_category(&app, "crypto", Some(MarketStatus::Open), None, None), vec![first_market.clone()]);
+    assert!(by_category(&app, "sports", None, None, None).is_empty());
+
+    let cursor = |app: &App, status: Option<MarketStatus>, start_after: Option<u128>, limit: Option<u32>| -> Option<u128> {
+        let market_list: MarketList = app
+        .wrap()
+        .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::FetchMarketsByCategory {
+            category: "crypto".to_string(),
+            status,
+            start_after,
+            limit
+        })
+        .unwrap();
+        market_list.next_start_after
+    };
+
+    // A cursor is returned while markets are left to scan
+    assert_eq!(cursor(&app, Some(MarketStatus::Open), None, Some(1)), Some(1));
+    assert_eq!(cursor(&app, None, None, Some(1)), Some(2));
+    assert_eq!(cursor(&app, None, None, None), None);
+
+    let categories = |app: &App| -> Vec<CategoryInfo> {
+        app
+        .wrap()
+        .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::ListCategories { start_after: None, limit: None })
+        .unwrap()
+    };
+
+    assert_eq!(categories(&app), vec![
+        CategoryInfo { name: "crypto".to_string(), registered: false, markets: 2 },
+        CategoryInfo { name: "finance".to_string(), registered: false, markets: 2 }
+    ]);
+
+    let add_category = |category: &str| FactoryExecuteMsg::AddCategory { category: category.to_string() };
+
+    app
+    .execute_contract(Addr::unchecked("trader"), factory_address.clone(), &add_category("crypto"), &[])
+    .unwrap_err();
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &add_category(" "), &[])
+    .unwrap_err();
+
+    for category in ["crypto", "sports"] {
+        app
+        .execute_contract(Addr::unchecked("user"), factory_address.clone(), &add_category(category), &[])
+        .unwrap();
+    }
+
+    // Once categories are registered, new markets must use them
+    let create = |categories: Vec<&str>| FactoryExecuteMsg::CreateMarket {
+        title: "Will the Lakers win?".to_string(),
+        description: "Bet on this market today!".to_string(),
+        end_date: 12456788910111213,
+        categories: categories.into_iter().map(String::from).collect(),
+        media: ["".to_string(), "".to_string()],
+        collateral: None
+    };
+
+    let err = app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &create(vec!["sports", "finance"]), &[])
+    .unwrap_err();
+
+    assert_eq!(err.root_cause().to_string(), "Category finance is not registered");
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &create(vec!["sports"]), &[])
+    .unwrap();
+
+    assert_eq!(categories(&app), vec![
+        CategoryInfo { name: "crypto".to_string(), registered: true, markets: 2 },
+        CategoryInfo { name: "finance".to_string(), registered: false, markets: 2 },
+        CategoryInfo { name: "sports".to_string(), registered: true, markets: 1 }
+    ]);
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &FactoryExecuteMsg::RemoveCategory { category: "finance".to_string() }, &[])
+    .unwrap_err();
+
+    app
+    .execute_contract(Addr::unchecked("user"), factory_address.clone(), &FactoryExecuteMsg::RemoveCategory { category: "crypto".to_string() }, &[])
+    .unwrap();
+
+    assert_eq!(categories(&app)[0], CategoryInfo { name: "crypto".to_string(), registered: false, markets: 2 });
+
+}
+
+#[test]
+fn search_markets_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    let usdc_denom = String::from(USDC_DENOM);
+    for account in ["user", "creator", "trader"] {
+        app.sudo(cw_multi_test::SudoMsg::Bank(cw_multi_test::BankSudo::Mint {
+            to_address: account.to_string(),
+            amount: coins(10_000_000_000, &usdc_denom),
+        }))
+        .unwrap();
+    }
+
+    app
+    .execute_contract(
+        Addr::unchecked("user"),
+        factory_address.clone(),
+        &FactoryExecuteMsg::GrantRole { account: Addr::unchecked("creator"), role: Role::MarketCreator },
+        &[],
+    )
+    .unwrap();
+
+    let day = 86_400u64;
+    let now = app.block_info().time.seconds();
+
+    let create = |app: &mut App, owner: &str, end_date: u64, category: &str| -> String {
+        let res = app
+        .execute_contract(
+            Addr::unchecked(owner),
+            factory_address.clone(),
+            &FactoryExecuteMsg::CreateMarket {
+                title: "Will it happen?".to_string(),
+                description: "Bet on this market today!".to_string(),
+                end_date,
+                categories: vec![category.to_string()],
+                media: ["".to_string(), "".to_string()],
+                collateral: None
+            },
+            &[],
+        )
+        .unwrap();
+        res.events[1].attributes[0].value.clone()
+    };
+
+    let first = create(&mut app, "user", now + day, "crypto");
+    let second = create(&mut app, "creator", now + 10 * day, "sports");
+    let third = create(&mut app, "user", now + 100 * day, "crypto");
+
+    for (owner, market, liquidity) in [("user", &first, 1_000_000_000u128), ("creator", &second, 2_000_000_000u128)] {
+        app
+        .execute_contract(
+            Addr::unchecked(owner),
+            factory_address.clone(),
+            &FactoryExecuteMsg::InitializeLiquidity {
+                market: Addr::unchecked(market.clone()),
+                yes_price: Uint128::from(50_000_000u128),
+                liquidity: Uint128::from(liquidity)
+            },
+            &coins(liquidity, &usdc_denom),
+        )
+        .unwrap();
+    }
+
+    for (market, amount) in [(&first, 25_000_000u128), (&second, 10_000_000u128)] {
+        app
+        .execute_contract(
+            Addr::unchecked("trader"),
+            factory_address.clone(),
+            &FactoryExecuteMsg::PlaceOrder {
+                market: Addr::unchecked(market.clone()),
+                variant: Outcome::Yes,
+                buy_or_sell: Side::Buy,
+                amount: Uint128::from(amount),
+                min_out: None,
+                on_behalf_of: None,
+                use_balance: false,
+                referrer: None
+            },
+            &coins(amount, &usdc_denom),
+        )
+        .unwrap();
+    }
+
+    let search = |app: &App, filter: MarketFilter, sort_by: MarketSort, descending: bool, start_after: Option<u128>, limit: Option<u32>| -> Vec<String> {
+        let market_list: MarketList = app
+        .wrap()
+        .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::SearchMarkets { filter, sort_by, descending, start_after, limit })
+        .unwrap();
+        market_list.contracts.into_iter().map(|market| market.to_string()).collect()
+    };
+
+    let all = MarketFilter::default();
+
+    assert_eq!(search(&app, all.clone(), MarketSort::Created, false, None, None), vec![first.clone(), second.clone(), third.clone()]);
+    assert_eq!(search(&app, all.clone(), MarketSort::Created, true, None, Some(2)), vec![third.clone(), second.clone()]);
+    assert_eq!(search(&app, all.clone(), MarketSort::Created, true, Some(2), None), vec![first.clone()]);
+
+    let by_owner = |owner: &str| MarketFilter { owner: Some(Addr::unchecked(owner)), ..Default::default() };
+
+    assert_eq!(search(&app, by_owner("user"), MarketSort::Created, false, None, None), vec![first.clone(), third.clone()]);
+    assert_eq!(search(&app, by_owner("creator"), MarketSort::Volume, false, None, None), vec![second.clone()]);
+
+    // Ending this week
+    let this_week = MarketFilter { ends_after: Some(now), ends_before: Some(now + 7 * day), ..Default::default() };
+
+    assert_eq!(search(&app, this_week.clone(), MarketSort::EndDate, false, None, None), vec![first.clone()]);
+    assert_eq!(search(&app, this_week, MarketSort::Created, false, None, None), vec![first.clone()]);
+
+    assert_eq!(search(&app, all.clone(), MarketSort::EndDate, true, None, None), vec![third.clone(), second.clone(), first.clone()]);
+    assert_eq!(search(&app, all.clone(), MarketSort::EndDate, true, Some(2), None), vec![first.clone()]);
+
+    assert_eq!(search(&app, all.clone(), MarketSort::Volume, true, None, None), vec![first.clone(), second.clone(), third.clone()]);
+    assert_eq!(search(&app, all.clone(), MarketSort::Volume, true, Some(1), None), vec![second.clone(), third.clone()]);
+
+    let category = MarketFilter { category: Some("crypto".to_string()), ..Default::default() };
+    assert_eq!(search(&app, category, MarketSort::EndDate, false, None, None), vec![first.clone(), third.clone()]);
+
+    let deep = MarketFilter { min_liquidity: Some(Uint128::from(1_500_000_000u128)), ..Default::default() };
+    assert_eq!(search(&app, deep, MarketSort::Created, false, None, None), vec![second.clone()]);
+
+    let shallow = MarketFilter { max_liquidity: Some(Uint128::from(1_500_000_000u128)), ..Default::default() };
+    assert_eq!(search(&app, shallow, MarketSort::Created, false, None, None), vec![first.clone(), third.clone()]);
+
+    app.update_block(|block| block.time = block.time.plus_seconds(2 * day));
+
+    resolve_market(&mut app, &factory_address, &first, Outcome::Yes, 1);
+
+    let resolved_to = |outcome: Outcome| MarketFilter { outcome: Some(outcome), ..Default::default() };
+
+    assert_eq!(search(&app, resolved_to(Outcome::Yes), MarketSort::Created, false, None, None), vec![first.clone()]);
+    assert!(search(&app, resolved_to(Outcome::No), MarketSort::Created, false, None, None).is_empty());
+
+    let open = MarketFilter { status: Some(MarketStatus::Open), ..Default::default() };
+    assert_eq!(search(&app, open, MarketSort::Created, false, None, None), vec![second.clone()]);
+
+    // The legacy numeric market type is still accepted
+    let legacy: FactoryQueryMsg = from_json(br#"{"fetch_markets":{"page":"1","items_per_page":"10","account":"user","market_type":"1"}}"#).unwrap();
+    assert_eq!(legacy, FactoryQueryMsg::FetchMarkets {
+        page: 1,
+        items_per_page: 10,
+        account: Addr::unchecked("user"),
+        market_type: MarketType::Active
+    });
+
+    let active: MarketList = app
+    .wrap()
+    .query_wasm_smart(factory_address.clone(), &legacy)
+    .unwrap();
+
+    // Resolving the first market moved the last active market into its slot
+    assert_eq!(active.contracts, vec![Addr::unchecked(second.clone()), Addr::unchecked(third.clone())]);
+    assert!(from_json::<MarketType>(br#""3""#).is_err());
+
+}
+
+#[test]
+fn search_markets_scan_limit_test() {
+
+    let mut app = mock_app();
+
+    let contract_info = initialize_contracts(&mut app);
+
+    let factory_address = contract_info.factory_contract_addr.clone();
+
+    for _ in 0..305 {
+        create_market(&mut app, factory_address.clone());
+    }
+
+    let search = |app: &App, start_after: Option<u128>| -> MarketList {
+        app
+        .wrap()
+        .query_wasm_smart(factory_address.clone(), &FactoryQueryMsg::SearchMarkets {
+            filter: MarketFilter { owner: Some(Addr::unchecked("nobody")), ..Default::default() },
+            sort_by: MarketSort::EndDate,
+            descending: false,
+            start_after,
+            limit: None
+        })
+        .unwrap()
+    };
+
+    // Nothing matches, the scan still stops and hands back a cursor
+    let first_page = search(&app, None);
+    assert!(first_page.contracts.is_empty());
+    assert_eq!(first_page.next_start_after, Some(300));
+
+    let last_page = search(&app, first_page.next_start_after);
+    assert!(last_page.contracts.is_empty());
+    assert_eq!(last_page.next_start_after, None);
+
+}